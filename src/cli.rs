@@ -1,4 +1,6 @@
+use crate::core::output::OutputFormat;
 use clap::Parser;
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "ccline")]
@@ -20,18 +22,148 @@ pub struct Cli {
     #[arg(long = "init")]
     pub init: bool,
 
+    /// With --init, replace an existing config (backed up to .bak first)
+    #[arg(long = "force", requires = "init")]
+    pub force: bool,
+
+    /// Write compact TOML (one line per setting and per segment) with --init or --print
+    #[arg(long = "minify", conflicts_with = "pretty")]
+    pub minify: bool,
+
+    /// Write human-readable TOML with --print (default)
+    #[arg(long = "pretty")]
+    pub pretty: bool,
+
+    /// Emit keys in alphabetical order with --print (stable output for diffing)
+    #[arg(long = "sort-keys", requires = "print")]
+    pub sort_keys: bool,
+
+    /// Put these segments first, in this order (comma-separated names, e.g. `git,model`);
+    /// the others follow in their configured order
+    #[arg(long = "order", value_name = "SEGMENTS", value_delimiter = ',')]
+    pub order: Vec<String>,
+
     /// Check configuration
     #[arg(long = "check")]
     pub check: bool,
 
+    /// Config file path (overrides CCLINE_CONFIG and the default location)
+    #[arg(long = "config-path", global = true)]
+    pub config_path: Option<PathBuf>,
+
+    /// Ignore the config file and render with built-in defaults (--theme still applies)
+    #[arg(long = "no-config", conflicts_with = "config_path")]
+    pub no_config: bool,
+
+    /// Print a JSON Schema of the config file for editor autocompletion
+    #[arg(long = "schema")]
+    pub schema: bool,
+
+    /// Print an example of the JSON ccline expects on stdin
+    #[arg(long = "print-input-schema")]
+    pub print_input_schema: bool,
+
+    /// Write a sample stdin JSON file for trying a config offline (`ccline < FILE`)
+    #[arg(long = "init-example-input", value_name = "FILE")]
+    pub init_example_input: Option<PathBuf>,
+
+    /// Print the theme's color palette as swatches
+    #[arg(long = "color-test")]
+    pub color_test: bool,
+
+    /// Disable colored output
+    #[arg(long = "no-color")]
+    pub no_color: bool,
+
+    /// Print the statusline without a trailing newline
+    #[arg(long = "no-newline")]
+    pub no_newline: bool,
+
     /// Check for updates
     #[arg(short = 'u', long = "update")]
     pub update: bool,
 
+    /// Output format for the statusline
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Keep running and re-render every SECS seconds (default 1)
+    #[arg(long = "watch", value_name = "SECS", num_args = 0..=1, default_missing_value = "1")]
+    pub watch: Option<u64>,
+
+    /// Health check: load config, reach network segments and render; prints a JSON report
+    #[arg(long = "ping")]
+    pub ping: bool,
+
+    /// Time the NewApi cost request N times (default 5) and report latency
+    #[arg(long = "bench-network", value_name = "N", num_args = 0..=1, default_missing_value = "5")]
+    pub bench_network: Option<u32>,
+
+    /// Show which config file is used and the locations searched for it
+    #[arg(long = "doctor")]
+    pub doctor: bool,
+
+    /// Show the cache directory and its cache files
+    #[arg(long = "cache-status", conflicts_with = "cache_clear")]
+    pub cache_status: bool,
+
+    /// Delete the cache files in the cache directory
+    #[arg(long = "cache-clear")]
+    pub cache_clear: bool,
+
+    /// Record the current NewApi cost as a mark to show spend since (see show_since_checkpoint)
+    #[arg(long = "checkpoint-cost", conflicts_with = "clear_checkpoint")]
+    pub checkpoint_cost: bool,
+
+    /// Remove the mark set by --checkpoint-cost
+    #[arg(long = "clear-checkpoint")]
+    pub clear_checkpoint: bool,
+
+    /// Run only this segment (e.g. new_api_cost) and print its data as JSON
+    #[arg(long = "segment-json", value_name = "ID")]
+    pub segment_json: Option<String>,
+
+    /// Run only this segment and explain what it did: config source, missing options, result
+    #[arg(
+        long = "validate-segment",
+        value_name = "ID",
+        conflicts_with = "segment_json"
+    )]
+    pub validate_segment: Option<String>,
+
+    /// Write the statusline to this file (atomically) instead of stdout
+    #[arg(long = "render-to")]
+    pub render_to: Option<PathBuf>,
+
+    /// Save the input, effective config, segment data and output of this render to DIR
+    #[arg(long = "record", value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Re-render a --record capture, using its recorded network segment data
+    #[arg(long = "replay", value_name = "DIR")]
+    pub replay: Option<PathBuf>,
+
+    /// Report errors on stderr as single-line JSON objects with a stable `code`
+    #[arg(long = "json-errors")]
+    pub json_errors: bool,
+
+    /// Append log lines to FILE instead of stderr (at debug level unless CCLINE_LOG is set)
+    #[arg(long = "log-file", value_name = "FILE")]
+    pub log_file: Option<PathBuf>,
+
+    /// Require stdin to be exactly one JSON object (no NDJSON or trailing data)
+    #[arg(long = "strict-input")]
+    pub strict_input: bool,
+
     /// Patch Claude Code cli.js to disable context warnings
     #[arg(long = "patch")]
     pub patch: Option<String>,
 
+    /// Render from the --init-example-input sample instead of stdin; with
+    /// --patch, report what would change without writing or backing up
+    #[arg(long = "dry-run")]
+    pub dry_run: bool,
+
     /// NewApi Cost: Base URL for API
     #[arg(long = "newapi-base-url")]
     pub newapi_base_url: Option<String>,
@@ -1,6 +1,8 @@
-use super::types::Config;
+use super::types::{AnsiColor, Config, SegmentConfig, SegmentId};
+use serde::Deserialize;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 pub struct ConfigLoader;
 
@@ -102,39 +104,333 @@ impl ConfigLoader {
     }
 }
 
+/// Contents of a file referenced from `include`
+#[derive(Debug, Default, Deserialize)]
+struct IncludeFile {
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    segments: Vec<SegmentConfig>,
+}
+
+/// A segment option key that was renamed: (segment, legacy key, canonical key)
+type OptionRename = (SegmentId, &'static str, &'static str);
+
+/// Option keys renamed after a release; add an entry when renaming a shipped option
+const LEGACY_OPTION_KEYS: &[OptionRename] = &[];
+
+/// Largest NewApi log `type` accepted for the `stat_type` option
+const MAX_STAT_TYPE: u64 = 99;
+
+/// Config file location set by the `--config-path` flag
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
 impl Config {
-    /// Load configuration from default location
+    /// Override the config file location for all config operations in this process
+    pub fn set_config_path<P: Into<PathBuf>>(path: P) {
+        let _ = CONFIG_PATH_OVERRIDE.set(path.into());
+    }
+
+    /// Load configuration from the resolved config path
     pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
+        Self::load_from(Self::get_config_path())
+    }
+
+    /// Load configuration from a specific path, falling back to defaults if it doesn't exist
+    pub fn load_from<P: AsRef<Path>>(config_path: P) -> Result<Config, Box<dyn std::error::Error>> {
         // Ensure themes directory exists and has built-in themes
         ConfigLoader::ensure_themes_exist();
 
-        let config_path = Self::get_config_path();
+        let config_path = config_path.as_ref();
 
         if !config_path.exists() {
             return Ok(Config::default());
         }
 
         let content = fs::read_to_string(config_path)?;
-        let config: Config = toml::from_str(&content)?;
+        let mut config: Config = toml::from_str(&content)?;
+        config.resolve_includes(config_path)?;
+        config.migrate_options();
         Ok(config)
     }
 
-    /// Save configuration to default location
+    /// Rename legacy segment option keys to their canonical names, so older
+    /// configs keep working; a canonical key already present wins
+    /// Returns `(segment, legacy key, canonical key)` for every rename
+    pub fn migrate_options(&mut self) -> Vec<(SegmentId, String, String)> {
+        self.rename_options(LEGACY_OPTION_KEYS)
+    }
+
+    fn rename_options(&mut self, renames: &[OptionRename]) -> Vec<(SegmentId, String, String)> {
+        let mut renamed = Vec::new();
+        for segment in &mut self.segments {
+            for (id, legacy, canonical) in renames {
+                if segment.id != *id {
+                    continue;
+                }
+                let Some(value) = segment.options.remove(*legacy) else {
+                    continue;
+                };
+                crate::log_warn!(
+                    "{:?}: option `{}` is deprecated, use `{}`",
+                    segment.id,
+                    legacy,
+                    canonical
+                );
+                segment
+                    .options
+                    .entry(canonical.to_string())
+                    .or_insert(value);
+                renamed.push((segment.id, legacy.to_string(), canonical.to_string()));
+            }
+        }
+        renamed
+    }
+
+    /// Merge segment definitions from `include` files into this config
+    /// Included segments come first; a segment defined in the including file
+    /// takes the place of an included one with the same ID. Duplicates within
+    /// one file are kept as written, for `check()` to report
+    pub fn resolve_includes(
+        &mut self,
+        config_path: &Path,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.include.is_empty() {
+            return Ok(());
+        }
+
+        let mut stack = vec![Self::canonical_path(config_path)];
+        let mut included = Vec::new();
+        let base_dir = config_path.parent().unwrap_or_else(|| Path::new(""));
+        for include in &self.include {
+            let include_path = base_dir.join(include);
+            Self::collect_included_segments(&include_path, &mut stack, &mut included)?;
+        }
+
+        let own_segments = std::mem::take(&mut self.segments);
+        Self::merge_segments(&mut included, own_segments);
+        self.segments = included;
+
+        Ok(())
+    }
+
+    /// Layer one file's segments over those of the files read before it:
+    /// the first definition of an id replaces the earlier files' one in place,
+    /// anything else (including repeats within the file) is appended
+    fn merge_segments(segments: &mut Vec<SegmentConfig>, layer: Vec<SegmentConfig>) {
+        let earlier = segments.len();
+        let mut seen = std::collections::HashSet::new();
+        for segment in layer {
+            let key = segment.key();
+            let slot = if seen.insert(key.clone()) {
+                segments[..earlier].iter().position(|s| s.key() == key)
+            } else {
+                None
+            };
+            match slot {
+                Some(index) => segments[index] = segment,
+                None => segments.push(segment),
+            }
+        }
+    }
+
+    /// Read an include file and its nested includes, depth first
+    /// `stack` holds the chain of files currently being read to detect cycles
+    fn collect_included_segments(
+        path: &Path,
+        stack: &mut Vec<PathBuf>,
+        segments: &mut Vec<SegmentConfig>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let canonical = Self::canonical_path(path);
+        if stack.contains(&canonical) {
+            let chain: Vec<String> = stack
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(format!("Cyclic config include: {}", chain.join(" -> ")).into());
+        }
+
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read include {}: {}", path.display(), e))?;
+        let include_file: IncludeFile = toml::from_str(&content)
+            .map_err(|e| format!("Failed to parse include {}: {}", path.display(), e))?;
+
+        stack.push(canonical);
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for include in &include_file.include {
+            Self::collect_included_segments(&base_dir.join(include), stack, segments)?;
+        }
+        stack.pop();
+
+        Self::merge_segments(segments, include_file.segments);
+        Ok(())
+    }
+
+    /// Canonical form of a path for identity comparison, falling back to the path itself
+    fn canonical_path(path: &Path) -> PathBuf {
+        fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    /// Save configuration to the resolved config path
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let config_path = Self::get_config_path();
+        self.save_to(Self::get_config_path())
+    }
+
+    /// Save configuration to a specific path
+    pub fn save_to<P: AsRef<Path>>(
+        &self,
+        config_path: P,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_to_with(config_path, false)
+    }
+
+    /// Save configuration to a specific path, optionally as compact TOML
+    pub fn save_to_with<P: AsRef<Path>>(
+        &self,
+        config_path: P,
+        minify: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let config_path = config_path.as_ref();
 
         // Ensure config directory exists
         if let Some(parent) = config_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        let content = toml::to_string_pretty(self)?;
+        let content = self.to_toml(minify)?;
         fs::write(config_path, content)?;
         Ok(())
     }
 
-    /// Get the default config file path (~/.claude/ccline/config.toml)
-    fn get_config_path() -> PathBuf {
+    /// Serialize to TOML, either pretty (multi-line arrays) or compact
+    pub fn to_toml(&self, minify: bool) -> Result<String, toml::ser::Error> {
+        if minify {
+            self.to_compact_toml()
+        } else {
+            toml::to_string_pretty(self)
+        }
+    }
+
+    /// Compact TOML: every top-level value inline on one line, except that
+    /// each segment of an array of tables gets a line of its own
+    /// Keys come out sorted, since `toml::Table` is a BTreeMap
+    fn to_compact_toml(&self) -> Result<String, toml::ser::Error> {
+        let value = toml::Value::try_from(self)?;
+        let mut content = String::new();
+        for (key, value) in value.as_table().into_iter().flatten() {
+            match value {
+                toml::Value::Array(items) if items.iter().any(toml::Value::is_table) => {
+                    content.push_str(&format!("{} = [\n", key));
+                    for item in items {
+                        content.push_str(&format!("{},\n", item));
+                    }
+                    content.push_str("]\n");
+                }
+                _ => content.push_str(&format!("{} = {}\n", key, value)),
+            }
+        }
+        Ok(content)
+    }
+
+    /// Serialize to TOML with every table's keys in alphabetical order,
+    /// so equivalent configs always produce identical text
+    pub fn to_sorted_toml(&self, minify: bool) -> Result<String, toml::ser::Error> {
+        // toml::Table is a BTreeMap, so the round trip sorts keys at every level
+        if minify {
+            return self.to_compact_toml();
+        }
+        let value = toml::Value::try_from(self)?;
+        toml::to_string_pretty(&value)
+    }
+
+    /// Resolve the config file path
+    /// `--config-path` wins outright, then `CCLINE_CONFIG` whether or not the file
+    /// exists yet. Otherwise the first existing file from
+    /// [`Config::config_search_paths`] is used, or the default location so
+    /// `--init` writes there
+    pub fn get_config_path() -> PathBuf {
+        if let Some(path) = CONFIG_PATH_OVERRIDE.get() {
+            return path.clone();
+        }
+
+        if let Some(path) = Self::env_config_path() {
+            return path;
+        }
+
+        Self::config_search_paths()
+            .into_iter()
+            .find(|path| path.is_file())
+            .unwrap_or_else(Self::default_config_path)
+    }
+
+    /// Which config file is used and what picked it, followed by every search
+    /// location and whether it exists (`--doctor`)
+    pub fn config_report() -> String {
+        let path = Self::get_config_path();
+        let source = if CONFIG_PATH_OVERRIDE.get().is_some() {
+            "--config-path"
+        } else if Self::env_config_path().is_some() {
+            "CCLINE_CONFIG"
+        } else if path.is_file() {
+            "search path"
+        } else {
+            "default location"
+        };
+        let missing = if path.is_file() {
+            ""
+        } else {
+            ", not created yet"
+        };
+
+        let mut lines = vec![
+            format!(
+                "Using config: {} ({}{})",
+                Self::display_path(&path),
+                source,
+                missing
+            ),
+            "Search order:".to_string(),
+        ];
+        for candidate in Self::config_search_paths() {
+            let state = if candidate == path {
+                "used"
+            } else if candidate.is_file() {
+                "found"
+            } else {
+                "missing"
+            };
+            lines.push(format!("  {} ({})", candidate.display(), state));
+        }
+        lines.join("\n")
+    }
+
+    /// Locations searched when `CCLINE_CONFIG` is unset, in priority order:
+    /// `./.ccline.toml`, `$XDG_CONFIG_HOME/ccometixline/config.toml`,
+    /// ~/.claude/ccline/config.toml
+    pub fn config_search_paths() -> Vec<PathBuf> {
+        let mut paths = vec![PathBuf::from(".ccline.toml")];
+
+        let xdg_config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".config")));
+        if let Some(dir) = xdg_config_home {
+            paths.push(dir.join("ccometixline").join("config.toml"));
+        }
+
+        paths.push(Self::default_config_path());
+        paths
+    }
+
+    fn env_config_path() -> Option<PathBuf> {
+        std::env::var("CCLINE_CONFIG")
+            .ok()
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }
+
+    fn default_config_path() -> PathBuf {
         if let Some(home) = dirs::home_dir() {
             home.join(".claude").join("ccline").join("config.toml")
         } else {
@@ -142,30 +438,64 @@ impl Config {
         }
     }
 
-    /// Initialize config directory and create default config
+    /// Initialize config directory and create default config at the resolved path
     pub fn init() -> Result<(), Box<dyn std::error::Error>> {
-        let config_path = Self::get_config_path();
+        Self::init_at(Self::get_config_path(), false, false)
+    }
+
+    /// Initialize config directory and create default config at a specific path
+    /// An existing config is kept unless `force` is set, in which case it is
+    /// backed up to `<name>.bak` before the default is written
+    pub fn init_at<P: AsRef<Path>>(
+        config_path: P,
+        minify: bool,
+        force: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let config_path = config_path.as_ref();
 
         // Create directory
         if let Some(parent) = config_path.parent() {
-            fs::create_dir_all(parent)?;
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
         }
 
         // Initialize themes directory and built-in themes
         ConfigLoader::init_themes()?;
 
-        // Create default config if it doesn't exist
         if !config_path.exists() {
-            let default_config = Config::default();
-            default_config.save()?;
-            println!("Created config at {}", config_path.display());
+            Config::default().save_to_with(config_path, minify)?;
+            println!("Created config at {}", Self::display_path(config_path));
+        } else if force {
+            let mut backup_path = config_path.as_os_str().to_owned();
+            backup_path.push(".bak");
+            let backup_path = PathBuf::from(backup_path);
+            fs::copy(config_path, &backup_path)?;
+
+            Config::default().save_to_with(config_path, minify)?;
+            println!(
+                "Reset config at {} (previous config saved to {})",
+                Self::display_path(config_path),
+                Self::display_path(&backup_path)
+            );
         } else {
-            println!("Config already exists at {}", config_path.display());
+            println!(
+                "Config already exists at {}, use --init --force to replace it (the old one is kept as .bak)",
+                Self::display_path(config_path)
+            );
         }
 
         Ok(())
     }
 
+    /// Absolute form of a path for user-facing messages
+    pub fn display_path(path: &Path) -> String {
+        fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .display()
+            .to_string()
+    }
+
     /// Validate configuration
     pub fn check(&self) -> Result<(), Box<dyn std::error::Error>> {
         // Basic validation
@@ -173,21 +503,748 @@ impl Config {
             return Err("No segments configured".into());
         }
 
-        // Validate segment IDs are unique
-        let mut seen_ids = std::collections::HashSet::new();
+        // Validate segment IDs are unique; each id (or custom segment name)
+        // may only be configured once
+        let mut seen_ids = std::collections::HashMap::new();
+        for (index, segment) in self.segments.iter().enumerate() {
+            if segment.id == SegmentId::Custom && segment.name.is_none() {
+                return Err(format!(
+                    "Custom segment ([[segments]] entry {}) needs a name",
+                    index + 1
+                )
+                .into());
+            }
+            let name = segment.key();
+            if let Some(first) = seen_ids.insert(name.clone(), index) {
+                return Err(format!(
+                    "Duplicate segment id \"{}\" ([[segments]] entries {} and {}); remove one of them",
+                    name,
+                    first + 1,
+                    index + 1
+                )
+                .into());
+            }
+        }
+
+        if self.empty_placeholder.is_some() && self.quiet_on_empty == Some(true) {
+            return Err(
+                "empty_placeholder and quiet_on_empty are mutually exclusive; remove one of them"
+                    .into(),
+            );
+        }
+
+        // 16-color indices above 15 have no ANSI code
+        let separator_colors = [
+            ("separator_fg", &self.style.separator_fg),
+            ("separator_bg", &self.style.separator_bg),
+        ];
+        for (name, color) in separator_colors {
+            if let Some(AnsiColor::Color16 { c16 }) = color {
+                if *c16 > 15 {
+                    return Err(
+                        format!("Invalid style.{}: c16 = {} (expected 0-15)", name, c16).into(),
+                    );
+                }
+            }
+        }
+
+        // Validate the NewApi cost billing day boundary and stat type
         for segment in &self.segments {
-            if !seen_ids.insert(segment.id) {
-                return Err(format!("Duplicate segment ID: {:?}", segment.id).into());
+            if let Some(value) = segment.options.get("day_start_hour") {
+                if !value.as_u64().map(|hour| hour < 24).unwrap_or(false) {
+                    return Err(format!(
+                        "Invalid day_start_hour for {:?}: {} (expected 0-23)",
+                        segment.id, value
+                    )
+                    .into());
+                }
+            }
+            if let Some(value) = segment.options.get("stat_type") {
+                if !value.as_u64().map(|t| t <= MAX_STAT_TYPE).unwrap_or(false) {
+                    return Err(format!(
+                        "Invalid stat_type for {:?}: {} (expected 0-{})",
+                        segment.id, value, MAX_STAT_TYPE
+                    )
+                    .into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Problems that don't make the config invalid but likely leave a segment
+    /// blank, such as an enabled network segment without its credentials
+    pub fn warnings(&self) -> Vec<String> {
+        self.segments
+            .iter()
+            .filter(|segment| segment.enabled)
+            .filter_map(|segment| {
+                let name = serde_json::to_value(segment.id)
+                    .ok()
+                    .and_then(|v| v.as_str().map(str::to_string))
+                    .unwrap_or_else(|| format!("{:?}", segment.id));
+                let missing: Vec<&str> = super::schema::required_options(&name)
+                    .iter()
+                    .copied()
+                    .filter(|option| {
+                        !segment
+                            .options
+                            .get(*option)
+                            .is_some_and(super::schema::is_option_set)
+                    })
+                    .collect();
+                (!missing.is_empty()).then(|| {
+                    format!(
+                        "Segment \"{}\" is enabled but missing {}; it will stay blank",
+                        name,
+                        missing.join(", ")
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Move the named segments to the front, in the given order; the others
+    /// keep their relative order after them. Names are segment keys (`git`,
+    /// or a custom segment's name); an unknown or repeated name is an error
+    pub fn reorder_segments<S: AsRef<str>>(&mut self, order: &[S]) -> Result<(), String> {
+        let order: Vec<&str> = order.iter().map(|name| name.as_ref().trim()).collect();
+        for (index, name) in order.iter().enumerate() {
+            if order[..index].contains(name) {
+                return Err(format!("Segment \"{}\" listed twice in --order", name));
+            }
+            if !self.segments.iter().any(|s| s.key() == *name) {
+                return Err(format!("Unknown segment \"{}\" in --order", name));
             }
         }
 
+        // Stable sort: unlisted segments share the last rank and keep their order
+        self.segments.sort_by_key(|segment| {
+            let key = segment.key();
+            order
+                .iter()
+                .position(|name| *name == key)
+                .unwrap_or(order.len())
+        });
         Ok(())
     }
 
-    /// Print configuration as TOML
-    pub fn print(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let content = toml::to_string_pretty(self)?;
+    /// Print configuration as TOML, optionally with sorted keys
+    pub fn print(&self, minify: bool, sort_keys: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let content = if sort_keys {
+            self.to_sorted_toml(minify)?
+        } else {
+            self.to_toml(minify)?
+        };
         println!("{}", content);
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{EnvGuard, TempDir};
+
+    /// Point HOME at a temp dir so theme files land there instead of the real home
+    fn isolated_home(env: &mut EnvGuard) -> TempDir {
+        let home = TempDir::new("home");
+        env.set("HOME", home.path());
+        home
+    }
+
+    #[test]
+    fn init_writes_to_given_path_and_load_reads_it_back() {
+        let mut env = EnvGuard::new();
+        let _home = isolated_home(&mut env);
+        let dir = TempDir::new("config");
+        let path = dir.join("nested/custom.toml");
+
+        Config::init_at(&path, false, false).unwrap();
+        assert!(path.is_file());
+
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.segments.len(), Config::default().segments.len());
+        config.check().unwrap();
+    }
+
+    #[test]
+    fn load_from_missing_path_gives_defaults() {
+        let mut env = EnvGuard::new();
+        let _home = isolated_home(&mut env);
+        let dir = TempDir::new("config");
+
+        let config = Config::load_from(dir.join("absent.toml")).unwrap();
+        assert_eq!(config.theme, Config::default().theme);
+    }
+
+    #[test]
+    fn save_to_round_trips_changes() {
+        let mut env = EnvGuard::new();
+        let _home = isolated_home(&mut env);
+        let dir = TempDir::new("config");
+        let path = dir.join("config.toml");
+
+        let config = Config {
+            theme: "nord".to_string(),
+            ..Config::default()
+        };
+        config.save_to(&path).unwrap();
+
+        assert_eq!(Config::load_from(&path).unwrap().theme, "nord");
+    }
+
+    /// Isolated HOME, XDG_CONFIG_HOME and working directory with the given
+    /// candidate config files created: "cwd", "xdg" and/or "default"
+    fn precedence_fixture(env: &mut EnvGuard, present: &[&str]) -> (TempDir, [PathBuf; 3]) {
+        let root = TempDir::new("precedence");
+        env.set("HOME", root.join("home"))
+            .set("XDG_CONFIG_HOME", root.join("xdg"))
+            .remove("CCLINE_CONFIG");
+        fs::create_dir_all(root.join("cwd")).unwrap();
+        env.set_current_dir(&root.join("cwd"));
+
+        let paths = [
+            root.join("cwd/.ccline.toml"),
+            root.join("xdg/ccometixline/config.toml"),
+            root.join("home/.claude/ccline/config.toml"),
+        ];
+        for (name, path) in ["cwd", "xdg", "default"].iter().zip(&paths) {
+            if present.contains(name) {
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, "").unwrap();
+            }
+        }
+        (root, paths)
+    }
+
+    #[test]
+    fn ccline_config_env_wins_whether_or_not_it_exists() {
+        let mut env = EnvGuard::new();
+        let (root, _) = precedence_fixture(&mut env, &["cwd", "xdg", "default"]);
+        let path = root.join("env.toml");
+        env.set("CCLINE_CONFIG", &path);
+
+        assert_eq!(Config::get_config_path(), path);
+        fs::write(&path, "").unwrap();
+        assert_eq!(Config::get_config_path(), path);
+    }
+
+    #[test]
+    fn empty_ccline_config_counts_as_unset() {
+        let mut env = EnvGuard::new();
+        let (_root, _) = precedence_fixture(&mut env, &["cwd"]);
+        env.set("CCLINE_CONFIG", "");
+
+        assert_eq!(Config::get_config_path(), PathBuf::from(".ccline.toml"));
+    }
+
+    #[test]
+    fn search_paths_are_tried_in_order_when_env_is_unset() {
+        let cases: &[(&[&str], usize)] = &[
+            (&["cwd", "xdg", "default"], 0),
+            (&["xdg", "default"], 1),
+            (&["default"], 2),
+            // Nothing exists: the default location, so --init writes there
+            (&[], 2),
+        ];
+        for (present, expected) in cases {
+            let mut env = EnvGuard::new();
+            let (_root, paths) = precedence_fixture(&mut env, present);
+            let expected = if *expected == 0 {
+                PathBuf::from(".ccline.toml")
+            } else {
+                paths[*expected].clone()
+            };
+            assert_eq!(Config::get_config_path(), expected, "{present:?}");
+        }
+    }
+
+    #[test]
+    fn xdg_falls_back_to_dot_config() {
+        let mut env = EnvGuard::new();
+        let (root, _) = precedence_fixture(&mut env, &["default"]);
+        env.remove("XDG_CONFIG_HOME");
+        let path = root.join("home/.config/ccometixline/config.toml");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "").unwrap();
+
+        assert_eq!(Config::get_config_path(), path);
+    }
+
+    /// Write a config file with the given includes and segments, each segment
+    /// tagged through its plain icon so tests can tell where it came from
+    fn write_config(
+        dir: &TempDir,
+        name: &str,
+        include: &[&str],
+        segments: &[(SegmentId, &str)],
+    ) -> PathBuf {
+        let config = Config {
+            include: include.iter().map(|i| i.to_string()).collect(),
+            segments: segments
+                .iter()
+                .map(|(id, tag)| {
+                    let mut segment = crate::test_support::segment_config(*id);
+                    segment.icon.plain = tag.to_string();
+                    segment
+                })
+                .collect(),
+            ..Config::default()
+        };
+        dir.write(name, &toml::to_string(&config).unwrap())
+    }
+
+    fn tags(config: &Config) -> Vec<(SegmentId, &str)> {
+        config
+            .segments
+            .iter()
+            .map(|s| (s.id, s.icon.plain.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn includes_merge_across_files_in_place() {
+        let mut env = EnvGuard::new();
+        let _home = isolated_home(&mut env);
+        let dir = TempDir::new("config");
+        write_config(
+            &dir,
+            "a.toml",
+            &[],
+            &[(SegmentId::Model, "a"), (SegmentId::Git, "a")],
+        );
+        write_config(&dir, "b.toml", &[], &[(SegmentId::Git, "b")]);
+        let main = write_config(
+            &dir,
+            "config.toml",
+            &["a.toml", "b.toml"],
+            &[(SegmentId::Model, "main"), (SegmentId::Usage, "main")],
+        );
+
+        let config = Config::load_from(&main).unwrap();
+        assert_eq!(
+            tags(&config),
+            vec![
+                (SegmentId::Model, "main"),
+                (SegmentId::Git, "b"),
+                (SegmentId::Usage, "main"),
+            ]
+        );
+        config.check().unwrap();
+    }
+
+    #[test]
+    fn duplicates_in_the_including_file_are_kept_for_check() {
+        let mut env = EnvGuard::new();
+        let _home = isolated_home(&mut env);
+        let dir = TempDir::new("config");
+        write_config(&dir, "a.toml", &[], &[(SegmentId::Git, "a")]);
+        let main = write_config(
+            &dir,
+            "config.toml",
+            &["a.toml"],
+            &[(SegmentId::Git, "first"), (SegmentId::Git, "second")],
+        );
+
+        let config = Config::load_from(&main).unwrap();
+        assert_eq!(
+            tags(&config),
+            vec![(SegmentId::Git, "first"), (SegmentId::Git, "second")]
+        );
+        let err = config.check().unwrap_err().to_string();
+        assert!(err.contains("Duplicate segment id \"git\""), "{err}");
+    }
+
+    #[test]
+    fn cyclic_include_is_an_error() {
+        let mut env = EnvGuard::new();
+        let _home = isolated_home(&mut env);
+        let dir = TempDir::new("config");
+        write_config(&dir, "a.toml", &["b.toml"], &[]);
+        write_config(&dir, "b.toml", &["a.toml"], &[]);
+        let main = write_config(&dir, "config.toml", &["a.toml"], &[]);
+
+        let err = Config::load_from(&main).unwrap_err().to_string();
+        assert!(err.contains("Cyclic config include"), "{err}");
+    }
+
+    #[test]
+    fn minified_default_config_puts_each_segment_on_one_line() {
+        let config = Config::default();
+        let minified = config.to_toml(true).unwrap();
+        let pretty = config.to_toml(false).unwrap();
+
+        let segment_lines = minified.lines().filter(|line| line.starts_with('{'));
+        assert_eq!(segment_lines.count(), config.segments.len());
+        assert!(!minified.lines().any(|line| line.trim().is_empty()));
+        assert!(
+            minified.lines().count() <= config.segments.len() + 10,
+            "{}",
+            minified
+        );
+        assert!(minified.len() < pretty.len());
+
+        let from_minified: Config = toml::from_str(&minified).unwrap();
+        assert_eq!(
+            serde_json::to_value(&from_minified).unwrap(),
+            serde_json::to_value(&config).unwrap()
+        );
+    }
+
+    #[test]
+    fn minified_and_pretty_toml_load_the_same_config() {
+        let config = Config {
+            theme: "nord".to_string(),
+            include: vec!["a.toml".to_string(), "b.toml".to_string()],
+            ..Config::default()
+        };
+
+        let minified = config.to_toml(true).unwrap();
+        let pretty = config.to_toml(false).unwrap();
+        assert!(minified.lines().count() < pretty.lines().count());
+
+        let from_minified: Config = toml::from_str(&minified).unwrap();
+        let from_pretty: Config = toml::from_str(&pretty).unwrap();
+        assert_eq!(
+            serde_json::to_value(&from_minified).unwrap(),
+            serde_json::to_value(&from_pretty).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&from_pretty).unwrap(),
+            serde_json::to_value(&config).unwrap()
+        );
+    }
+
+    fn keys(config: &Config) -> Vec<String> {
+        config.segments.iter().map(|s| s.key()).collect()
+    }
+
+    #[test]
+    fn reorder_moves_listed_segments_first() {
+        let mut config = Config::default();
+        let before = keys(&config);
+        config.reorder_segments(&["git", "model"]).unwrap();
+
+        let after = keys(&config);
+        assert_eq!(&after[..2], ["git", "model"]);
+        let rest: Vec<_> = before
+            .iter()
+            .filter(|key| *key != "git" && *key != "model")
+            .cloned()
+            .collect();
+        assert_eq!(after[2..], rest[..]);
+
+        // An empty order changes nothing
+        config.reorder_segments::<&str>(&[]).unwrap();
+        assert_eq!(keys(&config), after);
+    }
+
+    #[test]
+    fn reorder_rejects_unknown_and_repeated_names() {
+        let mut config = Config::default();
+        let before = keys(&config);
+
+        let err = config.reorder_segments(&["weather"]).unwrap_err();
+        assert!(err.contains("Unknown segment \"weather\""), "{err}");
+        let err = config.reorder_segments(&["git", "git"]).unwrap_err();
+        assert!(err.contains("listed twice"), "{err}");
+        assert_eq!(keys(&config), before);
+    }
+
+    /// Default config with `options` added to the first segment in the given order
+    fn config_with_options(options: &[(&str, serde_json::Value)]) -> Config {
+        let mut config = Config::default();
+        let segment = &mut config.segments[0];
+        segment.options.clear();
+        for (key, value) in options {
+            segment.options.insert(key.to_string(), value.clone());
+        }
+        config
+    }
+
+    #[test]
+    fn sorted_print_ignores_in_memory_key_order() {
+        let a = config_with_options(&[
+            ("zeta", serde_json::json!(1)),
+            ("alpha", serde_json::json!({"y": true, "b": false})),
+            ("mid", serde_json::json!("x")),
+        ]);
+        let b = config_with_options(&[
+            ("mid", serde_json::json!("x")),
+            ("alpha", serde_json::json!({"b": false, "y": true})),
+            ("zeta", serde_json::json!(1)),
+        ]);
+
+        for minify in [false, true] {
+            assert_eq!(
+                a.to_sorted_toml(minify).unwrap(),
+                b.to_sorted_toml(minify).unwrap()
+            );
+        }
+
+        let text = a.to_sorted_toml(false).unwrap();
+        let position = |key: &str| text.find(key).unwrap();
+        assert!(position("mid = ") < position("zeta = "));
+        assert!(position("b = false") < position("y = true"));
+        // Sorting doesn't change what the file means
+        let reparsed: Config = toml::from_str(&text).unwrap();
+        assert_eq!(reparsed.to_sorted_toml(false).unwrap(), text);
+    }
+
+    #[test]
+    fn init_keeps_an_existing_config_without_force() {
+        let mut env = EnvGuard::new();
+        let _home = isolated_home(&mut env);
+        let dir = TempDir::new("config");
+        let path = dir.write("config.toml", "# mine\n");
+
+        Config::init_at(&path, false, false).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# mine\n");
+        assert!(!dir.join("config.toml.bak").exists());
+    }
+
+    #[test]
+    fn init_force_backs_up_and_overwrites() {
+        let mut env = EnvGuard::new();
+        let _home = isolated_home(&mut env);
+        let dir = TempDir::new("config");
+        let path = dir.write("config.toml", "# mine\n");
+
+        Config::init_at(&path, false, true).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(dir.join("config.toml.bak")).unwrap(),
+            "# mine\n"
+        );
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.segments.len(), Config::default().segments.len());
+    }
+
+    #[test]
+    fn check_reports_a_duplicate_segment_id() {
+        let mut config = Config::default();
+        let model = config
+            .segments
+            .iter()
+            .position(|s| s.id == SegmentId::Model)
+            .unwrap();
+        config.segments.push(config.segments[model].clone());
+
+        let err = config.check().unwrap_err().to_string();
+        assert_eq!(
+            err,
+            format!(
+                "Duplicate segment id \"model\" ([[segments]] entries {} and {}); remove one of them",
+                model + 1,
+                config.segments.len()
+            )
+        );
+    }
+
+    #[test]
+    fn custom_segments_are_told_apart_by_name() {
+        let custom = |name: Option<&str>| {
+            let mut segment = crate::test_support::segment_config(SegmentId::Custom);
+            segment.name = name.map(str::to_string);
+            segment
+        };
+        let mut config = Config::default();
+        config.segments.push(custom(Some("weather")));
+        config.segments.push(custom(Some("uptime")));
+        config.check().unwrap();
+
+        config.segments.push(custom(Some("weather")));
+        let err = config.check().unwrap_err().to_string();
+        assert!(err.contains("Duplicate segment id \"weather\""), "{err}");
+
+        config.segments.pop();
+        config.segments.push(custom(None));
+        let err = config.check().unwrap_err().to_string();
+        assert!(err.contains("needs a name"), "{err}");
+    }
+
+    #[test]
+    fn check_accepts_only_small_integer_stat_types() {
+        let with_stat_type = |value: serde_json::Value| {
+            let mut config = Config::default();
+            let segment = config
+                .segments
+                .iter_mut()
+                .find(|s| s.id == SegmentId::NewApiCost)
+                .unwrap();
+            segment.options.insert("stat_type".to_string(), value);
+            config.check()
+        };
+
+        assert!(with_stat_type(serde_json::json!(5)).is_ok());
+        assert!(with_stat_type(serde_json::json!(MAX_STAT_TYPE)).is_ok());
+        for invalid in [
+            serde_json::json!(MAX_STAT_TYPE + 1),
+            serde_json::json!(-1),
+            serde_json::json!(2.5),
+            serde_json::json!("2"),
+        ] {
+            let err = with_stat_type(invalid.clone()).unwrap_err().to_string();
+            assert!(err.contains("Invalid stat_type"), "{invalid}: {err}");
+        }
+    }
+
+    const TEST_RENAMES: &[OptionRename] = &[
+        (SegmentId::NewApiCost, "old_divisor", "quota_per_unit"),
+        (SegmentId::NewApiCost, "older_divisor", "quota_per_unit"),
+        (SegmentId::Git, "old_flag", "show_sha"),
+    ];
+
+    fn newapi_options(
+        config: &mut Config,
+    ) -> &mut std::collections::HashMap<String, serde_json::Value> {
+        &mut config
+            .segments
+            .iter_mut()
+            .find(|s| s.id == SegmentId::NewApiCost)
+            .unwrap()
+            .options
+    }
+
+    #[test]
+    fn legacy_option_keys_are_renamed() {
+        let mut config = Config::default();
+        let options = newapi_options(&mut config);
+        options.remove("quota_per_unit");
+        options.insert("old_divisor".to_string(), serde_json::json!(1000));
+
+        let renamed = config.rename_options(TEST_RENAMES);
+
+        assert_eq!(
+            renamed,
+            vec![(
+                SegmentId::NewApiCost,
+                "old_divisor".to_string(),
+                "quota_per_unit".to_string()
+            )]
+        );
+        let options = newapi_options(&mut config);
+        assert_eq!(options["quota_per_unit"], serde_json::json!(1000));
+        assert!(!options.contains_key("old_divisor"));
+    }
+
+    #[test]
+    fn canonical_option_key_wins_over_a_legacy_one() {
+        let mut config = Config::default();
+        let options = newapi_options(&mut config);
+        options.insert("quota_per_unit".to_string(), serde_json::json!(500000));
+        options.insert("older_divisor".to_string(), serde_json::json!(1));
+
+        config.rename_options(TEST_RENAMES);
+
+        let options = newapi_options(&mut config);
+        assert_eq!(options["quota_per_unit"], serde_json::json!(500000));
+        assert!(!options.contains_key("older_divisor"));
+    }
+
+    #[test]
+    fn renames_only_apply_to_their_segment() {
+        let mut config = Config::default();
+        newapi_options(&mut config).insert("old_flag".to_string(), serde_json::json!(true));
+
+        assert!(config.rename_options(TEST_RENAMES).is_empty());
+        assert!(newapi_options(&mut config).contains_key("old_flag"));
+    }
+
+    #[test]
+    fn check_rejects_placeholder_with_quiet_on_empty() {
+        let mut config = Config {
+            empty_placeholder: Some("—".to_string()),
+            ..Config::default()
+        };
+        config.check().unwrap();
+
+        config.quiet_on_empty = Some(false);
+        config.check().unwrap();
+
+        config.quiet_on_empty = Some(true);
+        let err = config.check().unwrap_err().to_string();
+        assert!(err.contains("mutually exclusive"), "{err}");
+
+        config.empty_placeholder = None;
+        config.check().unwrap();
+    }
+
+    #[test]
+    fn check_rejects_out_of_range_separator_colors() {
+        let mut config = Config::default();
+        config.style.separator_fg = Some(AnsiColor::Color16 { c16: 15 });
+        config.style.separator_bg = Some(AnsiColor::Color256 { c256: 200 });
+        config.check().unwrap();
+
+        config.style.separator_bg = Some(AnsiColor::Color16 { c16: 16 });
+        let err = config.check().unwrap_err().to_string();
+        assert!(err.contains("style.separator_bg"), "{err}");
+    }
+
+    /// Default config with only the NewApi cost segment, enabled with `options`
+    fn newapi_config(options: serde_json::Value) -> Config {
+        let mut config = Config::default();
+        config.segments.retain(|s| s.id == SegmentId::NewApiCost);
+        let segment = &mut config.segments[0];
+        segment.enabled = true;
+        segment.options = serde_json::from_value(options).unwrap();
+        config
+    }
+
+    #[test]
+    fn under_configured_newapi_cost_is_flagged() {
+        let warnings = newapi_config(serde_json::json!({})).warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            warnings[0]
+                .contains("\"new_api_cost\" is enabled but missing base_url, user_token, user_id"),
+            "{}",
+            warnings[0]
+        );
+
+        let config = newapi_config(serde_json::json!({
+            "base_url": "https://api.example.com",
+            "user_token": "",
+        }));
+        config.check().unwrap();
+        let warnings = config.warnings();
+        assert!(
+            warnings[0].contains("missing user_token, user_id"),
+            "{}",
+            warnings[0]
+        );
+    }
+
+    #[test]
+    fn configured_or_disabled_newapi_cost_is_not_flagged() {
+        let mut config = newapi_config(serde_json::json!({
+            "base_url": "https://api.example.com",
+            "user_token": "sk-test",
+            "user_id": 42,
+        }));
+        assert!(config.warnings().is_empty());
+
+        config.segments[0].options.clear();
+        config.segments[0].enabled = false;
+        assert!(config.warnings().is_empty());
+    }
+
+    #[test]
+    fn unusable_newapi_cost_values_are_flagged() {
+        let config = newapi_config(serde_json::json!({
+            "base_url": "https://api.example.com",
+            "user_token": [""],
+            "user_id": true,
+        }));
+        let warnings = config.warnings();
+        assert!(
+            warnings[0].contains("missing user_token, user_id"),
+            "{}",
+            warnings[0]
+        );
+    }
+}
@@ -0,0 +1,879 @@
+use super::types::{Config, InputData};
+use serde_json::{json, Map, Value};
+
+/// Option type in the generated schema
+#[derive(Clone, Copy)]
+enum OptionType {
+    Bool,
+    Integer,
+    Number,
+    String,
+    StringList,
+    /// A string or a list of strings
+    StringOrList,
+    Color,
+    Enum(&'static [&'static str]),
+    /// Table with the given string-valued keys
+    Table(&'static [&'static str]),
+}
+
+/// (name, type, description) of a segment option
+type OptionSpec = (&'static str, OptionType, &'static str);
+
+/// Options understood by every segment (applied by the renderer)
+const COMMON_OPTIONS: &[OptionSpec] = &[
+    (
+        "on_error",
+        OptionType::Enum(&["hide", "glyph", "placeholder"]),
+        "What to show when the segment fails",
+    ),
+    (
+        "error_glyph",
+        OptionType::String,
+        "Glyph shown in glyph mode",
+    ),
+    ("error_color", OptionType::Color, "Text color in glyph mode"),
+    (
+        "error_placeholder",
+        OptionType::String,
+        "Text shown in placeholder mode",
+    ),
+    (
+        "layout",
+        OptionType::Enum(&[
+            "primary_secondary",
+            "secondary_primary",
+            "primary_only",
+            "secondary_only",
+        ]),
+        "Order of the primary and secondary text",
+    ),
+    (
+        "line",
+        OptionType::Integer,
+        "Statusline row (1 or 2) the segment is drawn on",
+    ),
+    (
+        "max_width",
+        OptionType::Integer,
+        "Maximum width of the main text in columns",
+    ),
+    (
+        "truncate",
+        OptionType::Enum(&["start", "middle", "end"]),
+        "Which part of over-long text is cut",
+    ),
+    (
+        "number_format",
+        OptionType::Enum(&["fixed", "adaptive"]),
+        "With adaptive, shorten numbers to fit max_width before truncating",
+    ),
+    (
+        "hide_when",
+        OptionType::StringList,
+        "Hide the segment when any condition holds, e.g. `metadata.cost < 0.01`",
+    ),
+    (
+        "priority",
+        OptionType::Integer,
+        "Higher priorities are kept longer when the row exceeds max_total_width",
+    ),
+    (
+        "timeout_ms",
+        OptionType::Integer,
+        "Limit on the segment's network requests or commands in milliseconds",
+    ),
+    (
+        "auto_color",
+        OptionType::Bool,
+        "Derive the text color from the segment's label by hashing it into the theme palette",
+    ),
+];
+
+/// Options read by each segment's factory, keyed by serialized segment id
+/// Keep in sync with the `with_config_from_options` readers of each segment
+const SEGMENT_OPTIONS: &[(&str, &[OptionSpec])] = &[
+    (
+        "git",
+        &[
+            ("show_sha", OptionType::Bool, "Show the short commit SHA"),
+            ("show_stash", OptionType::Bool, "Show the stash count"),
+            (
+                "show_commit_age",
+                OptionType::Bool,
+                "Show the age of the last commit",
+            ),
+            (
+                "show_provider",
+                OptionType::Bool,
+                "Use the origin remote's hosting provider icon",
+            ),
+            (
+                "show_operation",
+                OptionType::Bool,
+                "Show an in-progress rebase, merge, cherry-pick, revert or bisect",
+            ),
+            (
+                "show_worktree",
+                OptionType::Bool,
+                "Show the worktree name when inside a linked worktree",
+            ),
+            (
+                "show_added",
+                OptionType::Bool,
+                "Show the number of new files",
+            ),
+            (
+                "show_modified",
+                OptionType::Bool,
+                "Show the number of modified files",
+            ),
+            (
+                "show_deleted",
+                OptionType::Bool,
+                "Show the number of deleted files",
+            ),
+            (
+                "color_changes",
+                OptionType::Bool,
+                "Color the file counts (default true)",
+            ),
+            (
+                "added_color",
+                OptionType::Color,
+                "Color of the new file count",
+            ),
+            (
+                "modified_color",
+                OptionType::Color,
+                "Color of the modified file count",
+            ),
+            (
+                "deleted_color",
+                OptionType::Color,
+                "Color of the deleted file count",
+            ),
+            (
+                "operation_color",
+                OptionType::Color,
+                "Text color while an operation is in progress",
+            ),
+            (
+                "cache_ttl",
+                OptionType::Integer,
+                "Seconds to reuse results while HEAD and the index are unchanged",
+            ),
+        ],
+    ),
+    (
+        "context_window",
+        &[
+            (
+                "transcript_scan_limit",
+                OptionType::Integer,
+                "Bytes read from the end of the transcript (0 reads it all)",
+            ),
+            (
+                "display",
+                OptionType::Enum(&["text", "bar"]),
+                "Show usage as text or as a progress bar",
+            ),
+            (
+                "bar_width",
+                OptionType::Integer,
+                "Number of cells in the bar",
+            ),
+            ("bar_filled", OptionType::String, "Glyph for filled cells"),
+            ("bar_empty", OptionType::String, "Glyph for empty cells"),
+            (
+                "warning_threshold",
+                OptionType::Number,
+                "Usage fraction that colors the bar as a warning (default 0.8)",
+            ),
+            (
+                "critical_threshold",
+                OptionType::Number,
+                "Usage fraction that colors the bar as critical (default 0.95)",
+            ),
+            (
+                "warning_color",
+                OptionType::Color,
+                "Bar color past the warning threshold",
+            ),
+            (
+                "critical_color",
+                OptionType::Color,
+                "Bar color past the critical threshold",
+            ),
+        ],
+    ),
+    (
+        "usage",
+        &[
+            ("api_base_url", OptionType::String, "Usage API base URL"),
+            (
+                "cache_duration",
+                OptionType::Integer,
+                "Seconds to reuse cached usage",
+            ),
+            (
+                "timeout",
+                OptionType::Integer,
+                "Request timeout in seconds (timeout_ms takes precedence)",
+            ),
+        ],
+    ),
+    (
+        "cost",
+        &[("locale", OptionType::String, "Number formatting locale")],
+    ),
+    (
+        "new_api_cost",
+        &[
+            ("base_url", OptionType::String, "NewApi base URL"),
+            (
+                "user_token",
+                OptionType::StringOrList,
+                "NewApi user token (a list of tokens is also accepted)",
+            ),
+            (
+                "token_selection",
+                OptionType::Enum(&["first", "round_robin", "aggregate"]),
+                "Which of several user tokens a render uses (default first)",
+            ),
+            ("user_id", OptionType::String, "NewApi user ID"),
+            ("token_name", OptionType::String, "Only count this token"),
+            (
+                "provider",
+                OptionType::String,
+                "Provider name shown as secondary",
+            ),
+            (
+                "quota_per_unit",
+                OptionType::Number,
+                "Quota per currency unit",
+            ),
+            (
+                "timeout",
+                OptionType::Integer,
+                "Request timeout in seconds (timeout_ms takes precedence)",
+            ),
+            ("daily_budget", OptionType::Number, "Daily budget"),
+            (
+                "warning_threshold",
+                OptionType::Number,
+                "Budget fraction for the warning color",
+            ),
+            (
+                "critical_threshold",
+                OptionType::Number,
+                "Budget fraction for the critical color",
+            ),
+            ("warning_color", OptionType::Color, "Warning text color"),
+            ("critical_color", OptionType::Color, "Critical text color"),
+            (
+                "zero_display",
+                OptionType::String,
+                "show, hide or custom:<text>",
+            ),
+            (
+                "show_max",
+                OptionType::Bool,
+                "Show the window's highest cost",
+            ),
+            (
+                "show_delta",
+                OptionType::Bool,
+                "Show the increase since the previous render",
+            ),
+            (
+                "show_sparkline",
+                OptionType::Bool,
+                "Show a sparkline of recent costs",
+            ),
+            (
+                "show_since_checkpoint",
+                OptionType::Bool,
+                "Show the spend since ccline --checkpoint-cost",
+            ),
+            (
+                "show_projection",
+                OptionType::Bool,
+                "Show the end-of-day total at the current pace",
+            ),
+            (
+                "projection_min_hours",
+                OptionType::Number,
+                "Hours into the day before the projection shows (default 2)",
+            ),
+            (
+                "sparkline_length",
+                OptionType::Integer,
+                "Number of recent renders in the sparkline",
+            ),
+            ("locale", OptionType::String, "Number formatting locale"),
+            ("currency", OptionType::String, "Currency symbol"),
+            (
+                "exchange_rate",
+                OptionType::Number,
+                "Multiplier to show the cost in a second currency",
+            ),
+            (
+                "secondary_currency",
+                OptionType::String,
+                "Symbol of the second currency (default $)",
+            ),
+            ("compression", OptionType::Bool, "Accept gzip responses"),
+            ("proxy", OptionType::String, "Proxy URL"),
+            (
+                "mood",
+                OptionType::Enum(&["off", "prefix", "replace"]),
+                "Show the budget band as an emoji next to or instead of the percentage",
+            ),
+            (
+                "mood_emojis",
+                OptionType::StringList,
+                "Emoji for under, near and over budget",
+            ),
+            (
+                "indicator_only",
+                OptionType::Bool,
+                "Show only a glyph colored by the budget band",
+            ),
+            (
+                "indicator_glyph",
+                OptionType::String,
+                "Glyph used with indicator_only (default ●)",
+            ),
+            (
+                "indicator_color",
+                OptionType::Color,
+                "Glyph color below the warning threshold (default green)",
+            ),
+            (
+                "color_by",
+                OptionType::Enum(&["absolute", "velocity"]),
+                "Color by cost against the budget or by how fast the cost grows",
+            ),
+            (
+                "velocity_warning",
+                OptionType::Number,
+                "Cost per minute for the warning color (default 0.5)",
+            ),
+            (
+                "velocity_critical",
+                OptionType::Number,
+                "Cost per minute for the critical color (default 1.0)",
+            ),
+            (
+                "stat_type",
+                OptionType::Integer,
+                "Log type queried from the stat endpoint (default 2)",
+            ),
+            (
+                "balance_path",
+                OptionType::String,
+                "Endpoint below base_url reporting the remaining balance, e.g. /api/user/self",
+            ),
+            (
+                "balance_field",
+                OptionType::String,
+                "JSON path of the remaining quota in the balance response (default data.quota)",
+            ),
+            (
+                "response_mapping",
+                OptionType::Table(&["quota", "success", "message"]),
+                "JSON paths of the quota, success flag and message in the stat response",
+            ),
+            (
+                "wake_gap",
+                OptionType::Integer,
+                "Seconds without a fetch after which a failure is retried",
+            ),
+            (
+                "tz",
+                OptionType::String,
+                "local, utc or an offset like +08:00",
+            ),
+            (
+                "day_start_hour",
+                OptionType::Integer,
+                "Hour (0-23) the billing day starts at",
+            ),
+            (
+                "range",
+                OptionType::Enum(&["today", "week", "month", "custom"]),
+                "Cost window",
+            ),
+            (
+                "start_date",
+                OptionType::String,
+                "Custom range start (YYYY-MM-DD)",
+            ),
+            (
+                "end_date",
+                OptionType::String,
+                "Custom range end, inclusive (YYYY-MM-DD)",
+            ),
+        ],
+    ),
+    (
+        "runtime_version",
+        &[(
+            "language",
+            OptionType::Enum(&["python", "node", "rust"]),
+            "Only detect this runtime",
+        )],
+    ),
+    (
+        "notify",
+        &[
+            ("command", OptionType::String, "Command printing the count"),
+            ("file", OptionType::String, "File containing the count"),
+        ],
+    ),
+    (
+        "secrets",
+        &[(
+            "files",
+            OptionType::StringList,
+            "File names to watch for in the current directory",
+        )],
+    ),
+    (
+        "host",
+        &[
+            (
+                "show_only_ssh",
+                OptionType::Bool,
+                "Only show the host inside SSH sessions",
+            ),
+            (
+                "format",
+                OptionType::Enum(&["host", "user@host"]),
+                "Show the hostname alone or as user@host",
+            ),
+        ],
+    ),
+    (
+        "tmux",
+        &[(
+            "show_window",
+            OptionType::Bool,
+            "Show the window name after the session (default true)",
+        )],
+    ),
+    (
+        "exit_code",
+        &[
+            (
+                "env",
+                OptionType::String,
+                "Environment variable holding the exit code (default CCLINE_EXIT_CODE)",
+            ),
+            (
+                "input_field",
+                OptionType::String,
+                "Top-level stdin JSON field holding the exit code; wins over env",
+            ),
+            (
+                "show_success",
+                OptionType::Bool,
+                "Show success_glyph when the command succeeded",
+            ),
+            (
+                "success_glyph",
+                OptionType::String,
+                "Glyph on success (default ✓)",
+            ),
+            (
+                "failure_glyph",
+                OptionType::String,
+                "Glyph before a failing code (default ✗)",
+            ),
+            (
+                "success_color",
+                OptionType::Color,
+                "Text color on success (default green)",
+            ),
+            (
+                "failure_color",
+                OptionType::Color,
+                "Text color on failure (default red)",
+            ),
+        ],
+    ),
+    (
+        "plan",
+        &[
+            (
+                "input_field",
+                OptionType::String,
+                "Dotted path of the plan in the stdin JSON (default plan)",
+            ),
+            (
+                "env",
+                OptionType::String,
+                "Environment variable holding the plan (default CCLINE_PLAN)",
+            ),
+            (
+                "value",
+                OptionType::String,
+                "Plan shown when neither the input nor the environment has one",
+            ),
+        ],
+    ),
+];
+
+/// Options a segment can't work without, keyed by serialized segment id;
+/// `--check` warns when an enabled segment leaves any of them unset or empty
+const REQUIRED_OPTIONS: &[(&str, &[&str])] =
+    &[("new_api_cost", &["base_url", "user_token", "user_id"])];
+
+/// Whether a required option holds something usable: a non-empty string, a
+/// number, or a list with a non-empty string; anything else leaves it unset
+pub fn is_option_set(value: &Value) -> bool {
+    match value {
+        Value::String(text) => !text.is_empty(),
+        Value::Number(_) => true,
+        Value::Array(items) => items
+            .iter()
+            .any(|item| item.as_str().is_some_and(|text| !text.is_empty())),
+        _ => false,
+    }
+}
+
+/// Required options of a segment (see `REQUIRED_OPTIONS`)
+pub fn required_options(segment: &str) -> &'static [&'static str] {
+    REQUIRED_OPTIONS
+        .iter()
+        .find(|(name, _)| *name == segment)
+        .map(|(_, options)| *options)
+        .unwrap_or(&[])
+}
+
+fn option_schema(option_type: OptionType, description: &str) -> Value {
+    let mut schema = match option_type {
+        OptionType::Bool => json!({ "type": "boolean" }),
+        OptionType::Integer => json!({ "type": "integer", "minimum": 0 }),
+        OptionType::Number => json!({ "type": "number" }),
+        OptionType::String => json!({ "type": "string" }),
+        OptionType::StringList => json!({ "type": "array", "items": { "type": "string" } }),
+        OptionType::StringOrList => json!({
+            "anyOf": [
+                { "type": "string" },
+                { "type": "array", "items": { "type": "string" } }
+            ]
+        }),
+        OptionType::Color => json!({ "$ref": "#/definitions/AnsiColor" }),
+        OptionType::Enum(values) => json!({ "type": "string", "enum": values }),
+        OptionType::Table(keys) => {
+            let properties: Map<String, Value> = keys
+                .iter()
+                .map(|key| (key.to_string(), json!({ "type": "string" })))
+                .collect();
+            json!({ "type": "object", "properties": properties, "additionalProperties": false })
+        }
+    };
+    schema["description"] = json!(description);
+    schema
+}
+
+fn options_schema(options: &[&[OptionSpec]]) -> Value {
+    let properties: Map<String, Value> = options
+        .iter()
+        .flat_map(|specs| specs.iter())
+        .map(|(name, option_type, description)| {
+            (name.to_string(), option_schema(*option_type, description))
+        })
+        .collect();
+
+    json!({ "type": "object", "properties": properties })
+}
+
+/// JSON Schema for the config file, generated from the config types with
+/// each segment's known options attached
+pub fn config_schema() -> Value {
+    let mut schema = serde_json::to_value(schemars::schema_for!(Config)).unwrap_or_default();
+
+    let rules: Vec<Value> = SEGMENT_OPTIONS
+        .iter()
+        .map(|(id, options)| {
+            json!({
+                "if": { "properties": { "id": { "const": id } } },
+                "then": {
+                    "properties": { "options": options_schema(&[COMMON_OPTIONS, options]) }
+                }
+            })
+        })
+        .collect();
+
+    if let Some(segment) = schema.pointer_mut("/definitions/SegmentConfig") {
+        segment["properties"]["options"] = options_schema(&[COMMON_OPTIONS]);
+        segment["allOf"] = Value::Array(rules);
+    }
+
+    schema
+}
+
+/// Example of the JSON ccline reads from stdin
+/// `model`, `workspace` and `transcript_path` are required; `cost` and
+/// `output_style` may be omitted, and other fields (e.g. `cwd`) are ignored
+pub fn input_example() -> Result<Value, Box<dyn std::error::Error>> {
+    let example = json!({
+        "model": {
+            "id": "claude-sonnet-4-20250514",
+            "display_name": "Sonnet 4"
+        },
+        "cwd": "/home/user/project",
+        "workspace": {
+            "current_dir": "/home/user/project"
+        },
+        "transcript_path": "/home/user/.claude/projects/project/session.jsonl",
+        "cost": {
+            "total_cost_usd": 0.42,
+            "total_duration_ms": 120000,
+            "total_api_duration_ms": 45000,
+            "total_lines_added": 120,
+            "total_lines_removed": 30
+        },
+        "output_style": {
+            "name": "default"
+        }
+    });
+
+    // Run the example through the real deserializer so it cannot drift from InputData
+    InputData::parse(&example.to_string(), true)?;
+
+    Ok(example)
+}
+
+/// The example input pointed at `cwd`, so git and directory segments have
+/// data; what `--init-example-input` writes and `--dry-run` renders
+pub fn sample_input(cwd: &std::path::Path) -> Result<Value, Box<dyn std::error::Error>> {
+    let mut example = input_example()?;
+    let cwd = cwd.display().to_string();
+    example["cwd"] = json!(cwd);
+    example["workspace"]["current_dir"] = json!(cwd);
+    Ok(example)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Errors from checking `value` against the subset of JSON Schema that
+    /// `config_schema` uses; empty when it validates
+    fn validate(root: &Value, schema: &Value, value: &Value, path: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        let Some(schema) = schema.as_object() else {
+            return errors;
+        };
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let target = root
+                .pointer(reference.trim_start_matches('#'))
+                .expect("ref");
+            errors.extend(validate(root, target, value, path));
+        }
+        if let Some(types) = schema.get("type") {
+            let matches = |name: &Value| match name.as_str() {
+                Some("object") => value.is_object(),
+                Some("array") => value.is_array(),
+                Some("string") => value.is_string(),
+                Some("boolean") => value.is_boolean(),
+                Some("integer") => value.is_i64() || value.is_u64(),
+                Some("number") => value.is_number(),
+                Some("null") => value.is_null(),
+                _ => false,
+            };
+            let ok = match types {
+                Value::Array(types) => types.iter().any(matches),
+                name => matches(name),
+            };
+            if !ok {
+                errors.push(format!("{path}: expected {types}, got {value}"));
+            }
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            if !values.contains(value) {
+                errors.push(format!("{path}: {value} not in {values:?}"));
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if expected != value {
+                errors.push(format!("{path}: expected {expected}"));
+            }
+        }
+        if let (Some(minimum), Some(number)) = (
+            schema.get("minimum").and_then(Value::as_f64),
+            value.as_f64(),
+        ) {
+            if number < minimum {
+                errors.push(format!("{path}: {number} below {minimum}"));
+            }
+        }
+        if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+            for (i, item) in array.iter().enumerate() {
+                errors.extend(validate(root, items, item, &format!("{path}[{i}]")));
+            }
+        }
+        if let Some(object) = value.as_object() {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for required in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                let key = required.as_str().unwrap_or_default();
+                if !object.contains_key(key) {
+                    errors.push(format!("{path}: missing {key}"));
+                }
+            }
+            for (key, item) in object {
+                match properties.and_then(|p| p.get(key)) {
+                    Some(property) => {
+                        errors.extend(validate(root, property, item, &format!("{path}.{key}")))
+                    }
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        errors.push(format!("{path}: unexpected {key}"))
+                    }
+                    None => {}
+                }
+            }
+        }
+        for rule in schema
+            .get("allOf")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            errors.extend(validate(root, rule, value, path));
+        }
+        let passing = |key: &str| {
+            schema.get(key).and_then(Value::as_array).map(|rules| {
+                rules
+                    .iter()
+                    .filter(|rule| validate(root, rule, value, path).is_empty())
+                    .count()
+            })
+        };
+        if passing("anyOf") == Some(0) {
+            errors.push(format!("{path}: matches no anyOf branch"));
+        }
+        if passing("oneOf").is_some_and(|count| count != 1) {
+            errors.push(format!("{path}: must match exactly one oneOf branch"));
+        }
+        if let Some(condition) = schema.get("if") {
+            if validate(root, condition, value, path).is_empty() {
+                if let Some(then) = schema.get("then") {
+                    errors.extend(validate(root, then, value, path));
+                }
+            }
+        }
+
+        errors
+    }
+
+    fn config_errors(config: &Value) -> Vec<String> {
+        let schema = config_schema();
+        validate(&schema, &schema, config, "config")
+    }
+
+    #[test]
+    fn default_config_validates_against_schema() {
+        let config = serde_json::to_value(Config::default()).unwrap();
+        assert_eq!(config_errors(&config), Vec::<String>::new());
+    }
+
+    #[test]
+    fn every_theme_validates_against_schema() {
+        let mut env = crate::test_support::EnvGuard::new();
+        let home = crate::test_support::TempDir::new("home");
+        env.set("HOME", home.path());
+        for theme in crate::ui::themes::ThemePresets::list_available_themes() {
+            let config = crate::ui::themes::ThemePresets::get_theme(&theme);
+            let config = serde_json::to_value(config).unwrap();
+            assert_eq!(config_errors(&config), Vec::<String>::new(), "{theme}");
+        }
+    }
+
+    #[test]
+    fn wrong_option_types_are_rejected() {
+        let mut config = serde_json::to_value(Config::default()).unwrap();
+        let segments = config["segments"].as_array_mut().unwrap();
+        let git = segments
+            .iter_mut()
+            .find(|segment| segment["id"] == "git")
+            .unwrap();
+        git["options"]["show_sha"] = json!("yes");
+
+        let errors = config_errors(&config);
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(errors[0].contains("show_sha"), "{errors:?}");
+    }
+
+    #[test]
+    fn input_example_deserializes_into_every_field() {
+        let example = input_example().unwrap();
+        let input = InputData::parse(&example.to_string(), true).unwrap();
+
+        assert_eq!(input.model.display_name, "Sonnet 4");
+        assert_eq!(input.workspace.current_dir, "/home/user/project");
+        assert!(input.transcript_path.ends_with("session.jsonl"));
+        let cost = input.cost.expect("cost");
+        assert_eq!(cost.total_cost_usd, Some(0.42));
+        assert_eq!(cost.total_lines_removed, Some(30));
+        assert_eq!(input.output_style.expect("output_style").name, "default");
+        // Only the documented pass-through field lands outside the typed fields
+        assert_eq!(input.extra.keys().collect::<Vec<_>>(), vec!["cwd"]);
+    }
+
+    #[test]
+    fn input_example_lists_the_required_fields() {
+        let example = input_example().unwrap();
+        for field in ["model", "workspace", "transcript_path"] {
+            let mut partial = example.clone();
+            partial.as_object_mut().unwrap().remove(field);
+            assert!(
+                InputData::parse(&partial.to_string(), true).is_err(),
+                "{field} should be required"
+            );
+        }
+        for field in ["cost", "output_style", "cwd"] {
+            let mut partial = example.clone();
+            partial.as_object_mut().unwrap().remove(field);
+            assert!(
+                InputData::parse(&partial.to_string(), true).is_ok(),
+                "{field}"
+            );
+        }
+    }
+
+    #[test]
+    fn sample_input_points_at_the_given_directory() {
+        let cwd = std::path::Path::new("/tmp/project");
+        let sample = sample_input(cwd).unwrap();
+
+        let input = InputData::parse(&sample.to_string(), true).unwrap();
+        assert_eq!(input.workspace.current_dir, "/tmp/project");
+        assert_eq!(sample["cwd"], "/tmp/project");
+        assert_eq!(input.model.display_name, "Sonnet 4");
+        // Apart from the directory it is the documented example
+        let mut example = input_example().unwrap();
+        example["cwd"] = json!("/tmp/project");
+        example["workspace"]["current_dir"] = json!("/tmp/project");
+        assert_eq!(sample, example);
+    }
+
+    #[test]
+    fn required_options_are_listed_per_segment() {
+        assert_eq!(
+            required_options("new_api_cost"),
+            ["base_url", "user_token", "user_id"]
+        );
+        assert!(required_options("git").is_empty());
+        assert!(required_options("no_such_segment").is_empty());
+    }
+}
@@ -1,23 +1,96 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 // Main config structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
     pub style: StyleConfig,
     pub segments: Vec<SegmentConfig>,
     pub theme: String,
+    /// Extra files whose segment definitions are merged into this config
+    /// Relative paths resolve against the including file's directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Extra metadata keys masked in machine-readable output
+    /// Known-sensitive keys such as `user_token` are always masked
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact: Vec<String>,
+    /// Whole-line layout with `{segment_id}` placeholders, e.g. `"{model} | {git} {cost}"`
+    /// Replaces the separator-joined layout when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Terminal title template with the same placeholders as `format`,
+    /// emitted as an OSC 0 escape before the statusline when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_format: Option<String>,
+    /// How many segments are collected at once (default: network segments + 2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+    /// Print the statusline without a trailing newline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_newline: Option<bool>,
+    /// Text printed instead of an empty statusline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub empty_placeholder: Option<String>,
+    /// Print nothing at all, not even a newline, when the statusline is empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_on_empty: Option<bool>,
+    /// Directory for cache files (default: the platform cache directory)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<String>,
+    /// Reprint the previous line when invoked again within this many milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_render_interval_ms: Option<u64>,
+    /// Keep the latest stdin input on disk so the configurator preview can
+    /// show real data for local segments (off by default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview_real_input: Option<bool>,
 }
 
 // Default implementation moved to ui/themes/presets.rs
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct StyleConfig {
     pub mode: StyleMode,
     pub separator: String,
+    /// Thin separator used between powerline segments sharing a background
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subseparator: Option<String>,
+    /// Interpolate segment colors from `start` to `end` across the statusline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gradient: Option<GradientConfig>,
+    /// Hard cap on each rendered row's width in columns; over-long rows
+    /// drop secondaries, then low-priority segments, then truncate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_width: Option<usize>,
+    /// Color of plain separators and subseparators instead of the default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub separator_fg: Option<AnsiColor>,
+    /// Background behind plain separators
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub separator_bg: Option<AnsiColor>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct GradientConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    pub start: AnsiColor,
+    pub end: AnsiColor,
+    #[serde(default)]
+    pub target: GradientTarget,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GradientTarget {
+    #[default]
+    Foreground,
+    Background,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum StyleMode {
     Plain,
@@ -25,7 +98,7 @@ pub enum StyleMode {
     Powerline,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SegmentConfig {
     pub id: SegmentId,
     pub enabled: bool,
@@ -33,27 +106,45 @@ pub struct SegmentConfig {
     pub colors: ColorConfig,
     pub styles: TextStyleConfig,
     pub options: HashMap<String, serde_json::Value>,
+    /// Consecutive segments with the same group share one background,
+    /// split by subseparators, with powerline arrows only between groups
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Registered name of a `custom` segment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl SegmentConfig {
+    /// Name the segment is registered and referred to by: `name` for a
+    /// `custom` segment, otherwise the id's config name (e.g. `new_api_cost`)
+    pub fn key(&self) -> String {
+        match (self.id, &self.name) {
+            (SegmentId::Custom, Some(name)) => name.clone(),
+            _ => self.id.name(),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct IconConfig {
     pub plain: String,
     pub nerd_font: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ColorConfig {
     pub icon: Option<AnsiColor>,
     pub text: Option<AnsiColor>,
     pub background: Option<AnsiColor>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default, JsonSchema)]
 pub struct TextStyleConfig {
     pub text_bold: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum AnsiColor {
     Color16 { c16: u8 },
@@ -61,7 +152,7 @@ pub enum AnsiColor {
     Rgb { r: u8, g: u8, b: u8 },
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SegmentId {
     Model,
@@ -74,6 +165,25 @@ pub enum SegmentId {
     OutputStyle,
     Update,
     NewApiCost,
+    RuntimeVersion,
+    Notify,
+    Secrets,
+    Host,
+    Tmux,
+    ExitCode,
+    Plan,
+    /// A segment registered by code embedding this crate, chosen by `name`
+    Custom,
+}
+
+impl SegmentId {
+    /// Config name of the id (e.g. `new_api_cost`)
+    pub fn name(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_else(|| format!("{:?}", self))
+    }
 }
 
 // Legacy compatibility structure
@@ -86,18 +196,59 @@ pub struct SegmentsConfig {
 }
 
 // Data structures compatible with existing main.rs
-#[derive(Deserialize)]
+/// Model reported by Claude Code; accepts a plain string or an object
+/// with `id` and/or `display_name`, filling whichever is missing from the other
+#[derive(Clone, Deserialize)]
+#[serde(from = "RawModel")]
 pub struct Model {
     pub id: String,
     pub display_name: String,
 }
 
+/// Shapes the `model` field has had across Claude Code versions
 #[derive(Deserialize)]
+#[serde(untagged)]
+enum RawModel {
+    Name(String),
+    Object {
+        #[serde(default)]
+        id: String,
+        #[serde(default)]
+        display_name: String,
+    },
+}
+
+impl From<RawModel> for Model {
+    fn from(raw: RawModel) -> Self {
+        let (id, display_name) = match raw {
+            RawModel::Name(name) => (name.clone(), name),
+            RawModel::Object { id, display_name } => (id, display_name),
+        };
+        Model::normalize(id, display_name)
+    }
+}
+
+impl Model {
+    /// Prefer the display name, falling back to the id (and vice versa)
+    pub fn normalize(id: String, display_name: String) -> Self {
+        let display_name = match display_name.trim() {
+            "" => id.clone(),
+            name => name.to_string(),
+        };
+        let id = match id.trim() {
+            "" => display_name.clone(),
+            id => id.to_string(),
+        };
+        Model { id, display_name }
+    }
+}
+
+#[derive(Clone, Deserialize)]
 pub struct Workspace {
     pub current_dir: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct Cost {
     pub total_cost_usd: Option<f64>,
     pub total_duration_ms: Option<u64>,
@@ -106,18 +257,75 @@ pub struct Cost {
     pub total_lines_removed: Option<u32>,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct OutputStyle {
     pub name: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct InputData {
     pub model: Model,
     pub workspace: Workspace,
     pub transcript_path: String,
     pub cost: Option<Cost>,
     pub output_style: Option<OutputStyle>,
+    /// Top-level fields not covered above, e.g. ones added by a wrapper script
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl InputData {
+    /// Parse input read from stdin
+    /// Callers sometimes send several JSON values (NDJSON); the last complete object
+    /// represents the latest state and is used. In strict mode the input must be
+    /// exactly one JSON object.
+    pub fn parse(content: &str, strict: bool) -> Result<InputData, Box<dyn std::error::Error>> {
+        if strict {
+            return Ok(serde_json::from_str(content)?);
+        }
+
+        let mut last_object = None;
+        for value in serde_json::Deserializer::from_str(content).into_iter::<serde_json::Value>() {
+            match value {
+                Ok(value) if value.is_object() => last_object = Some(value),
+                Ok(_) => {}
+                // Stop at trailing garbage, keeping the last complete object
+                Err(_) => break,
+            }
+        }
+
+        match last_object {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            // Surface the original parse error
+            None => Ok(serde_json::from_str(content)?),
+        }
+    }
+
+    /// Where the most recent stdin input is kept for the configurator preview
+    fn last_seen_path() -> Option<std::path::PathBuf> {
+        Some(crate::utils::cache::cache_dir()?.join("last_input.json"))
+    }
+
+    /// Remember raw stdin input so the configurator can preview with real data
+    /// The file is only rewritten when the input changed since the last render
+    pub fn record_last_seen(content: &str) {
+        let Some(path) = Self::last_seen_path() else {
+            return;
+        };
+        if std::fs::read_to_string(&path).is_ok_and(|previous| previous == content) {
+            return;
+        }
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let _ = crate::utils::write_atomic(&path, content.as_bytes());
+    }
+
+    /// Input recorded by the last statusline render, if any
+    pub fn load_last_seen() -> Option<InputData> {
+        let content = std::fs::read_to_string(Self::last_seen_path()?).ok()?;
+        Self::parse(&content, false).ok()
+    }
 }
 
 // OpenAI-style nested token details
@@ -248,6 +456,8 @@ impl Config {
         // Compare style config
         if self.style.mode != theme_preset.style.mode
             || self.style.separator != theme_preset.style.separator
+            || self.style.subseparator != theme_preset.style.subseparator
+            || self.style.gradient != theme_preset.style.gradient
         {
             return false;
         }
@@ -283,6 +493,7 @@ impl Config {
             && self.color_matches(&current.colors.background, &preset.colors.background)
             && current.styles.text_bold == preset.styles.text_bold
             && current.options == preset.options
+            && current.group == preset.group
     }
 
     /// Compare two optional colors for equality
@@ -295,6 +506,65 @@ impl Config {
     }
 }
 
+impl AnsiColor {
+    /// ANSI escape setting this as the foreground color
+    pub fn foreground_code(&self) -> String {
+        match self {
+            AnsiColor::Color16 { c16 } => {
+                let code = if *c16 < 8 { 30 + c16 } else { 90 + (c16 - 8) };
+                format!("\x1b[{}m", code)
+            }
+            AnsiColor::Color256 { c256 } => format!("\x1b[38;5;{}m", c256),
+            AnsiColor::Rgb { r, g, b } => format!("\x1b[38;2;{};{};{}m", r, g, b),
+        }
+    }
+
+    /// Approximate RGB value using the standard xterm palette
+    pub fn to_rgb(&self) -> (u8, u8, u8) {
+        const BASIC: [(u8, u8, u8); 16] = [
+            (0, 0, 0),
+            (205, 0, 0),
+            (0, 205, 0),
+            (205, 205, 0),
+            (0, 0, 238),
+            (205, 0, 205),
+            (0, 205, 205),
+            (229, 229, 229),
+            (127, 127, 127),
+            (255, 0, 0),
+            (0, 255, 0),
+            (255, 255, 0),
+            (92, 92, 255),
+            (255, 0, 255),
+            (0, 255, 255),
+            (255, 255, 255),
+        ];
+
+        match self {
+            AnsiColor::Color16 { c16 } => BASIC[(*c16 as usize) % 16],
+            AnsiColor::Color256 { c256 } => match *c256 {
+                0..=15 => BASIC[*c256 as usize],
+                16..=231 => {
+                    let index = c256 - 16;
+                    let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+                    (level(index / 36), level((index / 6) % 6), level(index % 6))
+                }
+                _ => {
+                    let gray = 8 + (c256 - 232) * 10;
+                    (gray, gray, gray)
+                }
+            },
+            AnsiColor::Rgb { r, g, b } => (*r, *g, *b),
+        }
+    }
+
+    /// Hex representation (e.g. `#ff8800`)
+    pub fn to_hex(&self) -> String {
+        let (r, g, b) = self.to_rgb();
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+}
+
 impl PartialEq for AnsiColor {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -415,3 +685,105 @@ pub struct TranscriptEntry {
     pub parent_uuid: Option<String>,
     pub summary: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST: &str =
+        r#"{"model":"first","workspace":{"current_dir":"/a"},"transcript_path":""}"#;
+    const SECOND: &str =
+        r#"{"model":"second","workspace":{"current_dir":"/b"},"transcript_path":""}"#;
+
+    #[test]
+    fn single_object_input() {
+        let input = InputData::parse(FIRST, false).unwrap();
+        assert_eq!(input.model.id, "first");
+        assert_eq!(input.workspace.current_dir, "/a");
+    }
+
+    #[test]
+    fn multiple_objects_use_the_last() {
+        let content = format!("{}\n{}\n", FIRST, SECOND);
+        let input = InputData::parse(&content, false).unwrap();
+        assert_eq!(input.model.id, "second");
+    }
+
+    #[test]
+    fn trailing_partial_object_is_ignored() {
+        let content = format!("{}\n{{\"model\":", FIRST);
+        let input = InputData::parse(&content, false).unwrap();
+        assert_eq!(input.model.id, "first");
+    }
+
+    #[test]
+    fn strict_mode_rejects_multiple_objects() {
+        let content = format!("{}\n{}", FIRST, SECOND);
+        assert!(InputData::parse(&content, true).is_err());
+        assert!(InputData::parse(FIRST, true).is_ok());
+    }
+
+    #[test]
+    fn invalid_input_reports_an_error() {
+        assert!(InputData::parse("not json", false).is_err());
+        assert!(InputData::parse("", false).is_err());
+    }
+
+    #[test]
+    fn last_seen_input_lives_in_the_cache_dir_and_is_written_on_change() {
+        let dir = crate::test_support::isolated_cache();
+        let path = dir.join("last_input.json");
+
+        InputData::record_last_seen(FIRST);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), FIRST);
+        assert_eq!(InputData::load_last_seen().unwrap().model.id, "first");
+
+        // Same input again: the file is left alone
+        let written = std::fs::metadata(&path).unwrap().modified().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        InputData::record_last_seen(FIRST);
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().modified().unwrap(),
+            written
+        );
+
+        InputData::record_last_seen(SECOND);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), SECOND);
+    }
+
+    fn model(json: serde_json::Value) -> Model {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn model_object_prefers_the_display_name() {
+        let model = model(serde_json::json!({
+            "id": "claude-sonnet-4-5",
+            "display_name": "Sonnet 4.5",
+        }));
+        assert_eq!(model.id, "claude-sonnet-4-5");
+        assert_eq!(model.display_name, "Sonnet 4.5");
+    }
+
+    #[test]
+    fn model_object_with_only_an_id_falls_back_to_it() {
+        let id_only = model(serde_json::json!({"id": "claude-opus-4"}));
+        assert_eq!(id_only.display_name, "claude-opus-4");
+
+        let blank_name = model(serde_json::json!({"id": "claude-opus-4", "display_name": "  "}));
+        assert_eq!(blank_name.display_name, "claude-opus-4");
+    }
+
+    #[test]
+    fn model_as_a_plain_string_fills_both_fields() {
+        let model = model(serde_json::json!("claude-haiku-4"));
+        assert_eq!(model.id, "claude-haiku-4");
+        assert_eq!(model.display_name, "claude-haiku-4");
+    }
+
+    #[test]
+    fn model_with_only_a_display_name_uses_it_as_id() {
+        let model = model(serde_json::json!({"display_name": "Sonnet 4"}));
+        assert_eq!(model.id, "Sonnet 4");
+    }
+}
@@ -1,4 +1,11 @@
+pub mod output;
+pub mod palette;
+pub mod recording;
+pub mod registry;
 pub mod segments;
 pub mod statusline;
+pub mod throttle;
+pub mod visibility;
 
-pub use statusline::{collect_all_segments, StatusLineGenerator};
+pub use registry::{create_segment, register_segment};
+pub use statusline::{collect_all_segments, SegmentCollector, StatusLineGenerator};
@@ -0,0 +1,280 @@
+use crate::config::{Config, SegmentConfig};
+use crate::core::segments::SegmentData;
+use crate::core::{visibility, StatusLineGenerator};
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Output format for the rendered statusline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// ANSI-colored statusline (default)
+    #[default]
+    Text,
+    /// Machine-readable JSON with each segment's data
+    Json,
+}
+
+/// Metadata keys that are always masked in machine output
+pub const DEFAULT_REDACTED_KEYS: &[&str] = &[
+    "user_token",
+    "access_token",
+    "api_key",
+    "authorization",
+    "password",
+    "secret",
+    "token",
+];
+
+/// Replacement for redacted metadata values
+pub const REDACTED_VALUE: &str = "[redacted]";
+
+/// Whether a metadata key must be masked, given the config's extra `redact` keys
+pub fn is_redacted(key: &str, extra_keys: &[String]) -> bool {
+    DEFAULT_REDACTED_KEYS
+        .iter()
+        .any(|k| k.eq_ignore_ascii_case(key))
+        || extra_keys.iter().any(|k| k.eq_ignore_ascii_case(key))
+}
+
+/// Copy of the metadata with sensitive values masked
+pub fn redact_metadata(
+    metadata: &HashMap<String, String>,
+    extra_keys: &[String],
+) -> Map<String, Value> {
+    let mut keys: Vec<&String> = metadata.keys().collect();
+    keys.sort();
+
+    keys.into_iter()
+        .map(|key| {
+            let value = if is_redacted(key, extra_keys) {
+                REDACTED_VALUE.to_string()
+            } else {
+                metadata[key].clone()
+            };
+            (key.clone(), Value::String(value))
+        })
+        .collect()
+}
+
+/// Render one segment's data as JSON (`null` when it produced nothing)
+pub fn render_segment_json(config: &Config, data: Option<&SegmentData>) -> String {
+    match data {
+        Some(data) => json!({
+            "primary": data.primary,
+            "secondary": data.secondary,
+            "metadata": redact_metadata(&data.metadata, &config.redact),
+        }),
+        None => Value::Null,
+    }
+    .to_string()
+}
+
+/// Human-readable diagnostics for one segment (`--validate-segment`): where
+/// its settings come from, missing required options, and what it collected
+/// `from_config` is false when the segment only exists in the theme
+pub fn render_segment_report(
+    config: &Config,
+    segment_config: &SegmentConfig,
+    from_config: bool,
+    data: Option<&SegmentData>,
+    elapsed: std::time::Duration,
+) -> String {
+    let name = segment_config.key();
+    let mut lines = vec![format!("Segment: {}", name)];
+
+    lines.push(match (from_config, segment_config.enabled) {
+        (true, true) => "Config: enabled".to_string(),
+        (true, false) => "Config: disabled (collected anyway for this check)".to_string(),
+        (false, _) => format!("Config: not configured, using theme \"{}\"", config.theme),
+    });
+
+    let id = serde_json::to_value(segment_config.id)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let missing: Vec<&str> = crate::config::schema::required_options(&id)
+        .iter()
+        .copied()
+        .filter(|option| {
+            !segment_config
+                .options
+                .get(*option)
+                .is_some_and(crate::config::schema::is_option_set)
+        })
+        .collect();
+    if !missing.is_empty() {
+        lines.push(format!("Missing options: {}", missing.join(", ")));
+    }
+
+    lines.push(format!("Collected in {}ms", elapsed.as_millis()));
+    match data {
+        None => lines.push("Result: nothing to show".to_string()),
+        Some(data) => {
+            if visibility::is_hidden(segment_config, data) {
+                lines.push("Result: hidden by hide_when".to_string());
+            } else if data.is_empty() {
+                lines.push("Result: empty".to_string());
+            } else {
+                lines.push("Result: shown".to_string());
+            }
+            if let Some(error) = data.metadata.get("error") {
+                lines.push(format!("Error: {}", error));
+            }
+            lines.push(format!("  primary:   {}", data.primary));
+            lines.push(format!("  secondary: {}", data.secondary));
+            let mut metadata: Vec<(String, Value)> =
+                redact_metadata(&data.metadata, &config.redact)
+                    .into_iter()
+                    .collect();
+            metadata.sort_by(|a, b| a.0.cmp(&b.0));
+            for (key, value) in metadata {
+                let value = value
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| value.to_string());
+                lines.push(format!("  {} = {}", key, value));
+            }
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Render collected segments as JSON, including the plain statusline text
+pub fn render_json(config: &Config, segments_data: Vec<(SegmentConfig, SegmentData)>) -> String {
+    let segments: Vec<Value> = segments_data
+        .iter()
+        .filter(|(segment_config, data)| {
+            segment_config.enabled
+                && !data.is_empty()
+                && !visibility::is_hidden(segment_config, data)
+        })
+        .map(|(segment_config, data)| {
+            json!({
+                "id": segment_config.id,
+                "primary": data.primary,
+                "secondary": data.secondary,
+                "metadata": redact_metadata(&data.metadata, &config.redact),
+            })
+        })
+        .collect();
+
+    let statusline = StatusLineGenerator::new(config.clone()).generate(segments_data);
+
+    json!({
+        "statusline": statusline,
+        "segments": segments,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SegmentId;
+    use crate::test_support::{segment_config, segment_data};
+
+    fn data_with_metadata(pairs: &[(&str, &str)]) -> SegmentData {
+        let mut data = segment_data("$1.00", "");
+        for (key, value) in pairs {
+            data.metadata.insert(key.to_string(), value.to_string());
+        }
+        data
+    }
+
+    #[test]
+    fn json_output_masks_sensitive_keys_by_default() {
+        let mut config = Config::default();
+        config.segments.clear();
+        let data = data_with_metadata(&[("User_Token", "sk-live-123"), ("provider", "newapi")]);
+
+        let output = render_json(&config, vec![(segment_config(SegmentId::NewApiCost), data)]);
+        assert!(!output.contains("sk-live-123"), "{output}");
+
+        let json: Value = serde_json::from_str(&output).unwrap();
+        let metadata = &json["segments"][0]["metadata"];
+        assert_eq!(metadata["User_Token"], REDACTED_VALUE);
+        assert_eq!(metadata["provider"], "newapi");
+    }
+
+    #[test]
+    fn configured_redact_keys_are_masked_too() {
+        let config = Config {
+            redact: vec!["resolved_url".to_string()],
+            ..Config::default()
+        };
+        let data = data_with_metadata(&[("resolved_url", "https://x/?key=abc")]);
+
+        let output = render_segment_json(&config, Some(&data));
+        let json: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json["metadata"]["resolved_url"], REDACTED_VALUE);
+        assert_eq!(render_segment_json(&config, None), "null");
+    }
+
+    #[test]
+    fn segment_report_lists_result_and_redacted_metadata() {
+        let config = Config {
+            redact: vec!["resolved_url".to_string()],
+            ..Config::default()
+        };
+        let mut segment = segment_config(SegmentId::NewApiCost);
+        segment
+            .options
+            .insert("base_url".to_string(), serde_json::json!("https://x"));
+        segment
+            .options
+            .insert("user_token".to_string(), serde_json::json!(""));
+        let data = data_with_metadata(&[
+            ("resolved_url", "https://x/?key=abc"),
+            ("provider", "newapi"),
+        ]);
+
+        let report = render_segment_report(
+            &config,
+            &segment,
+            true,
+            Some(&data),
+            std::time::Duration::from_millis(12),
+        );
+
+        assert_eq!(
+            report,
+            [
+                "Segment: new_api_cost",
+                "Config: enabled",
+                "Missing options: user_token, user_id",
+                "Collected in 12ms",
+                "Result: shown",
+                "  primary:   $1.00",
+                "  secondary: ",
+                "  provider = newapi",
+                &format!("  resolved_url = {}", REDACTED_VALUE),
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn segment_report_explains_an_empty_result() {
+        let config = Config::default();
+        let mut segment = segment_config(SegmentId::Git);
+        segment.enabled = false;
+
+        let report = render_segment_report(&config, &segment, false, None, Default::default());
+        assert!(
+            report.contains("Config: not configured, using theme"),
+            "{report}"
+        );
+        assert!(report.ends_with("Result: nothing to show"), "{report}");
+
+        let mut errored = segment_data("n/a", "");
+        errored
+            .metadata
+            .insert("error".to_string(), "git timed out".to_string());
+        let report =
+            render_segment_report(&config, &segment, true, Some(&errored), Default::default());
+        assert!(report.contains("Config: disabled"), "{report}");
+        assert!(report.contains("Error: git timed out"), "{report}");
+    }
+}
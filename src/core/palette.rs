@@ -0,0 +1,231 @@
+use crate::config::{AnsiColor, Config};
+
+/// All colors defined by a theme, named after the field that defines them
+pub fn palette_entries(config: &Config) -> Vec<(String, AnsiColor)> {
+    let mut entries = Vec::new();
+
+    for segment in &config.segments {
+        let segment_name = serde_json::to_value(segment.id)
+            .ok()
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+            .unwrap_or_else(|| format!("{:?}", segment.id));
+
+        let fields = [
+            ("icon", &segment.colors.icon),
+            ("text", &segment.colors.text),
+            ("background", &segment.colors.background),
+        ];
+        for (field, color) in fields {
+            if let Some(color) = color {
+                entries.push((format!("{}.{}", segment_name, field), color.clone()));
+            }
+        }
+    }
+
+    if let Some(gradient) = &config.style.gradient {
+        entries.push(("gradient.start".to_string(), gradient.start.clone()));
+        entries.push(("gradient.end".to_string(), gradient.end.clone()));
+    }
+
+    entries
+}
+
+/// Colors picked from when no theme color qualifies (bright 16-color set)
+const FALLBACK_LABEL_COLORS: [u8; 6] = [9, 10, 11, 12, 13, 14];
+
+/// FNV-1a, used instead of `DefaultHasher` so colors (and cache keys built
+/// from it) don't change between Rust releases
+pub(crate) fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Distinct icon and text colors of the theme, in config order
+fn label_palette(config: &Config) -> Vec<AnsiColor> {
+    let mut seen = std::collections::HashSet::new();
+    let colors: Vec<AnsiColor> = palette_entries(config)
+        .into_iter()
+        .filter(|(name, _)| name.ends_with(".icon") || name.ends_with(".text"))
+        .map(|(_, color)| color)
+        .filter(|color| seen.insert(color.to_hex()))
+        .collect();
+
+    if colors.is_empty() {
+        FALLBACK_LABEL_COLORS
+            .iter()
+            .map(|c16| AnsiColor::Color16 { c16: *c16 })
+            .collect()
+    } else {
+        colors
+    }
+}
+
+/// Color for a label, the same one on every run for the same theme
+pub fn color_for_label(config: &Config, label: &str) -> AnsiColor {
+    let palette = label_palette(config);
+    let index = (fnv1a(label) % palette.len() as u64) as usize;
+    palette[index].clone()
+}
+
+fn background_code(color: &AnsiColor) -> String {
+    match color {
+        AnsiColor::Color16 { c16 } if *c16 < 8 => format!("\x1b[{}m", 40 + c16),
+        AnsiColor::Color16 { c16 } if *c16 < 16 => format!("\x1b[{}m", 100 + (c16 - 8)),
+        // Out of the 16-color range: the 256-color palette has the same index
+        AnsiColor::Color16 { c16: index } | AnsiColor::Color256 { c256: index } => {
+            format!("\x1b[48;5;{}m", index)
+        }
+        AnsiColor::Rgb { r, g, b } => format!("\x1b[48;2;{};{};{}m", r, g, b),
+    }
+}
+
+/// Render the palette as one line per color: swatch, name and hex value
+/// Without color only names and hex values are printed
+pub fn render_color_test(config: &Config, use_color: bool) -> String {
+    let entries = palette_entries(config);
+    let name_width = entries
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = vec![format!("Theme: {}", config.theme)];
+    for (name, color) in &entries {
+        let line = if use_color {
+            format!(
+                "{}      \x1b[0m {:<width$} {}",
+                background_code(color),
+                name,
+                color.to_hex(),
+                width = name_width
+            )
+        } else {
+            format!("{:<width$} {}", name, color.to_hex(), width = name_width)
+        };
+        lines.push(line);
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GradientConfig;
+    use crate::ui::themes::ThemePresets;
+
+    #[test]
+    fn enumerates_every_theme_color() {
+        let config = ThemePresets::get_theme("nord");
+        let expected: usize = config
+            .segments
+            .iter()
+            .map(|segment| {
+                [
+                    &segment.colors.icon,
+                    &segment.colors.text,
+                    &segment.colors.background,
+                ]
+                .iter()
+                .filter(|color| color.is_some())
+                .count()
+            })
+            .sum();
+
+        let entries = palette_entries(&config);
+        assert_eq!(entries.len(), expected);
+        assert!(entries.iter().any(|(name, _)| name == "git.icon"));
+    }
+
+    #[test]
+    fn includes_gradient_endpoints() {
+        let mut config = ThemePresets::get_theme("default");
+        config.segments.clear();
+        config.style.gradient = Some(GradientConfig {
+            enabled: true,
+            start: AnsiColor::Rgb { r: 0, g: 0, b: 0 },
+            end: AnsiColor::Rgb {
+                r: 255,
+                g: 255,
+                b: 255,
+            },
+            target: Default::default(),
+        });
+
+        let names: Vec<_> = palette_entries(&config)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["gradient.start", "gradient.end"]);
+    }
+
+    #[test]
+    fn color_test_lists_one_line_per_entry() {
+        let config = ThemePresets::get_theme("gruvbox");
+        let entries = palette_entries(&config);
+
+        let plain = render_color_test(&config, false);
+        assert!(!plain.contains('\x1b'));
+        assert_eq!(plain.lines().count(), entries.len() + 1);
+        for (name, color) in &entries {
+            assert!(plain.contains(name) && plain.contains(&color.to_hex()));
+        }
+
+        let colored = render_color_test(&config, true);
+        assert_eq!(colored.matches("\x1b[0m").count(), entries.len());
+    }
+
+    #[test]
+    fn background_codes_cover_every_index() {
+        assert_eq!(background_code(&AnsiColor::Color16 { c16: 1 }), "\x1b[41m");
+        assert_eq!(
+            background_code(&AnsiColor::Color16 { c16: 15 }),
+            "\x1b[107m"
+        );
+        // Past the 16 colors, the 256-color form keeps the code valid
+        assert_eq!(
+            background_code(&AnsiColor::Color16 { c16: 16 }),
+            "\x1b[48;5;16m"
+        );
+        assert_eq!(
+            background_code(&AnsiColor::Color16 { c16: 255 }),
+            "\x1b[48;5;255m"
+        );
+    }
+
+    #[test]
+    fn label_hash_is_pinned() {
+        // Reference FNV-1a 64-bit values; a change here recolors every label
+        assert_eq!(fnv1a(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn same_label_gets_the_same_color() {
+        let config = ThemePresets::get_theme("nord");
+        let palette = label_palette(&config);
+
+        let color = color_for_label(&config, "api-prod");
+        assert_eq!(color, color_for_label(&config, "api-prod"));
+        let index = (fnv1a("api-prod") % palette.len() as u64) as usize;
+        assert_eq!(color, palette[index]);
+    }
+
+    #[test]
+    fn labels_fall_back_to_bright_colors_without_theme_colors() {
+        let mut config = ThemePresets::get_theme("default");
+        for segment in &mut config.segments {
+            segment.colors.icon = None;
+            segment.colors.text = None;
+        }
+
+        let index = (fnv1a("api-prod") % FALLBACK_LABEL_COLORS.len() as u64) as usize;
+        assert_eq!(
+            color_for_label(&config, "api-prod"),
+            AnsiColor::Color16 {
+                c16: FALLBACK_LABEL_COLORS[index]
+            }
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! `--record` / `--replay`: capture the input, effective config and output
+//! of a render so a bad statusline can be reproduced elsewhere.
+
+use crate::config::{Config, InputData, SegmentConfig};
+use crate::core::output::{is_redacted, REDACTED_VALUE};
+use crate::core::segments::SegmentData;
+use crate::core::statusline::collect_configured;
+use crate::utils::write_atomic;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Raw stdin, as received
+pub const INPUT_FILE: &str = "input.json";
+/// Effective config after overrides
+pub const CONFIG_FILE: &str = "config.toml";
+/// Collected data of every shown segment, keyed by segment name
+pub const SEGMENTS_FILE: &str = "segments.json";
+/// Exactly the bytes written to stdout
+pub const OUTPUT_FILE: &str = "output.txt";
+
+/// Write a capture of one render into `dir` (created if missing)
+/// Sensitive options and metadata are masked as in machine output; replay
+/// uses the recorded data of network segments, so it never needs them
+pub fn record(
+    dir: &Path,
+    input: &str,
+    config: &Config,
+    segments: &[(SegmentConfig, SegmentData)],
+    output: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(dir)?;
+
+    let segments: HashMap<String, SegmentData> = segments
+        .iter()
+        .map(|(segment_config, data)| {
+            let mut data = data.clone();
+            for (key, value) in data.metadata.iter_mut() {
+                if is_redacted(key, &config.redact) {
+                    *value = REDACTED_VALUE.to_string();
+                }
+            }
+            (segment_config.key(), data)
+        })
+        .collect();
+
+    let mut config = config.clone();
+    for segment_config in &mut config.segments {
+        for (key, value) in segment_config.options.iter_mut() {
+            if is_redacted(key, &config.redact) {
+                *value = serde_json::Value::String(REDACTED_VALUE.to_string());
+            }
+        }
+    }
+
+    write_atomic(dir.join(INPUT_FILE), input.as_bytes())?;
+    write_atomic(
+        dir.join(CONFIG_FILE),
+        toml::to_string_pretty(&config)?.as_bytes(),
+    )?;
+    write_atomic(
+        dir.join(SEGMENTS_FILE),
+        serde_json::to_string_pretty(&segments)?.as_bytes(),
+    )?;
+    write_atomic(dir.join(OUTPUT_FILE), output.as_bytes())?;
+
+    Ok(())
+}
+
+/// A capture loaded back from a `--record` directory
+pub struct Replay {
+    pub input: InputData,
+    pub config: Config,
+    segments: HashMap<String, SegmentData>,
+}
+
+impl Replay {
+    pub fn load(dir: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let input = std::fs::read_to_string(dir.join(INPUT_FILE))?;
+        let config = std::fs::read_to_string(dir.join(CONFIG_FILE))?;
+        let segments = std::fs::read_to_string(dir.join(SEGMENTS_FILE))?;
+
+        Ok(Self {
+            input: InputData::parse(&input, false)?,
+            config: toml::from_str(&config)?,
+            segments: serde_json::from_str(&segments)?,
+        })
+    }
+
+    /// Collect the recorded config's segments; network segments use their
+    /// recorded data (absent means they were hidden), the rest run again
+    pub fn collect(&self) -> Vec<(SegmentConfig, SegmentData)> {
+        self.config
+            .segments
+            .iter()
+            .filter_map(|segment_config| {
+                let uses_network = crate::core::registry::create_segment(segment_config)
+                    .is_some_and(|segment| segment.uses_network());
+                let data = if uses_network {
+                    self.segments.get(&segment_config.key()).cloned()
+                } else {
+                    collect_configured(segment_config, &self.input)
+                };
+                data.map(|data| (segment_config.clone(), data))
+            })
+            .collect()
+    }
+
+    /// Output of the recorded render, to compare a replay against
+    pub fn recorded_output(dir: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(dir.join(OUTPUT_FILE))
+    }
+}
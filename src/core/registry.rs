@@ -0,0 +1,262 @@
+//! Segment registry
+//!
+//! Every segment type is created through a factory registered under its
+//! name: the config name of its id (`git`, `new_api_cost`, ...) for the
+//! built-in segments, or any other name for segments added by code embedding
+//! this crate. A config entry with `id = "custom"` picks a registered segment
+//! by its `name`; registering a built-in name replaces that segment.
+//! `collect_all_segments` builds each configured segment through
+//! [`create_segment`].
+//!
+//! ```
+//! use ccometixline::config::{Config, InputData, SegmentId};
+//! use ccometixline::core::register_segment;
+//! use ccometixline::core::segments::{Segment, SegmentData};
+//! use ccometixline::core::statusline::collect_configured;
+//! use std::collections::HashMap;
+//!
+//! struct Weather;
+//!
+//! impl Segment for Weather {
+//!     fn collect(&self, _input: &InputData) -> Option<SegmentData> {
+//!         Some(SegmentData {
+//!             primary: "21°C".to_string(),
+//!             secondary: String::new(),
+//!             metadata: HashMap::new(),
+//!         })
+//!     }
+//!
+//!     fn id(&self) -> SegmentId {
+//!         SegmentId::Custom
+//!     }
+//! }
+//!
+//! register_segment("weather", |_config| Box::new(Weather));
+//!
+//! // [[segments]] entries pick it with `id = "custom"` and `name = "weather"`
+//! let mut segment_config = Config::default().segments[0].clone();
+//! segment_config.id = SegmentId::Custom;
+//! segment_config.name = Some("weather".to_string());
+//!
+//! let input: InputData = serde_json::from_str(
+//!     r#"{"model": "Sonnet 4", "workspace": {"current_dir": "."}, "transcript_path": ""}"#,
+//! )
+//! .unwrap();
+//! let data = collect_configured(&segment_config, &input).unwrap();
+//! assert_eq!(data.primary, "21°C");
+//! ```
+
+use crate::config::{SegmentConfig, SegmentId};
+use crate::core::segments::count_source::CountSource;
+use crate::core::segments::runtime_version::Runtime;
+use crate::core::segments::*;
+use crate::utils::Locale;
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+/// Factory building a segment from its configuration
+pub type SegmentFactory = Box<dyn Fn(&SegmentConfig) -> Box<dyn Segment> + Send + Sync>;
+
+static REGISTRY: OnceLock<RwLock<HashMap<String, SegmentFactory>>> = OnceLock::new();
+
+fn registry() -> &'static RwLock<HashMap<String, SegmentFactory>> {
+    REGISTRY.get_or_init(|| RwLock::new(builtin_factories()))
+}
+
+fn factory<F>(f: F) -> SegmentFactory
+where
+    F: Fn(&SegmentConfig) -> Box<dyn Segment> + Send + Sync + 'static,
+{
+    Box::new(f)
+}
+
+/// Register a segment factory under `name`, replacing any existing one
+pub fn register_segment<F>(name: &str, f: F)
+where
+    F: Fn(&SegmentConfig) -> Box<dyn Segment> + Send + Sync + 'static,
+{
+    if let Ok(mut registry) = registry().write() {
+        registry.insert(name.to_string(), factory(f));
+    }
+}
+
+/// Create the segment for a configuration, if a factory is registered for its name
+pub fn create_segment(config: &SegmentConfig) -> Option<Box<dyn Segment>> {
+    let registry = registry().read().ok()?;
+    registry.get(&config.key()).map(|factory| factory(config))
+}
+
+fn builtin_factories() -> HashMap<String, SegmentFactory> {
+    let mut factories = HashMap::new();
+
+    factories.insert(
+        SegmentId::Model.name(),
+        factory(|_| Box::new(ModelSegment::new())),
+    );
+    factories.insert(
+        SegmentId::Directory.name(),
+        factory(|_| Box::new(DirectorySegment::new())),
+    );
+    factories.insert(
+        SegmentId::Git.name(),
+        factory(|config| Box::new(GitSegment::new().with_config_from_options(&config.options))),
+    );
+    factories.insert(
+        SegmentId::ContextWindow.name(),
+        factory(|config| {
+            Box::new(ContextWindowSegment::new().with_config_from_options(&config.options))
+        }),
+    );
+    factories.insert(
+        SegmentId::Usage.name(),
+        factory(|config| Box::new(UsageSegment::new().with_config_from_options(&config.options))),
+    );
+    factories.insert(
+        SegmentId::Cost.name(),
+        factory(|config| {
+            let locale = config
+                .options
+                .get("locale")
+                .and_then(|v| v.as_str())
+                .and_then(Locale::from_name)
+                .unwrap_or_default();
+            Box::new(CostSegment::new().with_locale(locale))
+        }),
+    );
+    factories.insert(
+        SegmentId::Session.name(),
+        factory(|_| Box::new(SessionSegment::new())),
+    );
+    factories.insert(
+        SegmentId::OutputStyle.name(),
+        factory(|_| Box::new(OutputStyleSegment::new())),
+    );
+    factories.insert(
+        SegmentId::Update.name(),
+        factory(|_| Box::new(UpdateSegment::new())),
+    );
+    factories.insert(
+        SegmentId::NewApiCost.name(),
+        factory(|config| {
+            Box::new(NewApiCostSegment::new().with_config_from_options(&config.options))
+        }),
+    );
+    factories.insert(
+        SegmentId::RuntimeVersion.name(),
+        factory(|config| {
+            let runtime = config
+                .options
+                .get("language")
+                .and_then(|v| v.as_str())
+                .and_then(Runtime::parse);
+            Box::new(
+                RuntimeVersionSegment::new()
+                    .with_runtime(runtime)
+                    .with_timeout(io_timeout(&config.options, DEFAULT_COMMAND_TIMEOUT)),
+            )
+        }),
+    );
+    factories.insert(
+        SegmentId::Notify.name(),
+        factory(|config| {
+            Box::new(
+                NotifySegment::new()
+                    .with_source(CountSource::from_options(&config.options))
+                    .with_timeout(io_timeout(&config.options, DEFAULT_COMMAND_TIMEOUT)),
+            )
+        }),
+    );
+    factories.insert(
+        SegmentId::Secrets.name(),
+        factory(|config| Box::new(SecretsSegment::new().with_config_from_options(&config.options))),
+    );
+    factories.insert(
+        SegmentId::Host.name(),
+        factory(|config| Box::new(HostSegment::new().with_config_from_options(&config.options))),
+    );
+    factories.insert(
+        SegmentId::Tmux.name(),
+        factory(|config| Box::new(TmuxSegment::new().with_config_from_options(&config.options))),
+    );
+    factories.insert(
+        SegmentId::ExitCode.name(),
+        factory(|config| {
+            Box::new(ExitCodeSegment::new().with_config_from_options(&config.options))
+        }),
+    );
+    factories.insert(
+        SegmentId::Plan.name(),
+        factory(|config| Box::new(PlanSegment::new().with_config_from_options(&config.options))),
+    );
+
+    factories
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, InputData, StyleMode};
+    use crate::core::statusline::{collect_all_segments, StatusLineGenerator};
+    use crate::test_support::{input_data, segment_config, strip_ansi};
+
+    struct Greeting(String);
+
+    impl Segment for Greeting {
+        fn collect(&self, _input: &InputData) -> Option<SegmentData> {
+            Some(SegmentData {
+                primary: self.0.clone(),
+                secondary: String::new(),
+                metadata: HashMap::new(),
+            })
+        }
+
+        fn id(&self) -> SegmentId {
+            SegmentId::Custom
+        }
+    }
+
+    fn custom_config(name: &str) -> SegmentConfig {
+        let mut config = segment_config(SegmentId::Custom);
+        config.name = Some(name.to_string());
+        config
+    }
+
+    #[test]
+    fn registered_custom_segment_renders() {
+        register_segment("test_greeting", |config| {
+            let text = config
+                .options
+                .get("text")
+                .and_then(|v| v.as_str())
+                .unwrap_or("hello");
+            Box::new(Greeting(text.to_string()))
+        });
+
+        let mut config = Config::default();
+        config.style.mode = StyleMode::Plain;
+        config.style.separator = " | ".to_string();
+        let mut custom = custom_config("test_greeting");
+        custom
+            .options
+            .insert("text".to_string(), serde_json::json!("hi there"));
+        config.segments = vec![custom];
+        config.check().unwrap();
+
+        let segments = collect_all_segments(&config, &input_data(serde_json::json!({})));
+        let line = StatusLineGenerator::new(config).generate(segments);
+        assert_eq!(strip_ansi(&line), "* hi there");
+    }
+
+    #[test]
+    fn unregistered_name_creates_nothing() {
+        assert!(create_segment(&custom_config("test_never_registered")).is_none());
+        assert!(create_segment(&segment_config(SegmentId::Custom)).is_none());
+    }
+
+    #[test]
+    fn builtins_are_registered_by_name() {
+        let segment = create_segment(&segment_config(SegmentId::NewApiCost)).unwrap();
+        assert_eq!(segment.id(), SegmentId::NewApiCost);
+        assert!(registry().read().unwrap().contains_key("new_api_cost"));
+    }
+}
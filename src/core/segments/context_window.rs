@@ -1,16 +1,146 @@
+use super::newapi_cost::BudgetBand;
 use super::{Segment, SegmentData};
-use crate::config::{InputData, ModelConfig, SegmentId, TranscriptEntry};
+use crate::config::{AnsiColor, InputData, ModelConfig, SegmentId, TranscriptEntry};
 use std::collections::HashMap;
 use std::fs;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 
-#[derive(Default)]
-pub struct ContextWindowSegment;
+/// Default number of bytes read from the end of a transcript (2 MiB)
+const DEFAULT_SCAN_LIMIT: u64 = 2 * 1024 * 1024;
+
+/// Default number of cells in the `bar` display
+const DEFAULT_BAR_WIDTH: usize = 8;
+
+/// How the context usage is shown
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ContextDisplay {
+    /// `60% · 120k tokens`
+    #[default]
+    Text,
+    /// `[█████░░░] 60%`, with the token count as secondary text
+    Bar,
+}
+
+pub struct ContextWindowSegment {
+    /// Bytes read from the end of the transcript; 0 reads the whole file
+    scan_limit: u64,
+    display: ContextDisplay,
+    bar_width: usize,
+    bar_filled: String,
+    bar_empty: String,
+    /// Fractions of the context limit that color the bar (default 0.8 / 0.95)
+    warning_threshold: Option<f64>,
+    critical_threshold: Option<f64>,
+    warning_color: Option<AnsiColor>,
+    critical_color: Option<AnsiColor>,
+}
+
+impl Default for ContextWindowSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl ContextWindowSegment {
     pub fn new() -> Self {
-        Self
+        Self {
+            scan_limit: DEFAULT_SCAN_LIMIT,
+            display: ContextDisplay::default(),
+            bar_width: DEFAULT_BAR_WIDTH,
+            bar_filled: "█".to_string(),
+            bar_empty: "░".to_string(),
+            warning_threshold: None,
+            critical_threshold: None,
+            warning_color: None,
+            critical_color: None,
+        }
+    }
+
+    pub fn with_scan_limit(mut self, scan_limit: u64) -> Self {
+        self.scan_limit = scan_limit;
+        self
+    }
+
+    /// Load settings from segment options HashMap
+    pub fn with_config_from_options(
+        mut self,
+        options: &HashMap<String, serde_json::Value>,
+    ) -> Self {
+        if let Some(scan_limit) = options
+            .get("transcript_scan_limit")
+            .and_then(|v| v.as_u64())
+        {
+            self.scan_limit = scan_limit;
+        }
+        if let Some(display) = options.get("display").and_then(|v| v.as_str()) {
+            self.display = match display {
+                "bar" => ContextDisplay::Bar,
+                _ => ContextDisplay::Text,
+            };
+        }
+        if let Some(width) = options.get("bar_width").and_then(|v| v.as_u64()) {
+            self.bar_width = (width as usize).max(1);
+        }
+        if let Some(glyph) = options.get("bar_filled").and_then(|v| v.as_str()) {
+            self.bar_filled = glyph.to_string();
+        }
+        if let Some(glyph) = options.get("bar_empty").and_then(|v| v.as_str()) {
+            self.bar_empty = glyph.to_string();
+        }
+        if let Some(value) = options.get("warning_threshold") {
+            self.warning_threshold = value.as_f64();
+        }
+        if let Some(value) = options.get("critical_threshold") {
+            self.critical_threshold = value.as_f64();
+        }
+        if let Some(value) = options.get("warning_color") {
+            self.warning_color = serde_json::from_value(value.clone()).ok();
+        }
+        if let Some(value) = options.get("critical_color") {
+            self.critical_color = serde_json::from_value(value.clone()).ok();
+        }
+        self
+    }
+
+    /// Render `[████░░░░]` for a usage percentage, rounding to the nearest cell
+    pub fn render_bar(&self, percentage: f64) -> String {
+        let fraction = (percentage / 100.0).clamp(0.0, 1.0);
+        let filled = ((fraction * self.bar_width as f64).round() as usize).min(self.bar_width);
+        format!(
+            "[{}{}]",
+            self.bar_filled.repeat(filled),
+            self.bar_empty.repeat(self.bar_width - filled)
+        )
+    }
+
+    /// Classify a usage percentage using the configured thresholds
+    pub fn usage_band(&self, percentage: f64) -> BudgetBand {
+        let fraction = percentage / 100.0;
+        if fraction >= self.critical_threshold.unwrap_or(0.95) {
+            BudgetBand::Critical
+        } else if fraction >= self.warning_threshold.unwrap_or(0.8) {
+            BudgetBand::Warning
+        } else {
+            BudgetBand::Normal
+        }
+    }
+
+    /// Text color override for a usage band (None keeps the theme color)
+    fn band_color(&self, band: BudgetBand) -> Option<AnsiColor> {
+        match band {
+            BudgetBand::Normal => None,
+            BudgetBand::Warning => Some(
+                self.warning_color
+                    .clone()
+                    .unwrap_or(AnsiColor::Color16 { c16: 11 }),
+            ),
+            BudgetBand::Critical => Some(
+                self.critical_color
+                    .clone()
+                    .unwrap_or(AnsiColor::Color16 { c16: 9 }),
+            ),
+        }
     }
 
     /// Get context limit for the specified model
@@ -25,7 +155,8 @@ impl Segment for ContextWindowSegment {
         // Dynamically determine context limit based on current model ID
         let context_limit = Self::get_context_limit_for_model(&input.model.id);
 
-        let context_used_token_opt = parse_transcript_usage(&input.transcript_path);
+        let context_used_token_opt =
+            parse_transcript_usage(&input.transcript_path, self.scan_limit);
 
         let (percentage_display, tokens_display) = match context_used_token_opt {
             Some(context_used_token) => {
@@ -71,9 +202,30 @@ impl Segment for ContextWindowSegment {
         metadata.insert("limit".to_string(), context_limit.to_string());
         metadata.insert("model".to_string(), input.model.id.clone());
 
+        let (primary, secondary) = match self.display {
+            ContextDisplay::Text => (
+                format!("{} · {} tokens", percentage_display, tokens_display),
+                String::new(),
+            ),
+            ContextDisplay::Bar => {
+                let rate = context_used_token_opt
+                    .map(|used| used as f64 / context_limit as f64 * 100.0)
+                    .unwrap_or(0.0);
+                if let Some(color) = self.band_color(self.usage_band(rate)) {
+                    if let Ok(color_json) = serde_json::to_string(&color) {
+                        metadata.insert("dynamic_text_color".to_string(), color_json);
+                    }
+                }
+                (
+                    format!("{} {}", self.render_bar(rate), percentage_display),
+                    format!("{} tokens", tokens_display),
+                )
+            }
+        };
+
         Some(SegmentData {
-            primary: format!("{} · {} tokens", percentage_display, tokens_display),
-            secondary: String::new(),
+            primary,
+            secondary,
             metadata,
         })
     }
@@ -83,17 +235,17 @@ impl Segment for ContextWindowSegment {
     }
 }
 
-fn parse_transcript_usage<P: AsRef<Path>>(transcript_path: P) -> Option<u32> {
+fn parse_transcript_usage<P: AsRef<Path>>(transcript_path: P, scan_limit: u64) -> Option<u32> {
     let path = transcript_path.as_ref();
 
     // Try to parse from current transcript file
-    if let Some(usage) = try_parse_transcript_file(path) {
+    if let Some(usage) = try_parse_transcript_file(path, scan_limit) {
         return Some(usage);
     }
 
     // If file doesn't exist, try to find usage from project history
     if !path.exists() {
-        if let Some(usage) = try_find_usage_from_project_history(path) {
+        if let Some(usage) = try_find_usage_from_project_history(path, scan_limit) {
             return Some(usage);
         }
     }
@@ -101,13 +253,35 @@ fn parse_transcript_usage<P: AsRef<Path>>(transcript_path: P) -> Option<u32> {
     None
 }
 
-fn try_parse_transcript_file(path: &Path) -> Option<u32> {
-    let file = fs::File::open(path).ok()?;
-    let reader = BufReader::new(file);
-    let lines: Vec<String> = reader
-        .lines()
-        .collect::<Result<Vec<_>, _>>()
-        .unwrap_or_default();
+/// Lines of the last `scan_limit` bytes of a file (all lines when 0)
+/// Seeks instead of reading the whole file, dropping the partial first line
+fn read_tail_lines(path: &Path, scan_limit: u64) -> Option<Vec<String>> {
+    let mut file = fs::File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+
+    if scan_limit == 0 || len <= scan_limit {
+        let reader = BufReader::new(file);
+        return Some(
+            reader
+                .lines()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap_or_default(),
+        );
+    }
+
+    file.seek(SeekFrom::Start(len - scan_limit)).ok()?;
+    let mut tail = Vec::with_capacity(scan_limit as usize);
+    file.take(scan_limit).read_to_end(&mut tail).ok()?;
+
+    // The seek most likely landed mid-line; skip to the first full line
+    let first_newline = tail.iter().position(|b| *b == b'\n')?;
+    let tail = String::from_utf8_lossy(&tail[first_newline + 1..]);
+
+    Some(tail.lines().map(|line| line.to_string()).collect())
+}
+
+fn try_parse_transcript_file(path: &Path, scan_limit: u64) -> Option<u32> {
+    let lines = read_tail_lines(path, scan_limit)?;
 
     if lines.is_empty() {
         return None;
@@ -233,7 +407,7 @@ fn find_assistant_message_by_uuid(lines: &[String], target_uuid: &str) -> Option
     None
 }
 
-fn try_find_usage_from_project_history(transcript_path: &Path) -> Option<u32> {
+fn try_find_usage_from_project_history(transcript_path: &Path, scan_limit: u64) -> Option<u32> {
     let project_dir = transcript_path.parent()?;
 
     // Find the most recent session file in the project directory
@@ -263,10 +437,123 @@ fn try_find_usage_from_project_history(transcript_path: &Path) -> Option<u32> {
 
     // Try to find usage from the most recent session
     for session_path in &session_files {
-        if let Some(usage) = try_parse_transcript_file(session_path) {
+        if let Some(usage) = try_parse_transcript_file(session_path, scan_limit) {
             return Some(usage);
         }
     }
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn assistant_line(input_tokens: u32) -> String {
+        format!(
+            r#"{{"type":"assistant","message":{{"usage":{{"input_tokens":{}}}}}}}"#,
+            input_tokens
+        )
+    }
+
+    /// A ~4MB transcript: an early assistant entry, filler, then `tail`
+    fn large_transcript(dir: &TempDir, tail: &[String]) -> std::path::PathBuf {
+        let filler = r#"{"type":"user","message":{"content":"padding padding padding"}}"#;
+        let mut contents = assistant_line(111) + "\n";
+        while contents.len() < 4 * 1024 * 1024 {
+            contents.push_str(filler);
+            contents.push('\n');
+        }
+        for line in tail {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+        dir.write("session.jsonl", &contents)
+    }
+
+    #[test]
+    fn tail_read_stays_within_the_scan_limit() {
+        let dir = TempDir::new("transcript");
+        let path = large_transcript(&dir, &[assistant_line(4242)]);
+
+        let lines = read_tail_lines(&path, 4096).unwrap();
+        let bytes: usize = lines.iter().map(|line| line.len() + 1).sum();
+        assert!(bytes <= 4096, "read {bytes} bytes");
+        assert_eq!(lines.last(), Some(&assistant_line(4242)));
+        // The partial line the seek landed in is dropped, so every line parses
+        assert!(lines
+            .iter()
+            .all(|line| serde_json::from_str::<TranscriptEntry>(line).is_ok()));
+    }
+
+    #[test]
+    fn large_transcript_uses_the_latest_usage_in_the_tail() {
+        let dir = TempDir::new("transcript");
+        let path = large_transcript(&dir, &[assistant_line(1000), assistant_line(4242)]);
+
+        assert_eq!(try_parse_transcript_file(&path, 64 * 1024), Some(4242));
+    }
+
+    #[test]
+    fn usage_before_the_tail_is_not_scanned() {
+        let dir = TempDir::new("transcript");
+        let path = large_transcript(&dir, &[]);
+
+        assert_eq!(try_parse_transcript_file(&path, 64 * 1024), None);
+        // Without a limit the whole file is read and the early entry is found
+        assert_eq!(try_parse_transcript_file(&path, 0), Some(111));
+    }
+
+    fn bar_segment(options: serde_json::Value) -> ContextWindowSegment {
+        let options: HashMap<String, serde_json::Value> = serde_json::from_value(options).unwrap();
+        ContextWindowSegment::new().with_config_from_options(&options)
+    }
+
+    #[test]
+    fn percentages_map_to_bar_fill() {
+        let segment = bar_segment(serde_json::json!({"display": "bar", "bar_width": 8}));
+        let cases = [
+            (0.0, "[░░░░░░░░]"),
+            (5.0, "[░░░░░░░░]"),
+            (12.5, "[█░░░░░░░]"),
+            (50.0, "[████░░░░]"),
+            (60.0, "[█████░░░]"),
+            (99.0, "[████████]"),
+            (100.0, "[████████]"),
+            (140.0, "[████████]"),
+            (-10.0, "[░░░░░░░░]"),
+        ];
+        for (percentage, expected) in cases {
+            assert_eq!(segment.render_bar(percentage), expected, "{percentage}%");
+        }
+    }
+
+    #[test]
+    fn bar_width_and_glyphs_are_configurable() {
+        let segment = bar_segment(serde_json::json!({
+            "bar_width": 4,
+            "bar_filled": "#",
+            "bar_empty": "-",
+        }));
+        assert_eq!(segment.render_bar(50.0), "[##--]");
+
+        let narrow = bar_segment(serde_json::json!({"bar_width": 0}));
+        assert_eq!(narrow.render_bar(100.0), "[█]");
+    }
+
+    #[test]
+    fn usage_bands_follow_the_thresholds() {
+        let segment = ContextWindowSegment::new();
+        assert_eq!(segment.usage_band(79.9), BudgetBand::Normal);
+        assert_eq!(segment.usage_band(80.0), BudgetBand::Warning);
+        assert_eq!(segment.usage_band(95.0), BudgetBand::Critical);
+
+        let custom = bar_segment(serde_json::json!({
+            "warning_threshold": 0.5,
+            "critical_threshold": 0.7,
+        }));
+        assert_eq!(custom.usage_band(60.0), BudgetBand::Warning);
+        assert_eq!(custom.usage_band(70.0), BudgetBand::Critical);
+    }
+}
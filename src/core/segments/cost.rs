@@ -1,13 +1,21 @@
 use super::{Segment, SegmentData};
 use crate::config::{InputData, SegmentId};
+use crate::utils::Locale;
 use std::collections::HashMap;
 
 #[derive(Default)]
-pub struct CostSegment;
+pub struct CostSegment {
+    locale: Locale,
+}
 
 impl CostSegment {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
     }
 }
 
@@ -18,9 +26,9 @@ impl Segment for CostSegment {
         // Primary display: total cost
         let primary = if let Some(cost) = cost_data.total_cost_usd {
             if cost == 0.0 || cost < 0.01 {
-                "$0".to_string()
+                self.locale.place_currency("0", "$")
             } else {
-                format!("${:.2}", cost)
+                self.locale.format_currency(cost, "$", 2)
             }
         } else {
             return None;
@@ -34,11 +42,16 @@ impl Segment for CostSegment {
             metadata.insert("cost".to_string(), cost.to_string());
         }
 
-        Some(SegmentData {
+        let mut data = SegmentData {
             primary,
             secondary,
             metadata,
-        })
+        };
+        if let Some(cost) = cost_data.total_cost_usd.filter(|cost| *cost >= 0.01) {
+            data.set_primary_alternatives(&self.locale.currency_candidates(cost, "$", 2));
+        }
+
+        Some(data)
     }
 
     fn id(&self) -> SegmentId {
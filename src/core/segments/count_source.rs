@@ -0,0 +1,100 @@
+use crate::utils::process::output_with_timeout;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// Where a count-style segment reads its number from
+#[derive(Debug, Clone, PartialEq)]
+pub enum CountSource {
+    /// Shell command whose output starts with the count (e.g. `notmuch count tag:unread`)
+    Command(String),
+    /// File whose contents start with the count
+    File(PathBuf),
+}
+
+impl CountSource {
+    /// Build from the `command` or `file` segment options; `command` wins if both are set
+    pub fn from_options(options: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        let option = |key: &str| {
+            options
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+        };
+
+        if let Some(command) = option("command") {
+            return Some(CountSource::Command(command));
+        }
+        option("file").map(|file| CountSource::File(expand_home(&file)))
+    }
+
+    /// Read the current count; relative files and commands resolve against `dir`
+    /// Commands are killed after `timeout`
+    pub fn read_count(&self, dir: &Path, timeout: Duration) -> Option<u64> {
+        let text = match self {
+            CountSource::Command(command) => {
+                let output =
+                    output_with_timeout(shell_command(command).current_dir(dir), timeout).ok()?;
+                if !output.status.success() {
+                    return None;
+                }
+                String::from_utf8_lossy(&output.stdout).to_string()
+            }
+            CountSource::File(path) => std::fs::read_to_string(dir.join(path)).ok()?,
+        };
+
+        parse_count(&text)
+    }
+}
+
+/// First whitespace-separated token of the text as a count
+pub fn parse_count(text: &str) -> Option<u64> {
+    text.split_whitespace().next()?.parse().ok()
+}
+
+fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_option_wins_over_file() {
+        let options: HashMap<String, serde_json::Value> = serde_json::from_value(
+            serde_json::json!({"command": "notmuch count", "file": "unread"}),
+        )
+        .unwrap();
+        assert_eq!(
+            CountSource::from_options(&options),
+            Some(CountSource::Command("notmuch count".into()))
+        );
+
+        let options: HashMap<String, serde_json::Value> =
+            serde_json::from_value(serde_json::json!({"command": " ", "file": "unread"})).unwrap();
+        assert_eq!(
+            CountSource::from_options(&options),
+            Some(CountSource::File("unread".into()))
+        );
+        assert_eq!(CountSource::from_options(&HashMap::new()), None);
+    }
+}
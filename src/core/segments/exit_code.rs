@@ -0,0 +1,233 @@
+use super::{Segment, SegmentData};
+use crate::config::{AnsiColor, InputData, SegmentId};
+use std::collections::HashMap;
+
+/// Where the last command's exit code is read from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExitCodeSource {
+    /// Environment variable set by the wrapper (default `CCLINE_EXIT_CODE`)
+    Env(String),
+    /// Top-level field of the stdin JSON
+    InputField(String),
+}
+
+impl Default for ExitCodeSource {
+    fn default() -> Self {
+        ExitCodeSource::Env("CCLINE_EXIT_CODE".to_string())
+    }
+}
+
+impl ExitCodeSource {
+    /// Build from the `input_field` or `env` segment options; `input_field` wins if both are set
+    pub fn from_options(options: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        let option = |key: &str| {
+            options
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+        };
+
+        if let Some(field) = option("input_field") {
+            return Some(ExitCodeSource::InputField(field));
+        }
+        option("env").map(ExitCodeSource::Env)
+    }
+
+    /// Exit code from this source; numbers and numeric strings are accepted
+    pub fn read(&self, input: &InputData) -> Option<i64> {
+        match self {
+            ExitCodeSource::Env(name) => std::env::var(name).ok()?.trim().parse().ok(),
+            ExitCodeSource::InputField(field) => match input.extra.get(field)? {
+                serde_json::Value::Number(number) => number.as_i64(),
+                serde_json::Value::String(text) => text.trim().parse().ok(),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Shows the exit code of the last shell command: `✗ 1` on failure and,
+/// when `show_success` is set, `✓` on success
+pub struct ExitCodeSegment {
+    source: ExitCodeSource,
+    show_success: bool,
+    success_glyph: String,
+    failure_glyph: String,
+    success_color: AnsiColor,
+    failure_color: AnsiColor,
+}
+
+impl Default for ExitCodeSegment {
+    fn default() -> Self {
+        Self {
+            source: ExitCodeSource::default(),
+            show_success: false,
+            success_glyph: "✓".to_string(),
+            failure_glyph: "✗".to_string(),
+            success_color: AnsiColor::Color16 { c16: 10 },
+            failure_color: AnsiColor::Color16 { c16: 9 },
+        }
+    }
+}
+
+impl ExitCodeSegment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load settings from segment options HashMap
+    pub fn with_config_from_options(
+        mut self,
+        options: &HashMap<String, serde_json::Value>,
+    ) -> Self {
+        if let Some(source) = ExitCodeSource::from_options(options) {
+            self.source = source;
+        }
+        if let Some(value) = options.get("show_success").and_then(|v| v.as_bool()) {
+            self.show_success = value;
+        }
+        if let Some(glyph) = options.get("success_glyph").and_then(|v| v.as_str()) {
+            self.success_glyph = glyph.to_string();
+        }
+        if let Some(glyph) = options.get("failure_glyph").and_then(|v| v.as_str()) {
+            self.failure_glyph = glyph.to_string();
+        }
+        if let Some(color) = options
+            .get("success_color")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+        {
+            self.success_color = color;
+        }
+        if let Some(color) = options
+            .get("failure_color")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+        {
+            self.failure_color = color;
+        }
+        self
+    }
+
+    /// Text and color for an exit code, or None when a success stays hidden
+    pub fn render(&self, code: i64) -> Option<(String, &AnsiColor)> {
+        if code == 0 {
+            self.show_success
+                .then(|| (self.success_glyph.clone(), &self.success_color))
+        } else {
+            Some((
+                format!("{} {}", self.failure_glyph, code),
+                &self.failure_color,
+            ))
+        }
+    }
+}
+
+impl Segment for ExitCodeSegment {
+    fn collect(&self, input: &InputData) -> Option<SegmentData> {
+        let code = self.source.read(input)?;
+        let (primary, color) = self.render(code)?;
+
+        let mut metadata = HashMap::new();
+        metadata.insert("exit_code".to_string(), code.to_string());
+        if let Ok(color_json) = serde_json::to_string(color) {
+            metadata.insert("dynamic_text_color".to_string(), color_json);
+        }
+
+        Some(SegmentData {
+            primary,
+            secondary: String::new(),
+            metadata,
+        })
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::ExitCode
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{input_data, EnvGuard};
+
+    fn segment(options: serde_json::Value) -> ExitCodeSegment {
+        let options: HashMap<String, serde_json::Value> = serde_json::from_value(options).unwrap();
+        ExitCodeSegment::new().with_config_from_options(&options)
+    }
+
+    fn collect(segment: &ExitCodeSegment, code: serde_json::Value) -> Option<SegmentData> {
+        segment.collect(&input_data(serde_json::json!({ "last_exit": code })))
+    }
+
+    fn text_color(data: &SegmentData) -> AnsiColor {
+        serde_json::from_str(&data.metadata["dynamic_text_color"]).unwrap()
+    }
+
+    #[test]
+    fn success_is_hidden_by_default() {
+        let segment = segment(serde_json::json!({"input_field": "last_exit"}));
+        assert!(collect(&segment, serde_json::json!(0)).is_none());
+    }
+
+    #[test]
+    fn success_is_shown_in_green_when_enabled() {
+        let segment = segment(serde_json::json!({
+            "input_field": "last_exit",
+            "show_success": true,
+        }));
+        let data = collect(&segment, serde_json::json!("0")).unwrap();
+
+        assert_eq!(data.primary, "✓");
+        assert_eq!(data.metadata["exit_code"], "0");
+        assert_eq!(text_color(&data), AnsiColor::Color16 { c16: 10 });
+    }
+
+    #[test]
+    fn failure_shows_the_code_in_red() {
+        let default = segment(serde_json::json!({"input_field": "last_exit"}));
+        let data = collect(&default, serde_json::json!(127)).unwrap();
+
+        assert_eq!(data.primary, "✗ 127");
+        assert_eq!(text_color(&data), AnsiColor::Color16 { c16: 9 });
+
+        let custom = segment(serde_json::json!({
+            "input_field": "last_exit",
+            "failure_glyph": "!",
+            "failure_color": {"c256": 160},
+        }));
+        let data = collect(&custom, serde_json::json!(1)).unwrap();
+        assert_eq!(data.primary, "! 1");
+        assert_eq!(text_color(&data), AnsiColor::Color256 { c256: 160 });
+    }
+
+    #[test]
+    fn exit_code_is_read_from_the_configured_env_var() {
+        let mut env = EnvGuard::new();
+        env.set("MY_STATUS", " 2 ").remove("CCLINE_EXIT_CODE");
+        let input = input_data(serde_json::json!({}));
+
+        assert!(ExitCodeSegment::new().collect(&input).is_none());
+        let data = segment(serde_json::json!({"env": "MY_STATUS"}))
+            .collect(&input)
+            .unwrap();
+        assert_eq!(data.primary, "✗ 2");
+
+        env.set("CCLINE_EXIT_CODE", "3");
+        assert_eq!(
+            ExitCodeSegment::new().collect(&input).unwrap().primary,
+            "✗ 3"
+        );
+    }
+
+    #[test]
+    fn missing_or_unreadable_code_renders_nothing() {
+        let segment = segment(serde_json::json!({"input_field": "last_exit"}));
+
+        assert!(segment
+            .collect(&input_data(serde_json::json!({})))
+            .is_none());
+        assert!(collect(&segment, serde_json::json!("oops")).is_none());
+        assert!(collect(&segment, serde_json::json!(true)).is_none());
+    }
+}
@@ -1,7 +1,11 @@
 use super::{Segment, SegmentData};
-use crate::config::{InputData, SegmentId};
+use crate::config::{AnsiColor, InputData, SegmentId};
+use crate::utils::process::output_with_timeout;
+use crate::utils::Cache;
 use std::collections::HashMap;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::Duration;
 
 #[derive(Debug)]
 pub struct GitInfo {
@@ -10,6 +14,13 @@ pub struct GitInfo {
     pub ahead: u32,
     pub behind: u32,
     pub sha: Option<String>,
+    pub stash_count: Option<u32>,
+    pub last_commit_age: Option<u64>,
+    pub provider: Option<GitProvider>,
+    pub operation: Option<GitOperation>,
+    /// Name of the linked worktree, None in the main checkout
+    pub worktree: Option<String>,
+    pub changes: ChangeCounts,
 }
 
 #[derive(Debug, PartialEq)]
@@ -19,8 +30,175 @@ pub enum GitStatus {
     Conflicts,
 }
 
+/// Changed files in the working tree, staged or not, each counted once
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChangeCounts {
+    /// New files, including untracked ones
+    pub added: u32,
+    /// Modified, renamed, copied, retyped or conflicted files
+    pub modified: u32,
+    pub deleted: u32,
+}
+
+impl ChangeCounts {
+    /// Count entries of `git status --porcelain` (v1) output; each line is
+    /// `XY path`, with X the staged and Y the unstaged state
+    pub fn from_porcelain(text: &str) -> Self {
+        let mut counts = Self::default();
+        for line in text.lines() {
+            let Some(code) = line.get(..2) else {
+                continue;
+            };
+            let conflicted = code.contains('U') || code == "AA" || code == "DD";
+            if conflicted {
+                counts.modified += 1;
+            } else if code == "??" || code.contains('A') {
+                counts.added += 1;
+            } else if code.contains('D') {
+                counts.deleted += 1;
+            } else if code.contains(['M', 'R', 'C', 'T']) {
+                counts.modified += 1;
+            }
+        }
+        counts
+    }
+}
+
+/// Hosting platform of the `origin` remote
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GitProvider {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    Generic,
+}
+
+impl GitProvider {
+    /// Infer the provider from a remote URL (HTTPS, `ssh://` or scp-like `git@host:path`)
+    pub fn from_remote_url(url: &str) -> Self {
+        let host = Self::remote_host(url).unwrap_or_default();
+        if host.contains("github") {
+            GitProvider::GitHub
+        } else if host.contains("gitlab") {
+            GitProvider::GitLab
+        } else if host.contains("bitbucket") {
+            GitProvider::Bitbucket
+        } else {
+            GitProvider::Generic
+        }
+    }
+
+    /// Host part of a remote URL, lowercased
+    pub fn remote_host(url: &str) -> Option<String> {
+        let url = url.trim();
+        let authority = match url.split_once("://") {
+            Some((_, rest)) => rest.split('/').next()?,
+            // scp-like syntax: [user@]host:path
+            None => url.split_once(':')?.0,
+        };
+        let host_port = authority.rsplit('@').next()?;
+        let host = host_port.split(':').next()?;
+        if host.is_empty() {
+            None
+        } else {
+            Some(host.to_ascii_lowercase())
+        }
+    }
+
+    /// Icons for the plain and Nerd Font icon modes
+    pub fn icons(&self) -> (&'static str, &'static str) {
+        match self {
+            GitProvider::GitHub => ("🐙", "\u{f09b}"),
+            GitProvider::GitLab => ("🦊", "\u{f296}"),
+            GitProvider::Bitbucket => ("🪣", "\u{f171}"),
+            GitProvider::Generic => ("🌿", "\u{f1d3}"),
+        }
+    }
+}
+
+/// An operation left in progress in the repository
+#[derive(Debug, Clone, PartialEq)]
+pub enum GitOperation {
+    /// Rebase with (current step, total steps) when known
+    Rebase(Option<(u32, u32)>),
+    Am,
+    Merge,
+    CherryPick,
+    Revert,
+    Bisect,
+}
+
+impl GitOperation {
+    /// Detect an in-progress operation from the marker files git leaves in its directory
+    pub fn detect(git_dir: &Path) -> Option<Self> {
+        let read_number = |path: PathBuf| -> Option<u32> {
+            std::fs::read_to_string(path).ok()?.trim().parse().ok()
+        };
+
+        let rebase_merge = git_dir.join("rebase-merge");
+        if rebase_merge.is_dir() {
+            let progress =
+                read_number(rebase_merge.join("msgnum")).zip(read_number(rebase_merge.join("end")));
+            return Some(GitOperation::Rebase(progress));
+        }
+
+        let rebase_apply = git_dir.join("rebase-apply");
+        if rebase_apply.is_dir() {
+            if rebase_apply.join("applying").exists() {
+                return Some(GitOperation::Am);
+            }
+            let progress =
+                read_number(rebase_apply.join("next")).zip(read_number(rebase_apply.join("last")));
+            return Some(GitOperation::Rebase(progress));
+        }
+
+        if git_dir.join("MERGE_HEAD").exists() {
+            Some(GitOperation::Merge)
+        } else if git_dir.join("CHERRY_PICK_HEAD").exists() {
+            Some(GitOperation::CherryPick)
+        } else if git_dir.join("REVERT_HEAD").exists() {
+            Some(GitOperation::Revert)
+        } else if git_dir.join("BISECT_LOG").exists() {
+            Some(GitOperation::Bisect)
+        } else {
+            None
+        }
+    }
+
+    /// Short indicator, e.g. `REBASE 2/5`
+    pub fn label(&self) -> String {
+        match self {
+            GitOperation::Rebase(Some((step, total))) => format!("REBASE {}/{}", step, total),
+            GitOperation::Rebase(None) => "REBASE".to_string(),
+            GitOperation::Am => "AM".to_string(),
+            GitOperation::Merge => "MERGE".to_string(),
+            GitOperation::CherryPick => "CHERRY-PICK".to_string(),
+            GitOperation::Revert => "REVERT".to_string(),
+            GitOperation::Bisect => "BISECT".to_string(),
+        }
+    }
+}
+
 pub struct GitSegment {
     show_sha: bool,
+    show_stash: bool,
+    show_commit_age: bool,
+    show_provider: bool,
+    show_operation: bool,
+    show_worktree: bool,
+    show_added: bool,
+    show_modified: bool,
+    show_deleted: bool,
+    /// Draw the change counts in their own colors
+    color_changes: bool,
+    added_color: AnsiColor,
+    modified_color: AnsiColor,
+    deleted_color: AnsiColor,
+    operation_color: AnsiColor,
+    /// Seconds to reuse rendered data for an unchanged repository (0 disables)
+    cache_ttl: u64,
+    /// Limit on each git command
+    timeout: Duration,
 }
 
 impl Default for GitSegment {
@@ -31,7 +209,24 @@ impl Default for GitSegment {
 
 impl GitSegment {
     pub fn new() -> Self {
-        Self { show_sha: false }
+        Self {
+            show_sha: false,
+            show_stash: false,
+            show_commit_age: false,
+            show_provider: false,
+            show_operation: false,
+            show_worktree: false,
+            show_added: false,
+            show_modified: false,
+            show_deleted: false,
+            color_changes: true,
+            added_color: AnsiColor::Color16 { c16: 10 },
+            modified_color: AnsiColor::Color16 { c16: 11 },
+            deleted_color: AnsiColor::Color16 { c16: 9 },
+            operation_color: AnsiColor::Color16 { c16: 11 },
+            cache_ttl: 0,
+            timeout: super::DEFAULT_COMMAND_TIMEOUT,
+        }
     }
 
     pub fn with_sha(mut self, show_sha: bool) -> Self {
@@ -39,6 +234,82 @@ impl GitSegment {
         self
     }
 
+    pub fn with_stash(mut self, show_stash: bool) -> Self {
+        self.show_stash = show_stash;
+        self
+    }
+
+    pub fn with_commit_age(mut self, show_commit_age: bool) -> Self {
+        self.show_commit_age = show_commit_age;
+        self
+    }
+
+    pub fn with_provider(mut self, show_provider: bool) -> Self {
+        self.show_provider = show_provider;
+        self
+    }
+
+    pub fn with_operation(mut self, show_operation: bool) -> Self {
+        self.show_operation = show_operation;
+        self
+    }
+
+    pub fn with_worktree(mut self, show_worktree: bool) -> Self {
+        self.show_worktree = show_worktree;
+        self
+    }
+
+    /// Toggle the added, modified and deleted file counts
+    pub fn with_changes(mut self, added: bool, modified: bool, deleted: bool) -> Self {
+        self.show_added = added;
+        self.show_modified = modified;
+        self.show_deleted = deleted;
+        self
+    }
+
+    /// Load toggles from segment options HashMap
+    pub fn with_config_from_options(self, options: &HashMap<String, serde_json::Value>) -> Self {
+        let flag = |key: &str| options.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+
+        let mut segment = self
+            .with_sha(flag("show_sha"))
+            .with_stash(flag("show_stash"))
+            .with_commit_age(flag("show_commit_age"))
+            .with_provider(flag("show_provider"))
+            .with_operation(flag("show_operation"))
+            .with_worktree(flag("show_worktree"))
+            .with_changes(
+                flag("show_added"),
+                flag("show_modified"),
+                flag("show_deleted"),
+            );
+        let color = |key: &str| {
+            options
+                .get(key)
+                .and_then(|v| serde_json::from_value::<AnsiColor>(v.clone()).ok())
+        };
+        if let Some(color) = color("operation_color") {
+            segment.operation_color = color;
+        }
+        if let Some(value) = options.get("color_changes").and_then(|v| v.as_bool()) {
+            segment.color_changes = value;
+        }
+        if let Some(color) = color("added_color") {
+            segment.added_color = color;
+        }
+        if let Some(color) = color("modified_color") {
+            segment.modified_color = color;
+        }
+        if let Some(color) = color("deleted_color") {
+            segment.deleted_color = color;
+        }
+        segment.timeout = super::io_timeout(options, segment.timeout);
+        if let Some(cache_ttl) = options.get("cache_ttl").and_then(|v| v.as_u64()) {
+            segment.cache_ttl = cache_ttl;
+        }
+        segment
+    }
+
     fn get_git_info(&self, working_dir: &str) -> Option<GitInfo> {
         if !self.is_git_repository(working_dir) {
             return None;
@@ -47,13 +318,43 @@ impl GitSegment {
         let branch = self
             .get_branch(working_dir)
             .unwrap_or_else(|| "detached".to_string());
-        let status = self.get_status(working_dir);
+        let (status, changes) = self.get_status(working_dir);
         let (ahead, behind) = self.get_ahead_behind(working_dir);
         let sha = if self.show_sha {
             self.get_sha(working_dir)
         } else {
             None
         };
+        let stash_count = if self.show_stash {
+            Some(self.get_stash_count(working_dir))
+        } else {
+            None
+        };
+        let last_commit_age = if self.show_commit_age {
+            self.get_last_commit_age(working_dir)
+        } else {
+            None
+        };
+
+        let provider = if self.show_provider {
+            self.get_origin_url(working_dir)
+                .map(|url| GitProvider::from_remote_url(&url))
+        } else {
+            None
+        };
+
+        let operation = if self.show_operation {
+            self.get_git_dir(working_dir)
+                .and_then(|git_dir| GitOperation::detect(&git_dir))
+        } else {
+            None
+        };
+
+        let worktree = if self.show_worktree {
+            self.get_worktree(working_dir)
+        } else {
+            None
+        };
 
         Some(GitInfo {
             branch,
@@ -61,24 +362,31 @@ impl GitSegment {
             ahead,
             behind,
             sha,
+            stash_count,
+            last_commit_age,
+            provider,
+            operation,
+            worktree,
+            changes,
         })
     }
 
+    /// Run git in `working_dir`, killed after the configured timeout
+    fn git(&self, working_dir: &str, args: &[&str]) -> std::io::Result<Output> {
+        output_with_timeout(
+            Command::new("git").args(args).current_dir(working_dir),
+            self.timeout,
+        )
+    }
+
     fn is_git_repository(&self, working_dir: &str) -> bool {
-        Command::new("git")
-            .args(["rev-parse", "--git-dir"])
-            .current_dir(working_dir)
-            .output()
+        self.git(working_dir, &["rev-parse", "--git-dir"])
             .map(|output| output.status.success())
             .unwrap_or(false)
     }
 
     fn get_branch(&self, working_dir: &str) -> Option<String> {
-        if let Ok(output) = Command::new("git")
-            .args(["branch", "--show-current"])
-            .current_dir(working_dir)
-            .output()
-        {
+        if let Ok(output) = self.git(working_dir, &["branch", "--show-current"]) {
             if output.status.success() {
                 let branch = String::from_utf8(output.stdout).ok()?.trim().to_string();
                 if !branch.is_empty() {
@@ -87,11 +395,7 @@ impl GitSegment {
             }
         }
 
-        if let Ok(output) = Command::new("git")
-            .args(["symbolic-ref", "--short", "HEAD"])
-            .current_dir(working_dir)
-            .output()
-        {
+        if let Ok(output) = self.git(working_dir, &["symbolic-ref", "--short", "HEAD"]) {
             if output.status.success() {
                 let branch = String::from_utf8(output.stdout).ok()?.trim().to_string();
                 if !branch.is_empty() {
@@ -103,30 +407,29 @@ impl GitSegment {
         None
     }
 
-    fn get_status(&self, working_dir: &str) -> GitStatus {
-        let output = Command::new("git")
-            .args(["status", "--porcelain"])
-            .current_dir(working_dir)
-            .output();
+    fn get_status(&self, working_dir: &str) -> (GitStatus, ChangeCounts) {
+        let output = self.git(working_dir, &["status", "--porcelain"]);
 
         match output {
             Ok(output) if output.status.success() => {
                 let status_text = String::from_utf8(output.stdout).unwrap_or_default();
+                let changes = ChangeCounts::from_porcelain(&status_text);
 
                 if status_text.trim().is_empty() {
-                    return GitStatus::Clean;
+                    return (GitStatus::Clean, changes);
                 }
 
-                if status_text.contains("UU")
+                let status = if status_text.contains("UU")
                     || status_text.contains("AA")
                     || status_text.contains("DD")
                 {
                     GitStatus::Conflicts
                 } else {
                     GitStatus::Dirty
-                }
+                };
+                (status, changes)
             }
-            _ => GitStatus::Clean,
+            _ => (GitStatus::Clean, ChangeCounts::default()),
         }
     }
 
@@ -137,10 +440,7 @@ impl GitSegment {
     }
 
     fn get_commit_count(&self, working_dir: &str, range: &str) -> u32 {
-        let output = Command::new("git")
-            .args(["rev-list", "--count", range])
-            .current_dir(working_dir)
-            .output();
+        let output = self.git(working_dir, &["rev-list", "--count", range]);
 
         match output {
             Ok(output) if output.status.success() => String::from_utf8(output.stdout)
@@ -152,10 +452,8 @@ impl GitSegment {
     }
 
     fn get_sha(&self, working_dir: &str) -> Option<String> {
-        let output = Command::new("git")
-            .args(["rev-parse", "--short=7", "HEAD"])
-            .current_dir(working_dir)
-            .output()
+        let output = self
+            .git(working_dir, &["rev-parse", "--short=7", "HEAD"])
             .ok()?;
 
         if output.status.success() {
@@ -169,14 +467,176 @@ impl GitSegment {
             None
         }
     }
-}
 
-impl Segment for GitSegment {
-    fn collect(&self, input: &InputData) -> Option<SegmentData> {
-        let git_info = self.get_git_info(&input.workspace.current_dir)?;
+    fn get_stash_count(&self, working_dir: &str) -> u32 {
+        let output = self.git(working_dir, &["stash", "list"]);
+
+        match output {
+            Ok(output) if output.status.success() => String::from_utf8(output.stdout)
+                .map(|s| s.lines().filter(|line| !line.trim().is_empty()).count() as u32)
+                .unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Absolute path of the repository's git directory (handles worktrees)
+    fn get_git_dir(&self, working_dir: &str) -> Option<PathBuf> {
+        let output = self
+            .git(working_dir, &["rev-parse", "--absolute-git-dir"])
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        let git_dir = String::from_utf8(output.stdout).ok()?.trim().to_string();
+        if git_dir.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(git_dir))
+        }
+    }
+
+    /// Name of the linked worktree `working_dir` belongs to
+    /// A linked worktree has a `.git` file (`gitdir: <repo>/.git/worktrees/<name>`)
+    /// instead of a directory; submodules, whose `.git` file points into
+    /// `.git/modules`, don't count
+    fn get_worktree(&self, working_dir: &str) -> Option<String> {
+        let output = self
+            .git(working_dir, &["rev-parse", "--show-toplevel"])
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let toplevel = String::from_utf8(output.stdout).ok()?.trim().to_string();
+        Self::worktree_name(&Path::new(&toplevel).join(".git"))
+    }
+
+    /// Worktree name from a checkout's `.git` entry, None unless it's a
+    /// file pointing into a `worktrees` directory
+    pub fn worktree_name(dot_git: &Path) -> Option<String> {
+        if !dot_git.is_file() {
+            return None;
+        }
+
+        let content = std::fs::read_to_string(dot_git).ok()?;
+        let gitdir = content
+            .lines()
+            .find_map(|line| line.strip_prefix("gitdir:"))?
+            .trim();
+        let gitdir = Path::new(gitdir);
+
+        let parent = gitdir.parent()?.file_name()?;
+        if parent != "worktrees" {
+            return None;
+        }
+        gitdir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+    }
+
+    fn get_origin_url(&self, working_dir: &str) -> Option<String> {
+        let output = self
+            .git(working_dir, &["remote", "get-url", "origin"])
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        let url = String::from_utf8(output.stdout).ok()?.trim().to_string();
+        if url.is_empty() {
+            None
+        } else {
+            Some(url)
+        }
+    }
+
+    /// Cache key identifying the repository state: root, HEAD, index mtime and
+    /// the display toggles. None (forcing live computation) if any part can't be read
+    fn cache_key(&self, working_dir: &str) -> Option<String> {
+        let output = self
+            .git(
+                working_dir,
+                &["rev-parse", "--show-toplevel", "--absolute-git-dir"],
+            )
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let stdout = String::from_utf8(output.stdout).ok()?;
+        let mut lines = stdout.lines();
+        let repo_root = lines.next()?.trim().to_string();
+        let git_dir = PathBuf::from(lines.next()?.trim());
+
+        let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+        let index_mtime = std::fs::metadata(git_dir.join("index"))
+            .and_then(|meta| meta.modified())
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_nanos();
+
+        Some(format!(
+            "{}|{}|{}|{}{}{}{}{}{}{}{}{}",
+            repo_root,
+            head.trim(),
+            index_mtime,
+            self.show_sha as u8,
+            self.show_stash as u8,
+            self.show_commit_age as u8,
+            self.show_provider as u8,
+            self.show_operation as u8,
+            self.show_worktree as u8,
+            self.show_added as u8,
+            self.show_modified as u8,
+            self.show_deleted as u8
+        ))
+    }
+
+    fn cache(&self) -> Cache<String, SegmentData> {
+        Cache::new("git").with_ttl(Duration::from_secs(self.cache_ttl))
+    }
+
+    /// Seconds elapsed since the last commit on HEAD
+    fn get_last_commit_age(&self, working_dir: &str) -> Option<u64> {
+        let output = self.git(working_dir, &["log", "-1", "--format=%ct"]).ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        let commit_time: u64 = String::from_utf8(output.stdout).ok()?.trim().parse().ok()?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+
+        Some(now.saturating_sub(commit_time))
+    }
+
+    /// Format an age in seconds as a compact duration (e.g. 45s, 12m, 5h, 3d)
+    fn format_age(seconds: u64) -> String {
+        if seconds < 60 {
+            format!("{}s", seconds)
+        } else if seconds < 3600 {
+            format!("{}m", seconds / 60)
+        } else if seconds < 86_400 {
+            format!("{}h", seconds / 3600)
+        } else {
+            format!("{}d", seconds / 86_400)
+        }
+    }
+
+    /// Run git and build the segment data from scratch
+    fn collect_live(&self, working_dir: &str) -> Option<SegmentData> {
+        let git_info = self.get_git_info(working_dir)?;
 
         let mut metadata = HashMap::new();
         metadata.insert("branch".to_string(), git_info.branch.clone());
+        metadata.insert("label".to_string(), git_info.branch.clone());
         metadata.insert("status".to_string(), format!("{:?}", git_info.status));
         metadata.insert("ahead".to_string(), git_info.ahead.to_string());
         metadata.insert("behind".to_string(), git_info.behind.to_string());
@@ -184,10 +644,43 @@ impl Segment for GitSegment {
         if let Some(ref sha) = git_info.sha {
             metadata.insert("sha".to_string(), sha.clone());
         }
+        if let Some(stash_count) = git_info.stash_count {
+            metadata.insert("stash_count".to_string(), stash_count.to_string());
+        }
+        if let Some(age) = git_info.last_commit_age {
+            metadata.insert("last_commit_age".to_string(), age.to_string());
+        }
+        if let Some(provider) = git_info.provider {
+            // The renderer picks the icon matching the configured icon mode
+            let (plain, nerd_font) = provider.icons();
+            metadata.insert("provider".to_string(), format!("{:?}", provider));
+            metadata.insert("dynamic_icon_plain".to_string(), plain.to_string());
+            metadata.insert("dynamic_icon_nerd_font".to_string(), nerd_font.to_string());
+        }
+
+        if let Some(ref worktree) = git_info.worktree {
+            metadata.insert("worktree".to_string(), worktree.clone());
+        }
+
+        if let Some(ref operation) = git_info.operation {
+            metadata.insert("operation".to_string(), operation.label());
+            // Highlight the whole segment while an operation is in progress
+            if let Ok(color_json) = serde_json::to_string(&self.operation_color) {
+                metadata.insert("dynamic_text_color".to_string(), color_json);
+            }
+        }
 
         let primary = git_info.branch;
         let mut status_parts = Vec::new();
 
+        if let Some(ref worktree) = git_info.worktree {
+            status_parts.push(format!("⎇ {}", worktree));
+        }
+
+        if let Some(ref operation) = git_info.operation {
+            status_parts.push(operation.label());
+        }
+
         match git_info.status {
             GitStatus::Clean => status_parts.push("✓".to_string()),
             GitStatus::Dirty => status_parts.push("●".to_string()),
@@ -205,14 +698,502 @@ impl Segment for GitSegment {
             status_parts.push(sha.clone());
         }
 
+        if let Some(stash_count) = git_info.stash_count {
+            if stash_count > 0 {
+                status_parts.push(format!("⚑{}", stash_count));
+            }
+        }
+
+        if let Some(age) = git_info.last_commit_age {
+            status_parts.push(Self::format_age(age));
+        }
+
+        // Counts stay plain text; with `color_changes` the renderer colors
+        // them from `secondary_colors`
+        let changes = git_info.changes;
+        metadata.insert("added".to_string(), changes.added.to_string());
+        metadata.insert("modified".to_string(), changes.modified.to_string());
+        metadata.insert("deleted".to_string(), changes.deleted.to_string());
+        let counts = [
+            (self.show_added, '+', changes.added, &self.added_color),
+            (
+                self.show_modified,
+                '~',
+                changes.modified,
+                &self.modified_color,
+            ),
+            (self.show_deleted, '-', changes.deleted, &self.deleted_color),
+        ];
+        let mut colors = Vec::new();
+        for (shown, sign, count, color) in counts {
+            if !shown || count == 0 {
+                continue;
+            }
+            let text = format!("{}{}", sign, count);
+            if self.color_changes {
+                colors.push((text.clone(), color));
+            }
+            status_parts.push(text);
+        }
+        if !colors.is_empty() {
+            if let Ok(colors_json) = serde_json::to_string(&colors) {
+                metadata.insert("secondary_colors".to_string(), colors_json);
+            }
+        }
+
         Some(SegmentData {
             primary,
             secondary: status_parts.join(" "),
             metadata,
         })
     }
+}
+
+impl Segment for GitSegment {
+    fn collect(&self, input: &InputData) -> Option<SegmentData> {
+        let working_dir = &input.workspace.current_dir;
+        if self.cache_ttl == 0 {
+            return self.collect_live(working_dir);
+        }
+
+        // Serve an unchanged repository from cache; a new index mtime or HEAD changes the key
+        let cache_key = self.cache_key(working_dir);
+        if let Some(data) = cache_key.as_ref().and_then(|key| self.cache().get(key)) {
+            return Some(data);
+        }
+
+        // `git status` may refresh the index, so key the entry by the state it leaves behind
+        let data = self.collect_live(working_dir)?;
+        if let Some(key) = self.cache_key(working_dir) {
+            let _ = self.cache().insert(key, &data);
+        }
+        Some(data)
+    }
 
     fn id(&self) -> SegmentId {
         SegmentId::Git
     }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(5))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// Run git in `dir` with a fixed identity, independent of the user's git config
+    fn git(dir: &Path, args: &[&str]) -> String {
+        git_at(dir, args, None)
+    }
+
+    /// Like `git`, with author and committer dates pinned to `date` (unix seconds)
+    fn git_at(dir: &Path, args: &[&str], date: Option<u64>) -> String {
+        let mut command = Command::new("git");
+        command
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args([
+                "-c",
+                "commit.gpgsign=false",
+                "-c",
+                "init.defaultBranch=main",
+            ])
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env("GIT_CONFIG_GLOBAL", "/dev/null");
+        if let Some(date) = date {
+            let date = format!("@{} +0000", date);
+            command
+                .env("GIT_AUTHOR_DATE", &date)
+                .env("GIT_COMMITTER_DATE", &date);
+        }
+        let output = command.output().expect("run git");
+        assert!(
+            output.status.success(),
+            "git {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    fn now() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    /// Repository on `main` with one commit of `file.txt`, made at `date`
+    fn fixture_repo(date: u64) -> TempDir {
+        let dir = TempDir::new("git");
+        git(dir.path(), &["init", "-q"]);
+        std::fs::write(dir.join("file.txt"), "one\n").unwrap();
+        git(dir.path(), &["add", "file.txt"]);
+        git_at(dir.path(), &["commit", "-q", "-m", "initial"], Some(date));
+        dir
+    }
+
+    fn collect(segment: &GitSegment, dir: &TempDir) -> SegmentData {
+        segment
+            .collect_live(dir.path().to_str().unwrap())
+            .expect("git data")
+    }
+
+    #[test]
+    fn stash_count_and_commit_age() {
+        let repo = fixture_repo(now() - 3 * 86_400 - 60);
+        std::fs::write(repo.join("file.txt"), "two\n").unwrap();
+        git(repo.path(), &["stash", "-q"]);
+
+        let segment = GitSegment::new().with_stash(true).with_commit_age(true);
+        let data = collect(&segment, &repo);
+
+        assert_eq!(data.primary, "main");
+        assert_eq!(data.metadata["stash_count"], "1");
+        assert!(data.secondary.contains("⚑1"), "{}", data.secondary);
+        assert!(data.secondary.ends_with("3d"), "{}", data.secondary);
+    }
+
+    #[test]
+    fn stash_and_age_hidden_by_default() {
+        let repo = fixture_repo(now());
+        let data = collect(&GitSegment::new(), &repo);
+
+        assert_eq!(data.secondary, "✓");
+        assert!(!data.metadata.contains_key("stash_count"));
+        assert!(!data.metadata.contains_key("last_commit_age"));
+    }
+
+    #[test]
+    fn format_age_picks_largest_unit() {
+        assert_eq!(GitSegment::format_age(45), "45s");
+        assert_eq!(GitSegment::format_age(12 * 60), "12m");
+        assert_eq!(GitSegment::format_age(5 * 3600 + 59), "5h");
+        assert_eq!(GitSegment::format_age(3 * 86_400), "3d");
+    }
+
+    #[test]
+    fn remote_urls_map_to_providers() {
+        let cases = [
+            (
+                "https://github.com/Haleclipse/CCometixLine.git",
+                GitProvider::GitHub,
+            ),
+            (
+                "git@github.com:Haleclipse/CCometixLine.git",
+                GitProvider::GitHub,
+            ),
+            (
+                "ssh://git@gitlab.com:2222/group/repo.git",
+                GitProvider::GitLab,
+            ),
+            (
+                "https://user@GitLab.example.org/group/repo",
+                GitProvider::GitLab,
+            ),
+            ("git@bitbucket.org:team/repo.git", GitProvider::Bitbucket),
+            ("https://git.sr.ht/~user/repo", GitProvider::Generic),
+            ("/srv/git/repo.git", GitProvider::Generic),
+        ];
+        for (url, provider) in cases {
+            assert_eq!(GitProvider::from_remote_url(url), provider, "{url}");
+        }
+        assert_eq!(
+            GitProvider::remote_host("ssh://git@gitlab.com:2222/group/repo.git").as_deref(),
+            Some("gitlab.com")
+        );
+    }
+
+    #[test]
+    fn provider_icon_comes_from_origin() {
+        let repo = fixture_repo(now());
+        git(
+            repo.path(),
+            &["remote", "add", "origin", "git@github.com:owner/repo.git"],
+        );
+
+        let data = collect(&GitSegment::new().with_provider(true), &repo);
+        assert_eq!(data.metadata["provider"], "GitHub");
+        assert_eq!(data.metadata["dynamic_icon_plain"], "🐙");
+
+        let data = collect(&GitSegment::new(), &repo);
+        assert!(!data.metadata.contains_key("provider"));
+    }
+
+    /// Marker files as (path, contents)
+    type Files<'a> = &'a [(&'a str, &'a str)];
+
+    /// Bare `.git`-like directory containing the given marker files
+    fn git_dir_with(files: Files) -> TempDir {
+        let dir = TempDir::new("gitdir");
+        for (path, contents) in files {
+            dir.write(path, contents);
+        }
+        dir
+    }
+
+    #[test]
+    fn operations_are_detected_from_marker_files() {
+        let cases: &[(Files, Option<&str>)] = &[
+            (&[], None),
+            (
+                &[("rebase-merge/msgnum", "2\n"), ("rebase-merge/end", "5\n")],
+                Some("REBASE 2/5"),
+            ),
+            (&[("rebase-merge/head-name", "x")], Some("REBASE")),
+            (
+                &[("rebase-apply/next", "1"), ("rebase-apply/last", "3")],
+                Some("REBASE 1/3"),
+            ),
+            (&[("rebase-apply/applying", "")], Some("AM")),
+            (&[("MERGE_HEAD", "abc")], Some("MERGE")),
+            (&[("CHERRY_PICK_HEAD", "abc")], Some("CHERRY-PICK")),
+            (&[("REVERT_HEAD", "abc")], Some("REVERT")),
+            (&[("BISECT_LOG", "")], Some("BISECT")),
+        ];
+
+        for (files, expected) in cases {
+            let dir = git_dir_with(files);
+            let label = GitOperation::detect(dir.path()).map(|op| op.label());
+            assert_eq!(label.as_deref(), *expected, "{files:?}");
+        }
+    }
+
+    #[test]
+    fn merge_in_progress_is_shown_when_enabled() {
+        let repo = fixture_repo(now());
+        let head = git(repo.path(), &["rev-parse", "HEAD"]);
+        std::fs::write(repo.join(".git/MERGE_HEAD"), head).unwrap();
+
+        let data = collect(&GitSegment::new().with_operation(true), &repo);
+        assert_eq!(data.metadata["operation"], "MERGE");
+        assert!(data.secondary.contains("MERGE"), "{}", data.secondary);
+        assert!(data.metadata.contains_key("dynamic_text_color"));
+
+        let data = collect(&GitSegment::new(), &repo);
+        assert!(!data.metadata.contains_key("operation"));
+    }
+
+    fn cached_segment() -> GitSegment {
+        let options = HashMap::from([("cache_ttl".to_string(), serde_json::json!(60))]);
+        GitSegment::new().with_config_from_options(&options)
+    }
+
+    fn collect_cached(segment: &GitSegment, dir: &TempDir) -> SegmentData {
+        let input = crate::test_support::input_data(
+            serde_json::json!({"workspace": {"current_dir": dir.path()}}),
+        );
+        segment.collect(&input).expect("git data")
+    }
+
+    #[test]
+    fn unchanged_repo_is_served_from_cache() {
+        let _cache = crate::test_support::isolated_cache();
+        let repo = fixture_repo(now());
+        let segment = cached_segment();
+        let dir = repo.path().to_str().unwrap();
+
+        let live = collect_cached(&segment, &repo);
+        let key = segment.cache_key(dir).expect("cache key");
+        let cached = segment
+            .cache()
+            .get(&key)
+            .expect("cached after first collect");
+        assert_eq!(
+            (cached.primary, cached.secondary),
+            (live.primary, live.secondary)
+        );
+
+        // A planted entry under the same key proves the next collect never runs git
+        let planted = crate::test_support::segment_data("cached", "");
+        segment.cache().insert(key, &planted).unwrap();
+        assert_eq!(collect_cached(&segment, &repo).primary, "cached");
+    }
+
+    #[test]
+    fn changed_index_busts_the_cache() {
+        let _cache = crate::test_support::isolated_cache();
+        let repo = fixture_repo(now());
+        let segment = cached_segment();
+        let dir = repo.path().to_str().unwrap();
+
+        collect_cached(&segment, &repo);
+        let key = segment.cache_key(dir).unwrap();
+        let planted = crate::test_support::segment_data("cached", "");
+        segment.cache().insert(key.clone(), &planted).unwrap();
+
+        std::fs::write(repo.join("new.txt"), "new\n").unwrap();
+        git(repo.path(), &["add", "new.txt"]);
+
+        assert_ne!(segment.cache_key(dir).unwrap(), key);
+        let data = collect_cached(&segment, &repo);
+        assert_eq!(data.primary, "main");
+    }
+
+    #[test]
+    fn missing_index_falls_back_to_live() {
+        let _cache = crate::test_support::isolated_cache();
+        let repo = TempDir::new("git");
+        git(repo.path(), &["init", "-q"]);
+        let segment = cached_segment();
+
+        assert_eq!(segment.cache_key(repo.path().to_str().unwrap()), None);
+        assert!(segment
+            .collect(&crate::test_support::input_data(
+                serde_json::json!({"workspace": {"current_dir": repo.path()}}),
+            ))
+            .is_some());
+    }
+
+    #[test]
+    fn main_checkout_is_not_a_worktree() {
+        let repo = fixture_repo(now());
+        let data = collect(&GitSegment::new().with_worktree(true), &repo);
+
+        assert!(!data.metadata.contains_key("worktree"));
+        assert!(!data.secondary.contains('⎇'), "{}", data.secondary);
+    }
+
+    #[test]
+    fn linked_worktree_shows_its_name() {
+        let repo = fixture_repo(now());
+        let linked = TempDir::new("linked");
+        let checkout = linked.join("feature-x");
+        git(
+            repo.path(),
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                "feature",
+                checkout.to_str().unwrap(),
+            ],
+        );
+        std::fs::create_dir(checkout.join("sub")).unwrap();
+
+        let segment = GitSegment::new().with_worktree(true);
+        for dir in [checkout.clone(), checkout.join("sub")] {
+            let data = segment
+                .collect_live(dir.to_str().unwrap())
+                .expect("git data");
+            assert_eq!(data.primary, "feature");
+            assert_eq!(data.metadata["worktree"], "feature-x");
+            assert!(data.secondary.contains("⎇ feature-x"), "{}", data.secondary);
+        }
+
+        let data = GitSegment::new()
+            .collect_live(checkout.to_str().unwrap())
+            .expect("git data");
+        assert!(!data.metadata.contains_key("worktree"));
+    }
+
+    #[test]
+    fn only_dot_git_files_into_worktrees_count() {
+        let dir = TempDir::new("dotgit");
+        let cases = [
+            ("gitdir: /src/repo/.git/worktrees/wt\n", Some("wt")),
+            ("gitdir: /src/repo/.git/modules/lib\n", None),
+            ("not a gitdir\n", None),
+        ];
+        for (content, expected) in cases {
+            let dot_git = dir.write(".git", content);
+            assert_eq!(
+                GitSegment::worktree_name(&dot_git).as_deref(),
+                expected,
+                "{content}"
+            );
+        }
+
+        std::fs::remove_file(dir.join(".git")).unwrap();
+        std::fs::create_dir(dir.join(".git")).unwrap();
+        assert_eq!(GitSegment::worktree_name(&dir.join(".git")), None);
+    }
+
+    #[test]
+    fn porcelain_counts_staged_unstaged_and_untracked() {
+        let porcelain = "\
+A  staged-new.rs
+ M unstaged.rs
+M  staged.rs
+MM both.rs
+ D unstaged-gone.rs
+D  staged-gone.rs
+R  old.rs -> new.rs
+?? untracked.txt
+UU conflict.rs
+AA both-added.rs
+";
+        assert_eq!(
+            ChangeCounts::from_porcelain(porcelain),
+            ChangeCounts {
+                added: 2,
+                modified: 6,
+                deleted: 2,
+            }
+        );
+        assert_eq!(ChangeCounts::from_porcelain(""), ChangeCounts::default());
+    }
+
+    /// Repository with one untracked, one modified and one deleted file
+    fn repo_with_changes() -> TempDir {
+        let repo = fixture_repo(now());
+        std::fs::write(repo.join("gone.txt"), "x\n").unwrap();
+        git(repo.path(), &["add", "gone.txt"]);
+        git(repo.path(), &["commit", "-q", "-m", "gone"]);
+        std::fs::remove_file(repo.join("gone.txt")).unwrap();
+        std::fs::write(repo.join("file.txt"), "two\n").unwrap();
+        std::fs::write(repo.join("new.txt"), "new\n").unwrap();
+        repo
+    }
+
+    #[test]
+    fn change_counts_stay_plain_with_colors_in_metadata() {
+        let repo = repo_with_changes();
+        let data = collect(&GitSegment::new().with_changes(true, true, true), &repo);
+
+        assert_eq!(data.secondary, "● +1 ~1 -1");
+        assert_eq!(
+            (
+                data.metadata["added"].as_str(),
+                data.metadata["modified"].as_str(),
+                data.metadata["deleted"].as_str()
+            ),
+            ("1", "1", "1")
+        );
+        let colors: Vec<(String, AnsiColor)> =
+            serde_json::from_str(&data.metadata["secondary_colors"]).unwrap();
+        assert_eq!(
+            colors,
+            [
+                ("+1".to_string(), AnsiColor::Color16 { c16: 10 }),
+                ("~1".to_string(), AnsiColor::Color16 { c16: 11 }),
+                ("-1".to_string(), AnsiColor::Color16 { c16: 9 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn change_counts_are_individually_toggled() {
+        let repo = repo_with_changes();
+
+        let data = collect(&GitSegment::new().with_changes(false, true, false), &repo);
+        assert_eq!(data.secondary, "● ~1");
+
+        let data = collect(&GitSegment::new(), &repo);
+        assert_eq!(data.secondary, "●");
+        assert!(!data.metadata.contains_key("secondary_colors"));
+
+        let options = HashMap::from([
+            ("show_added".to_string(), serde_json::json!(true)),
+            ("color_changes".to_string(), serde_json::json!(false)),
+        ]);
+        let data = collect(&GitSegment::new().with_config_from_options(&options), &repo);
+        assert_eq!(data.secondary, "● +1");
+        assert!(!data.metadata.contains_key("secondary_colors"));
+    }
 }
@@ -0,0 +1,198 @@
+use super::{Segment, SegmentData};
+use crate::config::{InputData, SegmentId};
+use crate::utils::process::output_with_timeout;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// What the host segment shows
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HostFormat {
+    /// `buildbox` (default)
+    #[default]
+    Host,
+    /// `deploy@buildbox`
+    UserAtHost,
+}
+
+impl HostFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "host" => Some(HostFormat::Host),
+            "user@host" => Some(HostFormat::UserAtHost),
+            _ => None,
+        }
+    }
+}
+
+/// Shows the machine's hostname, optionally only inside SSH sessions
+#[derive(Default)]
+pub struct HostSegment {
+    show_only_ssh: bool,
+    format: HostFormat,
+    /// Limit on the `hostname` fallback command (default `DEFAULT_COMMAND_TIMEOUT`)
+    timeout: Option<Duration>,
+}
+
+impl HostSegment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load settings from segment options HashMap
+    pub fn with_config_from_options(
+        mut self,
+        options: &HashMap<String, serde_json::Value>,
+    ) -> Self {
+        if let Some(value) = options.get("show_only_ssh").and_then(|v| v.as_bool()) {
+            self.show_only_ssh = value;
+        }
+        if let Some(format) = options
+            .get("format")
+            .and_then(|v| v.as_str())
+            .and_then(HostFormat::parse)
+        {
+            self.format = format;
+        }
+        self.timeout = Some(super::io_timeout(options, super::DEFAULT_COMMAND_TIMEOUT));
+        self
+    }
+
+    /// Whether this process runs inside an SSH session
+    pub fn in_ssh_session() -> bool {
+        ["SSH_CONNECTION", "SSH_TTY", "SSH_CLIENT"]
+            .iter()
+            .any(|name| std::env::var_os(name).is_some_and(|value| !value.is_empty()))
+    }
+
+    /// Short hostname (up to the first dot), from the environment or the OS
+    fn hostname(timeout: Duration) -> Option<String> {
+        let from_env = ["HOSTNAME", "COMPUTERNAME"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.trim().is_empty());
+        let from_os = || {
+            std::fs::read_to_string("/etc/hostname")
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+                .or_else(|| {
+                    output_with_timeout(&mut std::process::Command::new("hostname"), timeout)
+                        .ok()
+                        .filter(|output| output.status.success())
+                        .and_then(|output| String::from_utf8(output.stdout).ok())
+                })
+        };
+
+        let name = from_env.or_else(from_os)?;
+        let short = name.trim().split('.').next()?.to_string();
+        (!short.is_empty()).then_some(short)
+    }
+
+    fn username() -> Option<String> {
+        ["USER", "USERNAME", "LOGNAME"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+    }
+}
+
+impl Segment for HostSegment {
+    fn collect(&self, _input: &InputData) -> Option<SegmentData> {
+        let ssh = Self::in_ssh_session();
+        if self.show_only_ssh && !ssh {
+            return None;
+        }
+
+        let timeout = self.timeout.unwrap_or(super::DEFAULT_COMMAND_TIMEOUT);
+        let host = Self::hostname(timeout)?;
+        let user = Self::username();
+
+        let primary = match (self.format, &user) {
+            (HostFormat::UserAtHost, Some(user)) => format!("{}@{}", user, host),
+            _ => host.clone(),
+        };
+
+        let mut metadata = HashMap::new();
+        metadata.insert("host".to_string(), host);
+        metadata.insert("ssh".to_string(), ssh.to_string());
+        if let Some(user) = user {
+            metadata.insert("user".to_string(), user);
+        }
+
+        Some(SegmentData {
+            primary,
+            secondary: String::new(),
+            metadata,
+        })
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::Host
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{input_data, EnvGuard};
+
+    /// Pin the hostname and user, and clear every SSH variable
+    fn local_env() -> EnvGuard {
+        let mut env = EnvGuard::new();
+        env.set("HOSTNAME", "buildbox.example.com")
+            .set("USER", "deploy")
+            .remove("SSH_CONNECTION")
+            .remove("SSH_TTY")
+            .remove("SSH_CLIENT");
+        env
+    }
+
+    fn segment(options: serde_json::Value) -> HostSegment {
+        let options: HashMap<String, serde_json::Value> = serde_json::from_value(options).unwrap();
+        HostSegment::new().with_config_from_options(&options)
+    }
+
+    fn collect(segment: &HostSegment) -> Option<SegmentData> {
+        segment.collect(&input_data(serde_json::json!({})))
+    }
+
+    #[test]
+    fn local_session_shows_the_short_hostname() {
+        let _env = local_env();
+
+        let data = collect(&segment(serde_json::json!({}))).unwrap();
+        assert_eq!(data.primary, "buildbox");
+        assert_eq!(data.metadata["ssh"], "false");
+    }
+
+    #[test]
+    fn show_only_ssh_hides_the_segment_locally() {
+        let _env = local_env();
+
+        assert!(!HostSegment::in_ssh_session());
+        assert!(collect(&segment(serde_json::json!({"show_only_ssh": true}))).is_none());
+    }
+
+    #[test]
+    fn ssh_session_is_detected_from_the_environment() {
+        for name in ["SSH_CONNECTION", "SSH_TTY", "SSH_CLIENT"] {
+            let mut env = local_env();
+            env.set(name, "10.0.0.1 52000 10.0.0.2 22");
+
+            let data = collect(&segment(serde_json::json!({
+                "show_only_ssh": true,
+                "format": "user@host",
+            })))
+            .unwrap_or_else(|| panic!("hidden with {name} set"));
+            assert_eq!(data.primary, "deploy@buildbox");
+            assert_eq!(data.metadata["ssh"], "true");
+        }
+    }
+
+    #[test]
+    fn empty_ssh_variable_is_not_a_session() {
+        let mut env = local_env();
+        env.set("SSH_TTY", "");
+
+        assert!(!HostSegment::in_ssh_session());
+    }
+}
@@ -1,38 +1,156 @@
 pub mod context_window;
 pub mod cost;
+pub mod count_source;
 pub mod directory;
+pub mod exit_code;
 pub mod git;
+pub mod host;
 pub mod model;
 pub mod newapi_cost;
+pub mod notify;
 pub mod output_style;
+pub mod plan;
+pub mod runtime_version;
+pub mod secrets;
 pub mod session;
+pub mod tmux;
 pub mod update;
 pub mod usage;
 
 use crate::config::{InputData, SegmentId};
 use std::collections::HashMap;
+use std::time::Duration;
 
 // New Segment trait for data collection only
 pub trait Segment {
     fn collect(&self, input: &InputData) -> Option<SegmentData>;
     fn id(&self) -> SegmentId;
+
+    /// How long collected data stays fresh in long-lived modes such as `--watch`
+    /// None (the default) re-collects on every render
+    fn refresh_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Whether collecting makes network requests; in `--watch` such segments
+    /// are refreshed on a background thread so slow responses never delay a render
+    fn uses_network(&self) -> bool {
+        false
+    }
+}
+
+/// Default limit on a command run by a segment (git, runtime versions, ...)
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Timeout for a segment's I/O: the `timeout_ms` option, else the older
+/// `timeout` option in seconds, else `default`
+pub fn io_timeout(options: &HashMap<String, serde_json::Value>, default: Duration) -> Duration {
+    if let Some(millis) = options.get("timeout_ms").and_then(|v| v.as_u64()) {
+        return Duration::from_millis(millis);
+    }
+    options
+        .get("timeout")
+        .and_then(|v| v.as_u64())
+        .map(Duration::from_secs)
+        .unwrap_or(default)
 }
 
-#[derive(Debug, Clone)]
+/// Data collected by a segment for rendering
+///
+/// A segment with nothing to show should return `None` from `collect`.
+/// Returning `SegmentData::empty()` (an empty `primary`) is treated the same way:
+/// the renderer skips the segment entirely, with no separator or padding.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct SegmentData {
     pub primary: String,
     pub secondary: String,
     pub metadata: HashMap<String, String>,
 }
 
+impl SegmentData {
+    /// Segment data that renders as nothing
+    pub fn empty() -> Self {
+        Self {
+            primary: String::new(),
+            secondary: String::new(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Offer shorter stand-ins for `primary`, most precise first
+    /// With `number_format = "adaptive"` the renderer uses the first one that fits `max_width`
+    pub fn set_primary_alternatives(&mut self, alternatives: &[String]) {
+        if let Ok(json) = serde_json::to_string(alternatives) {
+            self.metadata
+                .insert("primary_alternatives".to_string(), json);
+        }
+    }
+
+    /// Shorter stand-ins for `primary` set by the segment, if any
+    pub fn primary_alternatives(&self) -> Vec<String> {
+        self.metadata
+            .get("primary_alternatives")
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether the renderer should skip this segment
+    pub fn is_empty(&self) -> bool {
+        self.primary.is_empty()
+    }
+
+    /// Segment data reporting a failure (e.g. a network error)
+    /// How it renders is decided by the segment's `on_error` option
+    pub fn error(message: impl Into<String>) -> Self {
+        let mut data = Self::empty();
+        data.metadata.insert("error".to_string(), message.into());
+        data
+    }
+
+    /// Whether this data reports a failure
+    pub fn is_error(&self) -> bool {
+        self.metadata.contains_key("error")
+    }
+}
+
+/// What a failed segment shows, set per segment with the `on_error` option
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OnError {
+    /// Drop the segment, as if it had nothing to show (default)
+    #[default]
+    Hide,
+    /// Show an error glyph (`error_glyph`, default `✗`) in the error color
+    Glyph,
+    /// Show placeholder text (`error_placeholder`, default `--`) in the normal colors
+    Placeholder,
+}
+
+impl OnError {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "hide" => Some(OnError::Hide),
+            "glyph" => Some(OnError::Glyph),
+            "placeholder" => Some(OnError::Placeholder),
+            _ => None,
+        }
+    }
+}
+
 // Re-export all segment types
 pub use context_window::ContextWindowSegment;
 pub use cost::CostSegment;
 pub use directory::DirectorySegment;
+pub use exit_code::ExitCodeSegment;
 pub use git::GitSegment;
+pub use host::HostSegment;
 pub use model::ModelSegment;
 pub use newapi_cost::NewApiCostSegment;
+pub use notify::NotifySegment;
 pub use output_style::OutputStyleSegment;
+pub use plan::PlanSegment;
+pub use runtime_version::RuntimeVersionSegment;
+pub use secrets::SecretsSegment;
 pub use session::SessionSegment;
+pub use tmux::TmuxSegment;
 pub use update::UpdateSegment;
 pub use usage::UsageSegment;
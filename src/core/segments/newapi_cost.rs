@@ -1,37 +1,603 @@
 use super::{Segment, SegmentData};
-use crate::config::{InputData, SegmentId};
-use chrono::{Local, Timelike};
-use serde::Deserialize;
+use crate::config::{AnsiColor, InputData, SegmentId};
+use crate::utils::{Cache, Locale};
+use chrono::{DateTime, Datelike, Days, FixedOffset, Local, NaiveDate, TimeZone, Utc};
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Once;
+use std::time::Duration;
 
-/// NewApi API response structure
-#[derive(Debug, Deserialize)]
-struct NewApiStatResponse {
-    success: bool,
-    #[allow(dead_code)]
-    message: String,
-    data: NewApiStatData,
+/// Where the stat response keeps its fields, as dot-separated JSON paths
+/// (numeric parts index into arrays), so forks with other layouts can be read
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseMapping {
+    /// Path to the quota value (default `data.quota`)
+    pub quota: String,
+    /// Path to the success flag (default `success`); None skips the check
+    pub success: Option<String>,
+    /// Path to the error message (default `message`)
+    pub message: Option<String>,
 }
 
-/// NewApi stat data structure
-#[derive(Debug, Deserialize)]
-struct NewApiStatData {
-    quota: i64,
-    #[allow(dead_code)]
-    rpm: Option<i64>,
-    #[allow(dead_code)]
-    tpm: Option<i64>,
+impl Default for ResponseMapping {
+    fn default() -> Self {
+        Self {
+            quota: "data.quota".to_string(),
+            success: Some("success".to_string()),
+            message: Some("message".to_string()),
+        }
+    }
+}
+
+impl ResponseMapping {
+    /// Read a `response_mapping` table; missing keys keep their defaults and
+    /// an empty string turns the success or message lookup off
+    fn from_value(value: &serde_json::Value) -> Option<Self> {
+        let table = value.as_object()?;
+        let mut mapping = Self::default();
+        let path = |key: &str| table.get(key).and_then(|v| v.as_str()).map(str::to_string);
+
+        if let Some(quota) = path("quota").filter(|quota| !quota.is_empty()) {
+            mapping.quota = quota;
+        }
+        if let Some(success) = path("success") {
+            mapping.success = Some(success).filter(|success| !success.is_empty());
+        }
+        if let Some(message) = path("message") {
+            mapping.message = Some(message).filter(|message| !message.is_empty());
+        }
+        Some(mapping)
+    }
+
+    /// Deserialize a stat response straight from `reader`, keeping only the
+    /// values on the mapped paths; the rest of the body is skipped unbuffered
+    pub fn read_body(&self, reader: impl std::io::Read) -> serde_json::Result<serde_json::Value> {
+        let paths: Vec<Vec<&str>> = std::iter::once(self.quota.as_str())
+            .chain(self.success.as_deref())
+            .chain(self.message.as_deref())
+            .map(|path| path.split('.').filter(|part| !part.is_empty()).collect())
+            .collect();
+        let filter = PathFilter {
+            paths: paths.iter().map(Vec::as_slice).collect(),
+        };
+
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let body = filter.deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(body)
+    }
+
+    /// Extract the quota from a stat response body
+    pub fn parse(&self, body: &serde_json::Value) -> Result<f64, String> {
+        if let Some(success_path) = &self.success {
+            let success = json_path(body, success_path).and_then(|v| v.as_bool());
+            if success != Some(true) {
+                let message = self
+                    .message
+                    .as_deref()
+                    .and_then(|path| json_path(body, path))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("no success flag");
+                return Err(message.to_string());
+            }
+        }
+
+        let quota =
+            json_path(body, &self.quota).ok_or_else(|| format!("no value at `{}`", self.quota))?;
+        quota
+            .as_f64()
+            .or_else(|| quota.as_str().and_then(|s| s.trim().parse().ok()))
+            .ok_or_else(|| format!("value at `{}` is not a number", self.quota))
+    }
+}
+
+/// Deserializes only the parts of a JSON document lying on one of `paths`
+/// (split into parts); everything else is skipped without being built
+struct PathFilter<'a> {
+    paths: Vec<&'a [&'a str]>,
+}
+
+impl<'a> PathFilter<'a> {
+    /// The remaining paths below an object key or array index
+    fn descend(&self, key: &str) -> Self {
+        let paths = self
+            .paths
+            .iter()
+            .filter_map(|path| match path.split_first() {
+                Some((first, rest)) if *first == key => Some(rest),
+                _ => None,
+            })
+            .collect();
+        Self { paths }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for PathFilter<'_> {
+    type Value = serde_json::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        // A path ends here: keep the whole value
+        if self.paths.iter().any(|path| path.is_empty()) {
+            return serde_json::Value::deserialize(deserializer);
+        }
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for PathFilter<'_> {
+    type Value = serde_json::Value;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Self::Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(serde_json::Value::Null)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut object = serde_json::Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let child = self.descend(&key);
+            if child.paths.is_empty() {
+                map.next_value::<IgnoredAny>()?;
+            } else {
+                object.insert(key, map.next_value_seed(child)?);
+            }
+        }
+        Ok(object.into())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        // Skipped elements become null so kept ones stay at their index
+        let mut items = Vec::new();
+        let mut index = 0usize;
+        loop {
+            let child = self.descend(&index.to_string());
+            let item = if child.paths.is_empty() {
+                seq.next_element::<IgnoredAny>()?
+                    .map(|_| serde_json::Value::Null)
+            } else {
+                seq.next_element_seed(child)?
+            };
+            match item {
+                Some(item) => items.push(item),
+                None => break,
+            }
+            index += 1;
+        }
+        Ok(items.into())
+    }
+}
+
+/// Follow a dot-separated path through objects and arrays
+fn json_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.')
+        .filter(|part| !part.is_empty())
+        .try_fold(value, |current, part| match current {
+            serde_json::Value::Array(items) => items.get(part.parse::<usize>().ok()?),
+            _ => current.get(part),
+        })
+}
+
+/// Key of the cost state entry in the segment's cache
+const CACHE_KEY: &str = "state";
+
+/// Persistent NewApi cost state, kept across invocations
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct NewApiCostCache {
+    /// Start of the cost window (seconds since epoch) the high-water mark belongs to
+    #[serde(default)]
+    day_start: i64,
+    /// Highest cost observed during that window
+    #[serde(default)]
+    max_cost: f64,
+    /// Cost seen by the previous render during that window
+    #[serde(default)]
+    last_cost: Option<f64>,
+    /// When the last successful fetch happened (seconds since epoch)
+    #[serde(default)]
+    updated_at: i64,
+    /// When a post-wake retry was last attempted (seconds since epoch)
+    #[serde(default)]
+    wake_retry_at: i64,
+    /// Costs seen by recent renders, oldest first; kept across window resets
+    #[serde(default)]
+    history: Vec<f64>,
+    /// When each of the most recent `history` entries was seen (seconds since epoch)
+    #[serde(default)]
+    history_times: Vec<i64>,
+    /// Position of the next token with `token_selection = "round_robin"`
+    #[serde(default)]
+    token_index: usize,
+    /// Cost recorded by `--checkpoint-cost`; kept until `--clear-checkpoint`
+    #[serde(default)]
+    checkpoint: Option<f64>,
+    /// Start of the cost window the checkpoint was taken in
+    #[serde(default)]
+    checkpoint_window: i64,
+    /// Cost state of each token with `token_selection = "round_robin"`, keyed
+    /// by a hash of the token, so one token's cost is never compared to another's
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    tokens: HashMap<String, NewApiCostCache>,
+}
+
+impl NewApiCostCache {
+    /// Cost state of a rotated token (by its state key), or the shared one
+    fn state(&self, token_key: Option<&str>) -> Option<&NewApiCostCache> {
+        match token_key {
+            Some(key) => self.tokens.get(key),
+            None => Some(self),
+        }
+    }
+
+    fn state_mut(&mut self, token_key: Option<&str>) -> &mut NewApiCostCache {
+        match token_key {
+            Some(key) => self.tokens.entry(key.to_string()).or_default(),
+            None => self,
+        }
+    }
+}
+
+/// Render values as a Unicode sparkline scaled between their minimum and maximum
+pub fn sparkline(values: &[f64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+
+    values
+        .iter()
+        .map(|value| {
+            if range <= f64::EPSILON {
+                BARS[0]
+            } else {
+                let level = ((value - min) / range * (BARS.len() - 1) as f64).round() as usize;
+                BARS[level.min(BARS.len() - 1)]
+            }
+        })
+        .collect()
+}
+
+/// First non-empty value among the given environment variables
+fn env_var_any(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// Host part of a URL, without scheme, credentials, port or path
+fn url_host(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host_port = authority.rsplit('@').next()?;
+    let host = if let Some(bracketed) = host_port.strip_prefix('[') {
+        bracketed.split(']').next()?
+    } else {
+        host_port.split(':').next()?
+    };
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+/// Whether a NO_PROXY list excludes the host
+/// Entries match the host itself or any subdomain; `*` matches everything
+fn no_proxy_matches(no_proxy: &str, host: &str) -> bool {
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim().to_ascii_lowercase())
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            if entry == "*" {
+                return true;
+            }
+            let entry = entry.trim_start_matches('.');
+            let entry = entry.split(':').next().unwrap_or(entry);
+            host == entry || host.ends_with(&format!(".{}", entry))
+        })
 }
 
-/// NewApi Cost segment for displaying today's consumption
+/// NewApi Cost segment for displaying today's (or the configured range's) consumption
 #[derive(Debug, Clone)]
 pub struct NewApiCostSegment {
     pub base_url: Option<String>,
     pub user_token: Option<String>,
+    /// All tokens when `user_token` is a list; `user_token` holds the first
+    pub user_tokens: Vec<String>,
+    pub token_selection: TokenSelection,
     pub user_id: Option<String>,
     pub token_name: Option<String>,
     pub provider: Option<String>,
     pub quota_per_unit: Option<f64>,
+    pub daily_budget: Option<f64>,
+    pub warning_threshold: Option<f64>,
+    pub critical_threshold: Option<f64>,
+    pub warning_color: Option<AnsiColor>,
+    pub critical_color: Option<AnsiColor>,
+    pub zero_display: ZeroDisplay,
+    pub show_max: bool,
+    pub locale: Locale,
+    pub currency: String,
+    pub exchange_rate: Option<f64>,
+    pub secondary_currency: String,
+    pub compression: bool,
+    pub proxy: Option<String>,
+    pub timezone: DayTimezone,
+    pub day_start_hour: u32,
+    pub show_delta: bool,
+    pub range: CostRange,
+    pub wake_gap_secs: i64,
+    pub show_sparkline: bool,
+    pub sparkline_length: usize,
+    pub timeout: Duration,
+    pub response_mapping: ResponseMapping,
+    /// Log `type` queried from the self-stat endpoint (default 2, consumption)
+    pub stat_type: u32,
+    /// Endpoint below `base_url` reporting the remaining balance (e.g. `/api/user/self`)
+    pub balance_path: Option<String>,
+    /// JSON path of the remaining quota in the balance response (default `data.quota`)
+    pub balance_field: String,
+    pub mood: MoodDisplay,
+    /// Emoji for the normal, warning and critical bands
+    pub mood_emojis: [String; 3],
+    /// Show only a glyph colored by the budget band; the cost stays in metadata
+    pub indicator_only: bool,
+    pub indicator_glyph: String,
+    /// Glyph color below the warning threshold (default green)
+    pub indicator_color: Option<AnsiColor>,
+    pub color_by: ColorBy,
+    /// Cost per minute for the warning color with `color_by = "velocity"`
+    pub velocity_warning: f64,
+    /// Cost per minute for the critical color with `color_by = "velocity"`
+    pub velocity_critical: f64,
+    /// Show the spend since the `--checkpoint-cost` mark (`+¥3.20 since mark`)
+    pub show_since_checkpoint: bool,
+    /// Show the end-of-day total the current pace leads to (`~¥40`)
+    pub show_projection: bool,
+    /// Hours into the day before a projection is shown (default 2)
+    pub projection_min_hours: f64,
+}
+
+/// Time window the cost is summed over
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CostRange {
+    /// Since midnight (default)
+    #[default]
+    Today,
+    /// Rolling 7 days: today and the 6 days before it
+    Week,
+    /// Since the first day of the current month
+    Month,
+    /// From `start_date` through `end_date` (inclusive, `YYYY-MM-DD`);
+    /// without an end date the window runs to now
+    Custom {
+        start: NaiveDate,
+        end: Option<NaiveDate>,
+    },
+}
+
+impl CostRange {
+    /// Parse the `range` option, reading `start_date`/`end_date` for `custom`
+    pub fn from_options(options: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        let date = |key: &str| {
+            options
+                .get(key)
+                .and_then(|v| v.as_str())
+                .and_then(|s| NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok())
+        };
+
+        match options.get("range")?.as_str()? {
+            "today" => Some(CostRange::Today),
+            "week" => Some(CostRange::Week),
+            "month" => Some(CostRange::Month),
+            "custom" => Some(CostRange::Custom {
+                start: date("start_date")?,
+                end: date("end_date"),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Short label shown next to the cost; None for today
+    pub fn label(&self) -> Option<String> {
+        match self {
+            CostRange::Today => None,
+            CostRange::Week => Some("7d".to_string()),
+            CostRange::Month => Some("month".to_string()),
+            CostRange::Custom { start, end: None } => Some(format!("since {}", start)),
+            CostRange::Custom {
+                start,
+                end: Some(end),
+            } => Some(format!("{}..{}", start, end)),
+        }
+    }
+}
+
+/// Timezone whose midnight starts the "today" window
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DayTimezone {
+    /// System local time, falling back to UTC if it can't be resolved (default)
+    #[default]
+    Local,
+    Utc,
+    /// Fixed UTC offset such as `+08:00`
+    Fixed(FixedOffset),
+}
+
+impl DayTimezone {
+    /// Parse `local`, `utc` or a fixed offset (`+08:00`, `-0530`, `+9`)
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        match value.to_lowercase().as_str() {
+            "local" => return Some(DayTimezone::Local),
+            "utc" | "z" => return Some(DayTimezone::Utc),
+            _ => {}
+        }
+
+        let (sign, rest) = match value.chars().next()? {
+            '+' => (1, &value[1..]),
+            '-' => (-1, &value[1..]),
+            _ => return None,
+        };
+        if !rest.is_ascii() {
+            return None;
+        }
+        let (hours, minutes) = match rest.split_once(':') {
+            Some((h, m)) => (h, m),
+            None if rest.len() > 2 => rest.split_at(2),
+            None => (rest, "0"),
+        };
+        let hours: i32 = hours.parse().ok()?;
+        let minutes: i32 = minutes.parse().ok()?;
+        if hours > 23 || minutes > 59 {
+            return None;
+        }
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).map(DayTimezone::Fixed)
+    }
+}
+
+/// How a zero (or near-zero) cost is displayed
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ZeroDisplay {
+    /// Show `¥0` (default)
+    #[default]
+    Show,
+    /// Hide the segment entirely
+    Hide,
+    /// Show custom text instead, configured as `custom:<text>`
+    Custom(String),
+}
+
+impl ZeroDisplay {
+    /// Parse `show`, `hide` or `custom:<text>`, returning None for unknown values
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "show" => Some(ZeroDisplay::Show),
+            "hide" => Some(ZeroDisplay::Hide),
+            _ => value
+                .strip_prefix("custom:")
+                .map(|text| ZeroDisplay::Custom(text.to_string())),
+        }
+    }
+}
+
+/// Whether the budget band is also shown as an emoji "mood"
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MoodDisplay {
+    /// No mood (default)
+    #[default]
+    Off,
+    /// Mood in front of the budget percentage
+    Prefix,
+    /// Mood in place of the budget percentage
+    Replace,
+}
+
+impl MoodDisplay {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(MoodDisplay::Off),
+            "prefix" => Some(MoodDisplay::Prefix),
+            "replace" => Some(MoodDisplay::Replace),
+            _ => None,
+        }
+    }
+}
+
+/// Which of several configured user tokens a render uses
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TokenSelection {
+    /// Always the first token (default)
+    #[default]
+    First,
+    /// The next token on each render, rotating through all of them
+    RoundRobin,
+    /// Every token, with their costs (and balances) summed
+    Aggregate,
+}
+
+impl TokenSelection {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "first" => Some(TokenSelection::First),
+            "round_robin" => Some(TokenSelection::RoundRobin),
+            "aggregate" => Some(TokenSelection::Aggregate),
+            _ => None,
+        }
+    }
+}
+
+/// What the warning/critical text color is based on
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ColorBy {
+    /// Today's cost against `daily_budget` (default)
+    #[default]
+    Absolute,
+    /// How fast the cost grows, per minute, over the cached history
+    Velocity,
+}
+
+impl ColorBy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "absolute" => Some(ColorBy::Absolute),
+            "velocity" => Some(ColorBy::Velocity),
+            _ => None,
+        }
+    }
+}
+
+/// Cost increase per minute over the trailing rising run of `history`
+/// Entries without a timestamp (written by older versions) are ignored, as is
+/// anything before the last drop (a window reset), so a restart reads as 0
+pub fn cost_velocity(history: &[f64], times: &[i64]) -> Option<f64> {
+    let count = history.len().min(times.len());
+    let samples: Vec<(i64, f64)> = times[times.len() - count..]
+        .iter()
+        .copied()
+        .zip(history[history.len() - count..].iter().copied())
+        .collect();
+
+    let mut start = samples.len().checked_sub(1)?;
+    while start > 0 && samples[start - 1].1 <= samples[start].1 {
+        start -= 1;
+    }
+
+    let (first_time, first_cost) = samples[start];
+    let (last_time, last_cost) = *samples.last()?;
+    let minutes = (last_time - first_time) as f64 / 60.0;
+    (minutes > 0.0).then(|| (last_cost - first_cost) / minutes)
+}
+
+/// Budget band of today's cost, used to pick the text color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BudgetBand {
+    Normal,
+    Warning,
+    Critical,
 }
 
 impl Default for NewApiCostSegment {
@@ -45,23 +611,88 @@ impl NewApiCostSegment {
         Self {
             base_url: None,
             user_token: None,
+            user_tokens: Vec::new(),
+            token_selection: TokenSelection::default(),
             user_id: None,
             token_name: None,
             provider: None,
             quota_per_unit: None,
+            daily_budget: None,
+            warning_threshold: None,
+            critical_threshold: None,
+            warning_color: None,
+            critical_color: None,
+            zero_display: ZeroDisplay::default(),
+            show_max: false,
+            locale: Locale::default(),
+            currency: "¥".to_string(),
+            exchange_rate: None,
+            secondary_currency: "$".to_string(),
+            compression: true,
+            proxy: None,
+            timezone: DayTimezone::default(),
+            day_start_hour: 0,
+            show_delta: false,
+            range: CostRange::default(),
+            wake_gap_secs: 600,
+            show_sparkline: false,
+            sparkline_length: 8,
+            timeout: Duration::from_secs(5),
+            response_mapping: ResponseMapping::default(),
+            stat_type: 2,
+            balance_path: None,
+            balance_field: "data.quota".to_string(),
+            mood: MoodDisplay::default(),
+            mood_emojis: ["😌".to_string(), "😐".to_string(), "😰".to_string()],
+            indicator_only: false,
+            indicator_glyph: "●".to_string(),
+            indicator_color: None,
+            color_by: ColorBy::default(),
+            velocity_warning: 0.5,
+            velocity_critical: 1.0,
+            show_since_checkpoint: false,
+            show_projection: false,
+            projection_min_hours: 2.0,
         }
     }
 
     /// Load configuration from segment options HashMap
-    pub fn with_config_from_options(mut self, options: &HashMap<String, serde_json::Value>) -> Self {
+    pub fn with_config_from_options(
+        mut self,
+        options: &HashMap<String, serde_json::Value>,
+    ) -> Self {
         if let Some(value) = options.get("base_url") {
             self.base_url = value.as_str().map(|s| s.to_string());
         }
         if let Some(value) = options.get("user_token") {
-            self.user_token = value.as_str().map(|s| s.to_string());
+            // A list of tokens to rotate through, or a single token
+            self.user_tokens = match value.as_array() {
+                Some(tokens) => tokens
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .filter(|token| !token.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                None => Vec::new(),
+            };
+            self.user_token = match self.user_tokens.first() {
+                Some(first) => Some(first.clone()),
+                None => value.as_str().map(|s| s.to_string()),
+            };
+        }
+        if let Some(selection) = options
+            .get("token_selection")
+            .and_then(|v| v.as_str())
+            .and_then(TokenSelection::parse)
+        {
+            self.token_selection = selection;
         }
         if let Some(value) = options.get("user_id") {
-            self.user_id = value.as_str().map(|s| s.to_string());
+            // NewApi user ids are numeric, so `user_id = 42` works as well as `"42"`
+            self.user_id = match value {
+                serde_json::Value::Number(id) => Some(id.to_string()),
+                _ => value.as_str().map(|s| s.to_string()),
+            };
         }
         if let Some(value) = options.get("token_name") {
             self.token_name = value.as_str().map(|s| s.to_string());
@@ -72,6 +703,157 @@ impl NewApiCostSegment {
         if let Some(value) = options.get("quota_per_unit") {
             self.quota_per_unit = value.as_f64();
         }
+        if let Some(value) = options.get("daily_budget") {
+            self.daily_budget = value.as_f64();
+        }
+        if let Some(value) = options.get("warning_threshold") {
+            self.warning_threshold = value.as_f64();
+        }
+        if let Some(value) = options.get("critical_threshold") {
+            self.critical_threshold = value.as_f64();
+        }
+        if let Some(value) = options.get("warning_color") {
+            self.warning_color = serde_json::from_value(value.clone()).ok();
+        }
+        if let Some(value) = options.get("critical_color") {
+            self.critical_color = serde_json::from_value(value.clone()).ok();
+        }
+        if let Some(zero_display) = options
+            .get("zero_display")
+            .and_then(|v| v.as_str())
+            .and_then(ZeroDisplay::parse)
+        {
+            self.zero_display = zero_display;
+        }
+        if let Some(value) = options.get("show_max") {
+            self.show_max = value.as_bool().unwrap_or(false);
+        }
+        if let Some(value) = options.get("show_delta") {
+            self.show_delta = value.as_bool().unwrap_or(false);
+        }
+        if let Some(locale) = options
+            .get("locale")
+            .and_then(|v| v.as_str())
+            .and_then(Locale::from_name)
+        {
+            self.locale = locale;
+        }
+        if let Some(currency) = options.get("currency").and_then(|v| v.as_str()) {
+            self.currency = currency.to_string();
+        }
+        if let Some(value) = options.get("exchange_rate") {
+            self.exchange_rate = value.as_f64().filter(|rate| *rate > 0.0);
+        }
+        if let Some(currency) = options.get("secondary_currency").and_then(|v| v.as_str()) {
+            self.secondary_currency = currency.to_string();
+        }
+        if let Some(compression) = options.get("compression").and_then(|v| v.as_bool()) {
+            self.compression = compression;
+        }
+        self.timeout = super::io_timeout(options, self.timeout);
+        if let Some(value) = options
+            .get("show_since_checkpoint")
+            .and_then(|v| v.as_bool())
+        {
+            self.show_since_checkpoint = value;
+        }
+        if let Some(value) = options.get("show_projection").and_then(|v| v.as_bool()) {
+            self.show_projection = value;
+        }
+        if let Some(hours) = options.get("projection_min_hours").and_then(|v| v.as_f64()) {
+            self.projection_min_hours = hours.max(0.0);
+        }
+        if let Some(value) = options.get("show_sparkline") {
+            self.show_sparkline = value.as_bool().unwrap_or(false);
+        }
+        if let Some(length) = options.get("sparkline_length").and_then(|v| v.as_u64()) {
+            self.sparkline_length = (length as usize).max(1);
+        }
+        if let Some(wake_gap) = options.get("wake_gap").and_then(|v| v.as_i64()) {
+            self.wake_gap_secs = wake_gap;
+        }
+        if let Some(range) = CostRange::from_options(options) {
+            self.range = range;
+        }
+        if let Some(timezone) = options
+            .get("tz")
+            .and_then(|v| v.as_str())
+            .and_then(DayTimezone::parse)
+        {
+            self.timezone = timezone;
+        }
+        if let Some(hour) = options
+            .get("day_start_hour")
+            .and_then(|v| v.as_u64())
+            .filter(|hour| *hour < 24)
+        {
+            self.day_start_hour = hour as u32;
+        }
+        if let Some(value) = options.get("proxy") {
+            self.proxy = value
+                .as_str()
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string());
+        }
+        if let Some(stat_type) = options.get("stat_type").and_then(|v| v.as_u64()) {
+            self.stat_type = stat_type as u32;
+        }
+        if let Some(path) = options
+            .get("balance_path")
+            .and_then(|v| v.as_str())
+            .filter(|path| !path.is_empty())
+        {
+            self.balance_path = Some(path.to_string());
+        }
+        if let Some(field) = options
+            .get("balance_field")
+            .and_then(|v| v.as_str())
+            .filter(|field| !field.is_empty())
+        {
+            self.balance_field = field.to_string();
+        }
+        if let Some(value) = options.get("indicator_only").and_then(|v| v.as_bool()) {
+            self.indicator_only = value;
+        }
+        if let Some(glyph) = options.get("indicator_glyph").and_then(|v| v.as_str()) {
+            self.indicator_glyph = glyph.to_string();
+        }
+        if let Some(value) = options.get("indicator_color") {
+            self.indicator_color = serde_json::from_value(value.clone()).ok();
+        }
+        if let Some(color_by) = options
+            .get("color_by")
+            .and_then(|v| v.as_str())
+            .and_then(ColorBy::parse)
+        {
+            self.color_by = color_by;
+        }
+        if let Some(value) = options.get("velocity_warning").and_then(|v| v.as_f64()) {
+            self.velocity_warning = value;
+        }
+        if let Some(value) = options.get("velocity_critical").and_then(|v| v.as_f64()) {
+            self.velocity_critical = value;
+        }
+        if let Some(mood) = options
+            .get("mood")
+            .and_then(|v| v.as_str())
+            .and_then(MoodDisplay::parse)
+        {
+            self.mood = mood;
+        }
+        if let Some(emojis) = options.get("mood_emojis").and_then(|v| v.as_array()) {
+            for (slot, emoji) in self.mood_emojis.iter_mut().zip(emojis) {
+                if let Some(emoji) = emoji.as_str() {
+                    *slot = emoji.to_string();
+                }
+            }
+        }
+        if let Some(mapping) = options
+            .get("response_mapping")
+            .and_then(ResponseMapping::from_value)
+        {
+            self.response_mapping = mapping;
+        }
         self
     }
 
@@ -111,127 +893,2127 @@ impl NewApiCostSegment {
         self
     }
 
-    /// Get today's start and end timestamps (seconds since epoch)
-    /// Returns (start_of_today, current_time)
-    fn get_today_timestamps() -> (i64, i64) {
-        let now = Local::now();
+    /// Builder method for daily_budget
+    pub fn with_daily_budget(mut self, daily_budget: f64) -> Self {
+        self.daily_budget = Some(daily_budget);
+        self
+    }
 
-        // Set time to 00:00:00 for start of today
-        let start_of_day = now
-            .with_hour(0)
-            .and_then(|dt| dt.with_minute(0))
-            .and_then(|dt| dt.with_second(0))
-            .and_then(|dt| dt.with_nanosecond(0))
-            .unwrap_or(now);
+    /// Classify a cost against the daily budget using the configured thresholds
+    /// Thresholds are fractions of the budget (default: warning 0.8, critical 1.0)
+    pub fn budget_band(&self, cost: f64, budget: f64) -> BudgetBand {
+        let fraction = cost / budget;
+        let warning = self.warning_threshold.unwrap_or(0.8);
+        let critical = self.critical_threshold.unwrap_or(1.0);
 
-        (start_of_day.timestamp(), now.timestamp())
+        if fraction >= critical {
+            BudgetBand::Critical
+        } else if fraction >= warning {
+            BudgetBand::Warning
+        } else {
+            BudgetBand::Normal
+        }
     }
 
-    /// Fetch today's cost data from NewApi
-    fn fetch_today_cost(&self) -> Option<f64> {
-        // Validate required fields
-        let base_url = self.base_url.as_ref()?;
-        let user_token = self.user_token.as_ref()?;
-        let user_id = self.user_id.as_ref()?;
+    /// Band of a cost velocity (cost per minute)
+    pub fn velocity_band(&self, velocity: f64) -> BudgetBand {
+        if velocity >= self.velocity_critical {
+            BudgetBand::Critical
+        } else if velocity >= self.velocity_warning {
+            BudgetBand::Warning
+        } else {
+            BudgetBand::Normal
+        }
+    }
 
-        // Get today's timestamps
-        let (start_timestamp, end_timestamp) = Self::get_today_timestamps();
+    /// Emoji for a budget band
+    pub fn mood_emoji(&self, band: BudgetBand) -> &str {
+        match band {
+            BudgetBand::Normal => &self.mood_emojis[0],
+            BudgetBand::Warning => &self.mood_emojis[1],
+            BudgetBand::Critical => &self.mood_emojis[2],
+        }
+    }
 
-        // Build query parameters
-        let mut url = format!(
-            "{}/api/log/self/stat?start_timestamp={}&end_timestamp={}&type=2",
-            base_url, start_timestamp, end_timestamp
-        );
+    /// Color of the `indicator_only` glyph for a budget band
+    pub fn indicator_band_color(&self, band: BudgetBand) -> AnsiColor {
+        self.band_color(band).unwrap_or_else(|| {
+            self.indicator_color
+                .clone()
+                .unwrap_or(AnsiColor::Color16 { c16: 10 })
+        })
+    }
 
-        // Add token_name if provided
-        if let Some(token_name) = &self.token_name {
-            if !token_name.is_empty() {
-                url.push_str(&format!("&token_name={}", token_name));
-            }
+    /// Text color override for a budget band (None keeps the theme color)
+    fn band_color(&self, band: BudgetBand) -> Option<AnsiColor> {
+        match band {
+            BudgetBand::Normal => None,
+            BudgetBand::Warning => Some(
+                self.warning_color
+                    .clone()
+                    .unwrap_or(AnsiColor::Color16 { c16: 11 }),
+            ),
+            BudgetBand::Critical => Some(
+                self.critical_color
+                    .clone()
+                    .unwrap_or(AnsiColor::Color16 { c16: 9 }),
+            ),
         }
+    }
 
-        // Get timeout from config (default 5 seconds)
-        let timeout_secs = self.get_timeout_from_config().unwrap_or(5);
+    /// Whether a cost counts as zero for display purposes
+    fn is_zero_cost(cost: f64) -> bool {
+        cost == 0.0 || cost < 0.01
+    }
 
-        // Build HTTP client
-        let agent = ureq::AgentBuilder::new()
-            .timeout(std::time::Duration::from_secs(timeout_secs))
-            .build();
+    /// Format an absolute cost value, applying `zero_display` to near-zero values
+    pub fn format_cost(&self, cost: f64) -> String {
+        if Self::is_zero_cost(cost) {
+            match &self.zero_display {
+                ZeroDisplay::Custom(text) => text.clone(),
+                _ => self.locale.place_currency("0", &self.currency),
+            }
+        } else {
+            self.locale.format_currency(cost, &self.currency, 2)
+        }
+    }
 
-        // Send GET request
-        let response = agent
-            .get(&url)
-            .set("Content-Type", "application/json")
-            .set("Authorization", &format!("Bearer {}", user_token))
-            .set("New-Api-User", user_id)
-            .call()
-            .ok()?;
+    /// Cost in the secondary currency, when an `exchange_rate` is configured
+    pub fn convert_cost(&self, cost: f64) -> Option<f64> {
+        self.exchange_rate.map(|rate| cost * rate)
+    }
 
-        // Check status code
-        if response.status() != 200 {
-            return None;
+    /// Format the cost, followed by its secondary-currency value when
+    /// converting, e.g. `¥12.50 ($1.73)`
+    pub fn format_cost_with_conversion(&self, cost: f64) -> String {
+        let base = self.format_cost(cost);
+        match self.convert_cost(cost) {
+            Some(converted) if !Self::is_zero_cost(cost) => format!(
+                "{} ({})",
+                base,
+                self.locale
+                    .format_currency(converted, &self.secondary_currency, 2)
+            ),
+            _ => base,
         }
+    }
+
+    fn cache() -> Cache<String, NewApiCostCache> {
+        Cache::new("newapi_cost")
+    }
 
-        // Parse response
-        let api_response: NewApiStatResponse = response.into_json().ok()?;
+    fn load_cache() -> Option<NewApiCostCache> {
+        Self::cache().get(&CACHE_KEY.to_string())
+    }
 
-        // Check success flag
-        if !api_response.success {
-            return None;
+    fn save_cache(cache: &NewApiCostCache) {
+        let _ = Self::cache().insert(CACHE_KEY.to_string(), cache);
+    }
+
+    /// Record an observed cost, returning the window's high-water mark, the
+    /// cost seen by the previous render (None on the first render of a window)
+    /// and the recent cost history (oldest first, at most `sparkline_length` values)
+    /// with the time each value was seen
+    /// The mark and previous cost reset whenever the window start changes, e.g. at midnight
+    /// `token_key` selects a rotated token's own state (see `token_state_key`)
+    fn record_cost(
+        &self,
+        token_key: Option<&str>,
+        cost: f64,
+        day_start: i64,
+    ) -> (f64, Option<f64>, Vec<f64>, Vec<i64>) {
+        let mut cache = Self::load_cache().unwrap_or_default();
+        let now = Utc::now().timestamp();
+        cache.updated_at = now;
+        let state = cache.state_mut(token_key);
+
+        if state.day_start != day_start {
+            *state = NewApiCostCache {
+                day_start,
+                updated_at: state.updated_at,
+                wake_retry_at: state.wake_retry_at,
+                history: std::mem::take(&mut state.history),
+                history_times: std::mem::take(&mut state.history_times),
+                token_index: state.token_index,
+                checkpoint: state.checkpoint,
+                checkpoint_window: state.checkpoint_window,
+                tokens: std::mem::take(&mut state.tokens),
+                ..NewApiCostCache::default()
+            };
         }
 
-        // Calculate cost: quota / quota_per_unit (default 500000)
-        let quota_per_unit = self.quota_per_unit.unwrap_or(500000.0);
-        let cost = api_response.data.quota as f64 / quota_per_unit;
+        let previous_cost = state.last_cost;
+        state.max_cost = state.max_cost.max(cost);
+        state.last_cost = Some(cost);
 
-        Some(cost)
-    }
+        state.history.push(cost);
+        let overflow = state.history.len().saturating_sub(self.sparkline_length);
+        state.history.drain(..overflow);
+        state.history_times.push(now);
+        let overflow = state
+            .history_times
+            .len()
+            .saturating_sub(state.history.len());
+        state.history_times.drain(..overflow);
 
-    /// Get timeout configuration from segment options
-    fn get_timeout_from_config(&self) -> Option<u64> {
-        let config = crate::config::Config::load().ok()?;
-        let segment_config = config
-            .segments
-            .iter()
-            .find(|s| s.id == SegmentId::NewApiCost)?;
+        let result = (
+            state.max_cost,
+            previous_cost,
+            state.history.clone(),
+            state.history_times.clone(),
+        );
+        Self::save_cache(&cache);
+        result
+    }
 
-        segment_config
-            .options
-            .get("timeout")
-            .and_then(|v| v.as_u64())
+    /// Key of a rotated token's cost state; a stable hash, so the token itself
+    /// never lands in the cache file and the key survives toolchain upgrades
+    fn token_state_key(token: &str) -> String {
+        format!("{:016x}", crate::core::palette::fnv1a(token))
     }
-}
 
-impl Segment for NewApiCostSegment {
-    fn collect(&self, _input: &InputData) -> Option<SegmentData> {
-        // Fetch today's cost from API
-        let cost = self.fetch_today_cost()?;
+    /// State key of the token this render was fetched with, when rotating
+    fn rendered_token_key(&self, fetchers: &[Cow<'_, Self>]) -> Option<String> {
+        if !self.rotates_tokens() {
+            return None;
+        }
+        fetchers
+            .first()
+            .and_then(|fetcher| fetcher.user_token.as_deref())
+            .map(Self::token_state_key)
+    }
 
-        // Primary display: today's cost
-        let primary = if cost == 0.0 || cost < 0.01 {
-            "¥0".to_string()
+    /// Fetch the current cost the way renders do and keep it as the checkpoint
+    /// mark, returning it; with `round_robin` every token is marked on its own
+    /// and the sum is returned
+    pub fn set_checkpoint(&self) -> Option<f64> {
+        let marks: Vec<(Option<String>, f64)> = if self.rotates_tokens() {
+            self.user_tokens
+                .iter()
+                .map(|token| {
+                    let cost = self.for_token(token).fetch_cost_once()?;
+                    Some((Some(Self::token_state_key(token)), cost))
+                })
+                .collect::<Option<_>>()?
         } else {
-            format!("¥{:.2}", cost)
+            let cost = self
+                .token_fetchers()
+                .iter()
+                .map(|fetcher| fetcher.fetch_cost_once())
+                .sum::<Option<f64>>()?;
+            vec![(None, cost)]
         };
+        let (window_start, _) = self.window_timestamps();
 
-        // Secondary display: could be used for additional info (e.g., provider name)
-        let secondary = self.provider.clone().unwrap_or_default();
+        let mut cache = Self::load_cache().unwrap_or_default();
+        for (token_key, cost) in &marks {
+            let state = cache.state_mut(token_key.as_deref());
+            state.checkpoint = Some(*cost);
+            state.checkpoint_window = window_start;
+        }
+        Self::save_cache(&cache);
+        Some(marks.iter().map(|(_, cost)| cost).sum())
+    }
 
-        // Store metadata
-        let mut metadata = HashMap::new();
-        metadata.insert("cost".to_string(), cost.to_string());
-        if let Some(provider) = &self.provider {
-            metadata.insert("provider".to_string(), provider.clone());
+    /// Forget the checkpoint mark; returns whether there was one
+    pub fn clear_checkpoint() -> bool {
+        let mut cache = match Self::load_cache() {
+            Some(cache) => cache,
+            None => return false,
+        };
+        let mut had_checkpoint = cache.checkpoint.take().is_some();
+        for state in cache.tokens.values_mut() {
+            had_checkpoint |= state.checkpoint.take().is_some();
         }
+        Self::save_cache(&cache);
+        had_checkpoint
+    }
 
-        Some(SegmentData {
-            primary,
-            secondary,
-            metadata,
-        })
+    /// Checkpoint mark for the window starting at `window_start`; a mark from an
+    /// earlier window doesn't apply since the cost has been reset since
+    fn checkpoint_for(token_key: Option<&str>, window_start: i64) -> Option<f64> {
+        let cache = Self::load_cache()?;
+        cache
+            .state(token_key)
+            .filter(|state| state.checkpoint_window == window_start)
+            .and_then(|state| state.checkpoint)
     }
 
-    fn id(&self) -> SegmentId {
-        SegmentId::NewApiCost
+    /// Spend since a checkpoint mark, never below zero
+    pub fn since_checkpoint(cost: f64, checkpoint: f64) -> f64 {
+        (cost - checkpoint).max(0.0)
+    }
+
+    /// Token for this render with `token_selection = "round_robin"`, advancing
+    /// the rotation kept in the cache; None when there is nothing to rotate
+    fn next_rotated_token(&self) -> Option<String> {
+        if !self.rotates_tokens() {
+            return None;
+        }
+
+        let mut cache = Self::load_cache().unwrap_or_default();
+        let (index, next) = Self::rotation(cache.token_index, self.user_tokens.len());
+        cache.token_index = next;
+        Self::save_cache(&cache);
+        self.user_tokens.get(index).cloned()
+    }
+
+    /// Whether renders rotate through several tokens
+    fn rotates_tokens(&self) -> bool {
+        self.token_selection == TokenSelection::RoundRobin && self.user_tokens.len() > 1
+    }
+
+    /// Copy of the segment that fetches with just `token`
+    fn for_token(&self, token: &str) -> Self {
+        let mut single = self.clone();
+        single.user_token = Some(token.to_string());
+        single.user_tokens.clear();
+        single
+    }
+
+    /// Segments to fetch with for this render, one per token `token_selection` picks
+    fn token_fetchers(&self) -> Vec<Cow<'_, Self>> {
+        if self.token_selection == TokenSelection::Aggregate && self.user_tokens.len() > 1 {
+            return self
+                .user_tokens
+                .iter()
+                .map(|token| Cow::Owned(self.for_token(token)))
+                .collect();
+        }
+        match self.next_rotated_token() {
+            Some(token) => vec![Cow::Owned(self.for_token(&token))],
+            None => vec![Cow::Borrowed(self)],
+        }
+    }
+
+    /// Token index to use now and the one stored for the next render, wrapping at `count`
+    /// A stored index past the end (the list got shorter) starts over at 0
+    pub fn rotation(stored: usize, count: usize) -> (usize, usize) {
+        let index = if stored < count { stored } else { 0 };
+        (index, (index + 1) % count.max(1))
+    }
+
+    /// Whether a failed fetch should be retried because the last successful fetch is
+    /// old enough to suggest the machine just woke from sleep (network still coming up)
+    /// Only the first failure after the gap qualifies; the attempt is recorded in the cache
+    fn claim_wake_retry(&self, now: i64) -> bool {
+        let mut cache = match Self::load_cache() {
+            Some(cache) => cache,
+            None => return false,
+        };
+
+        let long_gap = cache.updated_at > 0 && now - cache.updated_at > self.wake_gap_secs;
+        if !long_gap || cache.wake_retry_at > cache.updated_at {
+            return false;
+        }
+
+        cache.wake_retry_at = now;
+        Self::save_cache(&cache);
+        true
+    }
+
+    /// Increase since the previous render, or None if it didn't grow visibly
+    fn cost_delta(cost: f64, previous_cost: Option<f64>) -> Option<f64> {
+        previous_cost
+            .map(|previous| cost - previous)
+            .filter(|delta| !Self::is_zero_cost(*delta))
+    }
+
+    /// Start and end of the configured cost window (seconds since epoch)
+    fn window_timestamps(&self) -> (i64, i64) {
+        self.window_at(Utc::now())
+    }
+
+    /// Start and end of the cost window for the given moment
+    /// Day boundaries follow the configured timezone and start at `day_start_hour`
+    pub fn window_at(&self, now: DateTime<Utc>) -> (i64, i64) {
+        let timezone = self.effective_timezone();
+        // Before the boundary hour, the billing day is still the previous calendar day
+        let shifted = now - chrono::Duration::hours(self.day_start_hour as i64);
+        let today = Self::date_in(timezone, shifted);
+        let now_timestamp = now.timestamp();
+
+        let start_date = match self.range {
+            CostRange::Today => today,
+            CostRange::Week => today.checked_sub_days(Days::new(6)).unwrap_or(today),
+            CostRange::Month => today.with_day(1).unwrap_or(today),
+            CostRange::Custom { start, .. } => start,
+        };
+        let start =
+            Self::day_start_of(timezone, start_date, self.day_start_hour).unwrap_or(now_timestamp);
+
+        // A custom end date is inclusive, so the window runs to the next day's start
+        let end = match self.range {
+            CostRange::Custom { end: Some(end), .. } => end
+                .succ_opt()
+                .and_then(|next| Self::day_start_of(timezone, next, self.day_start_hour))
+                .map_or(now_timestamp, |end| end.min(now_timestamp)),
+            _ => now_timestamp,
+        };
+
+        (start, end)
+    }
+
+    /// End-of-day total if the cost keeps growing at its average pace so far
+    /// Only for the `today` range; None during the first `projection_min_hours`,
+    /// when a few minutes of usage would extrapolate to wild numbers
+    pub fn projected_total(&self, cost: f64, now: DateTime<Utc>) -> Option<f64> {
+        if self.range != CostRange::Today {
+            return None;
+        }
+
+        let timezone = self.effective_timezone();
+        let shifted = now - chrono::Duration::hours(self.day_start_hour as i64);
+        let today = Self::date_in(timezone, shifted);
+        let start = Self::day_start_of(timezone, today, self.day_start_hour)?;
+        // The next day's start rather than 24h, so DST days get their real length
+        let end = Self::day_start_of(timezone, today.succ_opt()?, self.day_start_hour)?;
+
+        let elapsed = (now.timestamp() - start) as f64;
+        let length = (end - start) as f64;
+        if length <= 0.0 || elapsed <= 0.0 || elapsed < self.projection_min_hours * 3600.0 {
+            return None;
+        }
+        Some(cost * length / elapsed.min(length))
+    }
+
+    /// Configured timezone, or UTC with a one-time warning when local time can't be resolved
+    fn effective_timezone(&self) -> DayTimezone {
+        Self::fallback_timezone(self.timezone, Self::local_timezone_available())
+    }
+
+    fn fallback_timezone(timezone: DayTimezone, local_available: bool) -> DayTimezone {
+        if timezone == DayTimezone::Local && !local_available {
+            static WARN_ONCE: Once = Once::new();
+            WARN_ONCE.call_once(|| {
+                crate::log_warn!("local timezone unavailable, using UTC day boundary");
+            });
+            return DayTimezone::Utc;
+        }
+        timezone
+    }
+
+    /// Calendar date of `now` in a timezone
+    fn date_in(timezone: DayTimezone, now: DateTime<Utc>) -> NaiveDate {
+        match timezone {
+            DayTimezone::Local => now.with_timezone(&Local).date_naive(),
+            DayTimezone::Utc => now.date_naive(),
+            DayTimezone::Fixed(offset) => now.with_timezone(&offset).date_naive(),
+        }
+    }
+
+    /// Start of the billing day `date` (at `hour`) in a timezone (seconds since epoch)
+    /// Falls back to UTC if that local time doesn't exist (DST gap)
+    fn day_start_of(timezone: DayTimezone, date: NaiveDate, hour: u32) -> Option<i64> {
+        let day_start = date.and_hms_opt(hour, 0, 0)?;
+        let zoned_start = match timezone {
+            DayTimezone::Local => Local
+                .from_local_datetime(&day_start)
+                .earliest()
+                .map(|dt| dt.timestamp()),
+            DayTimezone::Utc => None,
+            DayTimezone::Fixed(offset) => offset
+                .from_local_datetime(&day_start)
+                .earliest()
+                .map(|dt| dt.timestamp()),
+        };
+        Some(zoned_start.unwrap_or_else(|| Utc.from_utc_datetime(&day_start).timestamp()))
+    }
+
+    /// Whether the system has timezone data to resolve local time
+    /// Minimal containers often have neither `TZ` nor /etc/localtime
+    fn local_timezone_available() -> bool {
+        if !cfg!(unix) {
+            return true;
+        }
+        std::env::var("TZ")
+            .map(|tz| !tz.is_empty())
+            .unwrap_or(false)
+            || std::path::Path::new("/etc/localtime").exists()
+    }
+
+    /// Fetch the cost for the configured range from NewApi
+    fn fetch_cost(&self, agent: &ureq::Agent) -> Option<f64> {
+        let (start_timestamp, end_timestamp) = self.window_timestamps();
+
+        self.fetch_cost_with(agent, start_timestamp, end_timestamp)
+    }
+
+    /// Fetch the cost for an explicit time window (seconds since epoch)
+    /// Only `base_url` determines where the request goes, so this can be
+    /// pointed at any local server speaking the NewApi stat protocol
+    pub fn fetch_cost_between(&self, start_timestamp: i64, end_timestamp: i64) -> Option<f64> {
+        let agent = self.build_agent(self.timeout);
+        self.fetch_cost_with(&agent, start_timestamp, end_timestamp)
+    }
+
+    fn fetch_cost_with(
+        &self,
+        agent: &ureq::Agent,
+        start_timestamp: i64,
+        end_timestamp: i64,
+    ) -> Option<f64> {
+        let url = self.build_stat_url(start_timestamp, end_timestamp)?;
+
+        if let Some(quota) = self.request_stat(agent, &url) {
+            return Some(self.quota_to_cost(quota));
+        }
+
+        // First failure after a long gap (e.g. wake from sleep): retry once with a longer timeout
+        if !self.claim_wake_retry(Utc::now().timestamp()) {
+            return None;
+        }
+        crate::log_debug!("retrying NewApi request after a long gap");
+        let agent = self.build_agent(self.timeout * 2);
+        let quota = self.request_stat(&agent, &url)?;
+
+        Some(self.quota_to_cost(quota))
+    }
+
+    /// Fetch the configured window's cost with a single request (no retry),
+    /// for timing the API in isolation
+    pub fn fetch_cost_once(&self) -> Option<f64> {
+        let (start_timestamp, end_timestamp) = self.window_timestamps();
+        let url = self.build_stat_url(start_timestamp, end_timestamp)?;
+        let agent = self.build_agent(self.timeout);
+        let quota = self.request_stat(&agent, &url)?;
+
+        Some(self.quota_to_cost(quota))
+    }
+
+    /// Build the self-stat request URL for a time window
+    pub fn build_stat_url(&self, start_timestamp: i64, end_timestamp: i64) -> Option<String> {
+        let base_url = self.base_url.as_ref()?.trim_end_matches('/');
+
+        // Build query parameters
+        let mut url = format!(
+            "{}/api/log/self/stat?start_timestamp={}&end_timestamp={}&type={}",
+            base_url, start_timestamp, end_timestamp, self.stat_type
+        );
+
+        // Add token_name if provided
+        if let Some(token_name) = &self.token_name {
+            if !token_name.is_empty() {
+                url.push_str(&format!("&token_name={}", token_name));
+            }
+        }
+
+        Some(url)
+    }
+
+    /// Build the HTTP client used for NewApi requests
+    fn build_agent(&self, timeout: Duration) -> ureq::Agent {
+        let mut builder = ureq::AgentBuilder::new().timeout(timeout);
+
+        if let Some(proxy) = self
+            .resolve_proxy()
+            .and_then(|url| ureq::Proxy::new(url).ok())
+        {
+            builder = builder.proxy(proxy);
+        }
+
+        builder.build()
+    }
+
+    /// Proxy URL to use for requests to base_url
+    /// The `proxy` option wins; otherwise HTTPS_PROXY/HTTP_PROXY apply unless NO_PROXY excludes the host
+    pub fn resolve_proxy(&self) -> Option<String> {
+        if let Some(proxy) = &self.proxy {
+            return Some(proxy.clone());
+        }
+
+        let base_url = self.base_url.as_deref()?;
+        let host = url_host(base_url)?;
+        if let Some(no_proxy) = env_var_any(&["NO_PROXY", "no_proxy"]) {
+            if no_proxy_matches(&no_proxy, &host) {
+                return None;
+            }
+        }
+
+        if base_url.starts_with("https://") {
+            env_var_any(&["HTTPS_PROXY", "https_proxy"])
+        } else {
+            env_var_any(&["HTTP_PROXY", "http_proxy"])
+        }
+    }
+
+    /// Create an authenticated GET request
+    /// With compression enabled ureq advertises gzip and decompresses transparently
+    fn authorized_get(&self, agent: &ureq::Agent, url: &str) -> Option<ureq::Request> {
+        // Validate required fields
+        let user_token = self.user_token.as_ref()?;
+        let user_id = self.user_id.as_ref()?;
+
+        let mut request = agent
+            .get(url)
+            .set("Content-Type", "application/json")
+            .set("Authorization", &format!("Bearer {}", user_token))
+            .set("New-Api-User", user_id);
+
+        if !self.compression {
+            request = request.set("Accept-Encoding", "identity");
+        }
+
+        Some(request)
+    }
+
+    /// Fetch the remaining balance from `balance_path`, when configured
+    fn fetch_balance(&self, agent: &ureq::Agent) -> Option<f64> {
+        let path = self.balance_path.as_deref()?;
+        let base_url = self.base_url.as_ref()?.trim_end_matches('/');
+        let url = format!("{}/{}", base_url, path.trim_start_matches('/'));
+        crate::log_debug!("GET {}", url);
+
+        let response = match self.authorized_get(agent, &url)?.call() {
+            Ok(response) => response,
+            Err(e) => {
+                crate::log_warn!("NewApi balance request failed: {}", e);
+                return None;
+            }
+        };
+
+        let mapping = ResponseMapping {
+            quota: self.balance_field.clone(),
+            ..ResponseMapping::default()
+        };
+        let quota = mapping
+            .read_body(response.into_reader())
+            .map_err(|e| e.to_string())
+            .and_then(|body| mapping.parse(&body));
+        match quota {
+            Ok(quota) => Some(self.quota_to_cost(quota)),
+            Err(e) => {
+                crate::log_warn!("NewApi balance could not be read: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Send the stat request and read the quota from a successful response
+    fn request_stat(&self, agent: &ureq::Agent, url: &str) -> Option<f64> {
+        let started = std::time::Instant::now();
+        crate::log_debug!("GET {}", url);
+
+        // Send GET request
+        let response = match self.authorized_get(agent, url)?.call() {
+            Ok(response) => response,
+            Err(e) => {
+                crate::log_warn!("NewApi request failed: {}", e);
+                return None;
+            }
+        };
+        crate::log_debug!(
+            "NewApi responded {} in {}ms",
+            response.status(),
+            started.elapsed().as_millis()
+        );
+
+        // Check status code
+        if response.status() != 200 {
+            return None;
+        }
+
+        // Parse the response as it streams in, keeping only the mapped fields
+        let body = match self.response_mapping.read_body(response.into_reader()) {
+            Ok(body) => body,
+            Err(e) => {
+                crate::log_warn!("NewApi response could not be parsed: {}", e);
+                return None;
+            }
+        };
+
+        // Check success flag and read the quota per the response mapping
+        match self.response_mapping.parse(&body) {
+            Ok(quota) => Some(quota),
+            Err(e) => {
+                crate::log_warn!("NewApi reported failure: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Convert a raw quota value into cost: quota / quota_per_unit (default 500000)
+    pub fn quota_to_cost(&self, quota: f64) -> f64 {
+        let quota_per_unit = self.quota_per_unit.unwrap_or(500000.0);
+        quota / quota_per_unit
+    }
+}
+
+impl Segment for NewApiCostSegment {
+    fn collect(&self, _input: &InputData) -> Option<SegmentData> {
+        // One agent serves both the cost and the balance request
+        let agent = self.build_agent(self.timeout);
+        let fetchers = self.token_fetchers();
+
+        // Fetch the configured range's cost from API, summed when aggregating tokens
+        let cost = match fetchers
+            .iter()
+            .map(|fetcher| fetcher.fetch_cost(&agent))
+            .sum::<Option<f64>>()
+        {
+            Some(cost) => cost,
+            None => return Some(SegmentData::error("NewApi cost request failed")),
+        };
+
+        if Self::is_zero_cost(cost) && self.zero_display == ZeroDisplay::Hide {
+            return None;
+        }
+
+        let mut metadata = HashMap::new();
+        metadata.insert("cost".to_string(), cost.to_string());
+        if let Some(converted) = self.convert_cost(cost) {
+            metadata.insert("converted_cost".to_string(), converted.to_string());
+        }
+        if let Some(provider) = &self.provider {
+            metadata.insert("provider".to_string(), provider.clone());
+            metadata.insert("label".to_string(), provider.clone());
+        }
+
+        let (mut primary, base_secondary) = match self.daily_budget.filter(|budget| *budget > 0.0) {
+            Some(budget) => {
+                // Budget display: percentage of budget, absolute value in secondary
+                let percentage = cost / budget * 100.0;
+                let budget_decimals = if budget.fract() == 0.0 { 0 } else { 2 };
+                let budget_display =
+                    self.locale
+                        .format_currency(budget, &self.currency, budget_decimals);
+
+                let band = self.budget_band(cost, budget);
+                if self.color_by == ColorBy::Absolute {
+                    if let Some(color) = self.band_color(band) {
+                        if let Ok(color_json) = serde_json::to_string(&color) {
+                            metadata.insert("dynamic_text_color".to_string(), color_json);
+                        }
+                    }
+                }
+                metadata.insert("budget".to_string(), budget.to_string());
+                metadata.insert("budget_percentage".to_string(), percentage.to_string());
+                metadata.insert("budget_band".to_string(), format!("{:?}", band));
+
+                let budget_text = format!("{:.0}% of {}", percentage, budget_display);
+                let primary = match self.mood {
+                    MoodDisplay::Off => budget_text,
+                    MoodDisplay::Prefix => format!("{} {}", self.mood_emoji(band), budget_text),
+                    MoodDisplay::Replace => self.mood_emoji(band).to_string(),
+                };
+                (primary, self.format_cost_with_conversion(cost))
+            }
+            None => {
+                // Primary display: today's cost
+                // Secondary display: provider name
+                (
+                    self.format_cost_with_conversion(cost),
+                    self.provider.clone().unwrap_or_default(),
+                )
+            }
+        };
+
+        let mut secondary_parts = vec![base_secondary];
+
+        // The balance is optional: a failed request leaves the cost untouched
+        let balance = fetchers
+            .iter()
+            .map(|fetcher| fetcher.fetch_balance(&agent))
+            .sum::<Option<f64>>();
+        if let Some(balance) = balance {
+            metadata.insert("balance".to_string(), balance.to_string());
+            let decimals = if balance.fract() == 0.0 { 0 } else { 2 };
+            secondary_parts.push(format!(
+                "{} left",
+                self.locale
+                    .format_currency(balance, &self.currency, decimals)
+            ));
+        }
+
+        if let Some(label) = self.range.label() {
+            metadata.insert("range".to_string(), label.clone());
+            secondary_parts.push(label);
+        }
+
+        // High-water mark and previous render's cost for the current window
+        // A rotated token's cost is only ever compared with that token's earlier renders
+        let token_key = self.rendered_token_key(&fetchers);
+        let (window_start, _) = self.window_timestamps();
+        let (max_cost, previous_cost, history, history_times) =
+            self.record_cost(token_key.as_deref(), cost, window_start);
+        metadata.insert("max_cost".to_string(), max_cost.to_string());
+
+        // Velocity coloring works without a budget; too little history counts as normal
+        let velocity_band = match self.color_by {
+            ColorBy::Absolute => None,
+            ColorBy::Velocity => {
+                let velocity = cost_velocity(&history, &history_times).unwrap_or(0.0);
+                let band = self.velocity_band(velocity);
+                metadata.insert("cost_velocity".to_string(), velocity.to_string());
+                metadata.insert("velocity_band".to_string(), format!("{:?}", band));
+                if let Some(color) = self.band_color(band) {
+                    if let Ok(color_json) = serde_json::to_string(&color) {
+                        metadata.insert("dynamic_text_color".to_string(), color_json);
+                    }
+                }
+                Some(band)
+            }
+        };
+
+        if let Some(delta) = Self::cost_delta(cost, previous_cost) {
+            metadata.insert("cost_delta".to_string(), delta.to_string());
+            if self.show_delta {
+                // Shown next to the absolute cost: primary without a budget, secondary with one
+                let delta_display = format!(
+                    "(+{})",
+                    self.locale.format_currency(delta, &self.currency, 2)
+                );
+                if self.daily_budget.filter(|budget| *budget > 0.0).is_some() {
+                    secondary_parts.push(delta_display);
+                } else {
+                    primary = format!("{} {}", primary, delta_display);
+                }
+            }
+        }
+
+        if self.show_max {
+            secondary_parts.push(format!("(max {})", self.format_cost(max_cost)));
+        }
+
+        if let Some(checkpoint) = Self::checkpoint_for(token_key.as_deref(), window_start) {
+            let spent = Self::since_checkpoint(cost, checkpoint);
+            metadata.insert("checkpoint".to_string(), checkpoint.to_string());
+            metadata.insert("since_checkpoint".to_string(), spent.to_string());
+            if self.show_since_checkpoint {
+                secondary_parts.push(format!(
+                    "+{} since mark",
+                    self.locale.format_currency(spent, &self.currency, 2)
+                ));
+            }
+        }
+
+        if let Some(projection) = self.projected_total(cost, Utc::now()) {
+            metadata.insert("projected_cost".to_string(), projection.to_string());
+            if self.show_projection {
+                secondary_parts.push(format!(
+                    "~{}",
+                    self.locale.format_currency(projection, &self.currency, 0)
+                ));
+            }
+        }
+
+        if self.show_sparkline && history.len() > 1 {
+            secondary_parts.push(sparkline(&history));
+        }
+
+        let secondary = secondary_parts
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if self.indicator_only {
+            // Without a budget there is no band to show, so the glyph stays green
+            let band = match (
+                velocity_band,
+                self.daily_budget.filter(|budget| *budget > 0.0),
+            ) {
+                (Some(band), _) => band,
+                (None, Some(budget)) => self.budget_band(cost, budget),
+                (None, None) => BudgetBand::Normal,
+            };
+            if let Ok(color_json) = serde_json::to_string(&self.indicator_band_color(band)) {
+                metadata.insert("dynamic_text_color".to_string(), color_json);
+            }
+            metadata.insert("display".to_string(), primary);
+            return Some(SegmentData {
+                primary: self.indicator_glyph.clone(),
+                secondary: String::new(),
+                metadata,
+            });
+        }
+
+        let mut data = SegmentData {
+            primary,
+            secondary,
+            metadata,
+        };
+        // Without a budget the primary is the cost itself; offer compacter forms of it
+        if self.daily_budget.filter(|budget| *budget > 0.0).is_none() && !Self::is_zero_cost(cost) {
+            data.set_primary_alternatives(&self.locale.currency_candidates(
+                cost,
+                &self.currency,
+                2,
+            ));
+        }
+
+        Some(data)
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::NewApiCost
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(60))
+    }
+
+    fn uses_network(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::mock_http::{MockResponse, MockServer};
+    use crate::test_support::{input_data, isolated_cache};
+
+    fn segment_for(server: &MockServer, extra: serde_json::Value) -> NewApiCostSegment {
+        let mut options: HashMap<String, serde_json::Value> =
+            serde_json::from_value(serde_json::json!({
+                "base_url": server.url(),
+                "user_token": "sk-test",
+                "user_id": "42",
+            }))
+            .unwrap();
+        if let serde_json::Value::Object(extra) = extra {
+            options.extend(extra);
+        }
+        NewApiCostSegment::new().with_config_from_options(&options)
+    }
+
+    #[test]
+    fn numeric_user_id_is_sent_as_text() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(&stat_body(1.0));
+        let segment = segment_for(&server, serde_json::json!({"user_id": 42}));
+
+        assert_eq!(segment.user_id.as_deref(), Some("42"));
+        assert_eq!(segment.fetch_cost_between(100, 200), Some(1.0));
+        assert_eq!(server.requests()[0].header("New-Api-User"), Some("42"));
+    }
+
+    #[test]
+    fn fetch_sends_stat_request_with_auth_headers() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(r#"{"success":true,"message":"","data":{"quota":1000000}}"#);
+        let segment = segment_for(&server, serde_json::json!({"token_name": "ci"}));
+
+        assert_eq!(segment.fetch_cost_between(100, 200), Some(2.0));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert_eq!(request.method, "GET");
+        assert!(request.path.starts_with("/api/log/self/stat?"));
+        assert_eq!(request.query("start_timestamp"), Some("100"));
+        assert_eq!(request.query("end_timestamp"), Some("200"));
+        assert_eq!(request.query("type"), Some("2"));
+        assert_eq!(request.query("token_name"), Some("ci"));
+        assert_eq!(request.header("Authorization"), Some("Bearer sk-test"));
+        assert_eq!(request.header("New-Api-User"), Some("42"));
+    }
+
+    #[test]
+    fn fetch_reads_gzip_response() {
+        let _cache = isolated_cache();
+        let server = MockServer::start(vec![MockResponse::gzip_json(
+            r#"{"success":true,"data":{"quota":250000}}"#,
+        )]);
+        let segment = segment_for(&server, serde_json::json!({}));
+
+        assert_eq!(segment.fetch_cost_between(0, 1), Some(0.5));
+        let accept = server.requests()[0]
+            .header("Accept-Encoding")
+            .map(str::to_string);
+        assert!(accept.unwrap_or_default().contains("gzip"));
+    }
+
+    #[test]
+    fn fetch_applies_quota_per_unit() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(r#"{"success":true,"data":{"quota":1500}}"#);
+        let segment = segment_for(&server, serde_json::json!({"quota_per_unit": 1000}));
+
+        assert_eq!(segment.fetch_cost_between(0, 1), Some(1.5));
+    }
+
+    #[test]
+    fn fetch_fails_on_error_status_and_failure_flag() {
+        let _cache = isolated_cache();
+        let server = MockServer::start(vec![MockResponse::new(500, "oops")]);
+        assert_eq!(
+            segment_for(&server, serde_json::json!({})).fetch_cost_once(),
+            None
+        );
+
+        let server = MockServer::json(r#"{"success":false,"message":"bad token"}"#);
+        assert_eq!(
+            segment_for(&server, serde_json::json!({})).fetch_cost_once(),
+            None
+        );
+    }
+
+    #[test]
+    fn fetch_needs_token_and_user() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(r#"{"success":true,"data":{"quota":1}}"#);
+        let mut segment = segment_for(&server, serde_json::json!({}));
+        segment.user_id = None;
+
+        assert_eq!(segment.fetch_cost_once(), None);
+        assert!(server.requests().is_empty());
+    }
+
+    /// Quota response worth `cost` at the default 500000 quota per unit
+    fn stat_body(cost: f64) -> String {
+        format!(
+            r#"{{"success":true,"message":"","data":{{"quota":{}}}}}"#,
+            cost * 500000.0
+        )
+    }
+
+    fn collect_cost(cost: f64, options: serde_json::Value) -> SegmentData {
+        let server = MockServer::json(&stat_body(cost));
+        segment_for(&server, options)
+            .collect(&input_data(serde_json::json!({})))
+            .expect("segment data")
+    }
+
+    #[test]
+    fn budget_under_shows_percentage_without_color() {
+        let _cache = isolated_cache();
+        let data = collect_cost(5.0, serde_json::json!({"daily_budget": 20}));
+
+        assert_eq!(data.primary, "25% of ¥20");
+        assert_eq!(data.secondary, "¥5.00");
+        assert_eq!(data.metadata["budget_band"], "Normal");
+        assert!(!data.metadata.contains_key("dynamic_text_color"));
+    }
+
+    #[test]
+    fn budget_reached_is_critical() {
+        let _cache = isolated_cache();
+        let data = collect_cost(20.0, serde_json::json!({"daily_budget": 20}));
+
+        assert_eq!(data.primary, "100% of ¥20");
+        assert_eq!(data.metadata["budget_band"], "Critical");
+        assert!(data.metadata.contains_key("dynamic_text_color"));
+    }
+
+    #[test]
+    fn budget_exceeded_goes_past_100_percent() {
+        let _cache = isolated_cache();
+        let data = collect_cost(30.0, serde_json::json!({"daily_budget": 20}));
+
+        assert_eq!(data.primary, "150% of ¥20");
+        assert_eq!(data.secondary, "¥30.00");
+        assert_eq!(data.metadata["budget_band"], "Critical");
+    }
+
+    #[test]
+    fn budget_warning_band_uses_threshold() {
+        let segment = NewApiCostSegment::new();
+        assert_eq!(segment.budget_band(15.0, 20.0), BudgetBand::Normal);
+        assert_eq!(segment.budget_band(16.0, 20.0), BudgetBand::Warning);
+        assert_eq!(segment.budget_band(20.0, 20.0), BudgetBand::Critical);
+    }
+
+    #[test]
+    fn without_budget_shows_absolute_cost() {
+        let _cache = isolated_cache();
+        let data = collect_cost(5.0, serde_json::json!({"provider": "relay"}));
+
+        assert_eq!(data.primary, "¥5.00");
+        assert_eq!(data.secondary, "relay");
+    }
+
+    #[test]
+    fn zero_display_parses_each_mode() {
+        assert_eq!(ZeroDisplay::parse("show"), Some(ZeroDisplay::Show));
+        assert_eq!(ZeroDisplay::parse("hide"), Some(ZeroDisplay::Hide));
+        assert_eq!(
+            ZeroDisplay::parse("custom:no spend"),
+            Some(ZeroDisplay::Custom("no spend".to_string()))
+        );
+        assert_eq!(ZeroDisplay::parse("blank"), None);
+    }
+
+    #[test]
+    fn zero_cost_shows_zero_by_default() {
+        let _cache = isolated_cache();
+        assert_eq!(collect_cost(0.0, serde_json::json!({})).primary, "¥0");
+        assert_eq!(collect_cost(0.004, serde_json::json!({})).primary, "¥0");
+    }
+
+    #[test]
+    fn zero_cost_hidden() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(&stat_body(0.0));
+        let segment = segment_for(&server, serde_json::json!({"zero_display": "hide"}));
+
+        assert!(segment
+            .collect(&input_data(serde_json::json!({})))
+            .is_none());
+    }
+
+    #[test]
+    fn zero_cost_custom_text() {
+        let _cache = isolated_cache();
+        let data = collect_cost(0.0, serde_json::json!({"zero_display": "custom:☕"}));
+        assert_eq!(data.primary, "☕");
+
+        // Non-zero costs are unaffected
+        let data = collect_cost(1.5, serde_json::json!({"zero_display": "custom:☕"}));
+        assert_eq!(data.primary, "¥1.50");
+    }
+
+    #[test]
+    fn high_water_mark_rises_persists_and_resets() {
+        let _cache = isolated_cache();
+        let segment = NewApiCostSegment::new();
+        let (today, tomorrow) = (1_700_000_000, 1_700_086_400);
+
+        assert_eq!(segment.record_cost(None, 2.0, today).0, 2.0);
+        assert_eq!(segment.record_cost(None, 5.0, today).0, 5.0);
+        // A lower reading (e.g. a refund) keeps the mark
+        assert_eq!(segment.record_cost(None, 3.0, today).0, 5.0);
+        assert_eq!(NewApiCostSegment::load_cache().unwrap().max_cost, 5.0);
+
+        // A new day starts over
+        assert_eq!(segment.record_cost(None, 1.0, tomorrow).0, 1.0);
+    }
+
+    #[test]
+    fn show_max_appends_the_mark() {
+        let _cache = isolated_cache();
+        collect_cost(8.0, serde_json::json!({}));
+        let data = collect_cost(6.0, serde_json::json!({"show_max": true}));
+
+        assert_eq!(data.metadata["max_cost"], "8");
+        assert!(
+            data.secondary.ends_with("(max ¥8.00)"),
+            "{}",
+            data.secondary
+        );
+    }
+
+    #[test]
+    fn compression_off_asks_for_identity() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(&stat_body(1.0));
+        let segment = segment_for(&server, serde_json::json!({"compression": false}));
+
+        assert_eq!(segment.fetch_cost_once(), Some(1.0));
+        assert_eq!(
+            server.requests()[0].header("Accept-Encoding"),
+            Some("identity")
+        );
+    }
+
+    #[test]
+    fn proxy_option_routes_requests_through_the_proxy() {
+        let _cache = isolated_cache();
+        let proxy = MockServer::json(r#"{"success":true,"message":"","data":{"quota":500000}}"#);
+        let options: HashMap<String, serde_json::Value> =
+            serde_json::from_value(serde_json::json!({
+                "base_url": "http://newapi.invalid",
+                "user_token": "sk-test",
+                "user_id": "42",
+                "proxy": proxy.url(),
+            }))
+            .unwrap();
+        let segment = NewApiCostSegment::new().with_config_from_options(&options);
+
+        assert_eq!(segment.fetch_cost_between(100, 200), Some(1.0));
+        let requests = proxy.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(
+            requests[0].path.starts_with("http://newapi.invalid/api/"),
+            "{}",
+            requests[0].path
+        );
+    }
+
+    #[test]
+    fn proxy_resolution_honours_env_and_no_proxy() {
+        let mut env = crate::test_support::EnvGuard::new();
+        for name in [
+            "NO_PROXY",
+            "no_proxy",
+            "HTTPS_PROXY",
+            "https_proxy",
+            "HTTP_PROXY",
+            "http_proxy",
+        ] {
+            env.remove(name);
+        }
+        env.set("HTTPS_PROXY", "http://proxy.corp:3128");
+        let mut segment = NewApiCostSegment::new();
+        segment.base_url = Some("https://api.example.com/v1".to_string());
+
+        assert_eq!(
+            segment.resolve_proxy().as_deref(),
+            Some("http://proxy.corp:3128")
+        );
+
+        env.set("NO_PROXY", "localhost, .example.com");
+        assert_eq!(segment.resolve_proxy(), None);
+
+        segment.proxy = Some("http://explicit:8080".to_string());
+        assert_eq!(
+            segment.resolve_proxy().as_deref(),
+            Some("http://explicit:8080")
+        );
+    }
+
+    #[test]
+    fn url_host_strips_scheme_credentials_port_and_path() {
+        assert_eq!(
+            url_host("https://user:pw@API.example.com:8443/x?y#z").as_deref(),
+            Some("api.example.com")
+        );
+        assert_eq!(url_host("http://[::1]:3000/").as_deref(), Some("::1"));
+        assert_eq!(url_host("example.com?q=1").as_deref(), Some("example.com"));
+        assert_eq!(url_host("https:///path"), None);
+    }
+
+    #[test]
+    fn missing_local_timezone_falls_back_to_utc_day() {
+        assert_eq!(
+            NewApiCostSegment::fallback_timezone(DayTimezone::Local, false),
+            DayTimezone::Utc
+        );
+        assert_eq!(
+            NewApiCostSegment::fallback_timezone(DayTimezone::Local, true),
+            DayTimezone::Local
+        );
+
+        let mut segment = NewApiCostSegment::new();
+        segment.timezone = NewApiCostSegment::fallback_timezone(DayTimezone::Local, false);
+        let now = Utc.with_ymd_and_hms(2024, 3, 5, 13, 45, 0).unwrap();
+        let (start, end) = segment.window_at(now);
+        assert_eq!(
+            start,
+            Utc.with_ymd_and_hms(2024, 3, 5, 0, 0, 0)
+                .unwrap()
+                .timestamp()
+        );
+        assert_eq!(end, now.timestamp());
+    }
+
+    #[test]
+    fn first_render_has_no_delta() {
+        let _cache = isolated_cache();
+        let data = collect_cost(12.2, serde_json::json!({"show_delta": true}));
+
+        assert_eq!(data.primary, "¥12.20");
+        assert!(!data.metadata.contains_key("cost_delta"));
+    }
+
+    #[test]
+    fn delta_shows_increase_since_previous_render() {
+        let _cache = isolated_cache();
+        let options = serde_json::json!({"show_delta": true});
+        collect_cost(12.2, options.clone());
+
+        let data = collect_cost(12.5, options.clone());
+        assert_eq!(data.primary, "¥12.50 (+¥0.30)");
+
+        // No growth and a drop (e.g. a refund) are both shown without a delta
+        assert_eq!(collect_cost(12.5, options.clone()).primary, "¥12.50");
+        assert_eq!(collect_cost(10.0, options).primary, "¥10.00");
+    }
+
+    fn utc_segment(range: CostRange) -> NewApiCostSegment {
+        let mut segment = NewApiCostSegment::new();
+        segment.timezone = DayTimezone::Utc;
+        segment.range = range;
+        segment
+    }
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn range_windows_start_at_the_right_day() {
+        let now = utc(2024, 3, 3, 15);
+        let window = |range| utc_segment(range).window_at(now);
+
+        assert_eq!(
+            window(CostRange::Today),
+            (utc(2024, 3, 3, 0).timestamp(), now.timestamp())
+        );
+        // Rolling week crosses into a leap February
+        assert_eq!(
+            window(CostRange::Week),
+            (utc(2024, 2, 26, 0).timestamp(), now.timestamp())
+        );
+        assert_eq!(
+            window(CostRange::Month),
+            (utc(2024, 3, 1, 0).timestamp(), now.timestamp())
+        );
+    }
+
+    #[test]
+    fn month_window_handles_month_lengths() {
+        for (month, last_day) in [(1, 31), (2, 29), (4, 30), (12, 31)] {
+            let now = utc(2024, month, last_day, 23);
+            let (start, _) = utc_segment(CostRange::Month).window_at(now);
+            assert_eq!(start, utc(2024, month, 1, 0).timestamp(), "month {month}");
+        }
+        // The first of the month starts a fresh window
+        let (start, _) = utc_segment(CostRange::Month).window_at(utc(2023, 3, 1, 1));
+        assert_eq!(start, utc(2023, 3, 1, 0).timestamp());
+    }
+
+    #[test]
+    fn custom_window_includes_the_end_date() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 30).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 2, 2).unwrap();
+        let now = utc(2024, 3, 1, 12);
+
+        let segment = utc_segment(CostRange::Custom {
+            start,
+            end: Some(end),
+        });
+        assert_eq!(
+            segment.window_at(now),
+            (
+                utc(2024, 1, 30, 0).timestamp(),
+                utc(2024, 2, 3, 0).timestamp()
+            )
+        );
+
+        // An end date in the future is capped at now
+        let segment = utc_segment(CostRange::Custom {
+            start,
+            end: Some(NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()),
+        });
+        assert_eq!(segment.window_at(now).1, now.timestamp());
+    }
+
+    #[test]
+    fn range_option_parses_and_labels() {
+        let parse = |value: serde_json::Value| {
+            let options: HashMap<String, serde_json::Value> =
+                serde_json::from_value(value).unwrap();
+            CostRange::from_options(&options)
+        };
+
+        assert_eq!(
+            parse(serde_json::json!({"range": "week"})),
+            Some(CostRange::Week)
+        );
+        assert_eq!(parse(serde_json::json!({"range": "custom"})), None);
+        let custom = parse(serde_json::json!({
+            "range": "custom",
+            "start_date": "2024-02-01",
+        }))
+        .unwrap();
+        assert_eq!(custom.label().as_deref(), Some("since 2024-02-01"));
+        assert_eq!(CostRange::Month.label().as_deref(), Some("month"));
+        assert_eq!(CostRange::Today.label(), None);
+    }
+
+    fn save_last_success(updated_at: i64) {
+        NewApiCostSegment::save_cache(&NewApiCostCache {
+            updated_at,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn first_failure_after_long_gap_retries_with_longer_timeout() {
+        let _cache = isolated_cache();
+        save_last_success(Utc::now().timestamp() - 3600);
+        let server = MockServer::start(vec![
+            // Slower than the normal timeout but within the doubled one
+            MockResponse::json(&stat_body(2.0)).with_delay(Duration::from_millis(300)),
+            MockResponse::json(&stat_body(2.0)).with_delay(Duration::from_millis(300)),
+        ]);
+        let segment = segment_for(&server, serde_json::json!({"timeout_ms": 200}));
+
+        assert_eq!(segment.fetch_cost_between(100, 200), Some(2.0));
+        assert_eq!(server.requests().len(), 2);
+
+        // The retry is used up until the next successful fetch
+        assert_eq!(segment.fetch_cost_between(100, 200), None);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn failure_with_fresh_cache_is_not_retried() {
+        let _cache = isolated_cache();
+        save_last_success(Utc::now().timestamp() - 60);
+        let server = MockServer::start(vec![MockResponse::new(502, "bad gateway")]);
+        let segment = segment_for(&server, serde_json::json!({}));
+
+        assert_eq!(segment.fetch_cost_between(100, 200), None);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn sparkline_maps_values_onto_bar_levels() {
+        let ramp: Vec<f64> = (0..8).map(f64::from).collect();
+        assert_eq!(sparkline(&ramp), "▁▂▃▄▅▆▇█");
+        assert_eq!(sparkline(&[1.0, 5.0, 3.0]), "▁█▅");
+        assert_eq!(sparkline(&[2.5, 2.5, 2.5]), "▁▁▁");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn sparkline_shows_the_most_recent_history() {
+        let _cache = isolated_cache();
+        let options = serde_json::json!({"show_sparkline": true, "sparkline_length": 3});
+
+        // A single value has no trend to show
+        assert_eq!(collect_cost(1.0, options.clone()).secondary, "");
+        collect_cost(2.0, options.clone());
+        collect_cost(4.0, options.clone());
+        let data = collect_cost(3.0, options);
+        assert_eq!(data.secondary, "▁█▅");
+    }
+
+    #[test]
+    fn day_starting_at_4am_covers_late_night_work() {
+        let mut segment = utc_segment(CostRange::Today);
+        segment.day_start_hour = 4;
+
+        // 03:00 still belongs to the billing day that began yesterday at 04:00
+        let before = utc(2024, 3, 5, 3);
+        assert_eq!(segment.window_at(before).0, utc(2024, 3, 4, 4).timestamp());
+
+        let after = utc(2024, 3, 5, 5);
+        assert_eq!(segment.window_at(after).0, utc(2024, 3, 5, 4).timestamp());
+
+        let at_boundary = utc(2024, 3, 5, 4);
+        assert_eq!(
+            segment.window_at(at_boundary),
+            (at_boundary.timestamp(), at_boundary.timestamp())
+        );
+    }
+
+    #[test]
+    fn day_start_hour_option_is_read_and_checked() {
+        let segment = segment_for(
+            &MockServer::json("{}"),
+            serde_json::json!({"day_start_hour": 4}),
+        );
+        assert_eq!(segment.day_start_hour, 4);
+
+        for (hour, valid) in [(0, true), (23, true), (24, false)] {
+            let mut config = crate::config::Config::default();
+            let mut newapi = crate::test_support::segment_config(SegmentId::NewApiCost);
+            newapi.options.insert("base_url".into(), "http://x".into());
+            newapi.options.insert("user_token".into(), "t".into());
+            newapi.options.insert("user_id".into(), "1".into());
+            newapi
+                .options
+                .insert("day_start_hour".into(), serde_json::json!(hour));
+            config.segments = vec![newapi];
+            assert_eq!(config.check().is_ok(), valid, "hour {hour}");
+        }
+    }
+
+    fn segment_with(options: serde_json::Value) -> NewApiCostSegment {
+        let options: HashMap<String, serde_json::Value> = serde_json::from_value(options).unwrap();
+        NewApiCostSegment::new().with_config_from_options(&options)
+    }
+
+    #[test]
+    fn exchange_rate_multiplies_the_base_cost() {
+        let segment = segment_with(serde_json::json!({"exchange_rate": 0.1384}));
+
+        let converted = segment.convert_cost(12.5).unwrap();
+        assert!((converted - 1.73).abs() < 1e-9);
+        assert_eq!(segment_with(serde_json::json!({})).convert_cost(12.5), None);
+    }
+
+    #[test]
+    fn non_positive_exchange_rate_is_ignored() {
+        let segment = segment_with(serde_json::json!({"exchange_rate": 0}));
+        assert_eq!(segment.exchange_rate, None);
+        assert_eq!(segment.format_cost_with_conversion(12.5), "¥12.50");
+    }
+
+    #[test]
+    fn conversion_formats_both_currencies() {
+        let segment = segment_with(serde_json::json!({
+            "exchange_rate": 0.1384,
+            "secondary_currency": "$",
+        }));
+        assert_eq!(segment.format_cost_with_conversion(12.5), "¥12.50 ($1.73)");
+
+        let euros = segment_with(serde_json::json!({
+            "currency": "$",
+            "exchange_rate": 0.5,
+            "secondary_currency": "€",
+        }));
+        assert_eq!(euros.format_cost_with_conversion(3.0), "$3.00 (€1.50)");
+    }
+
+    #[test]
+    fn conversion_is_omitted_when_unset_or_zero() {
+        assert_eq!(
+            segment_with(serde_json::json!({})).format_cost_with_conversion(12.5),
+            "¥12.50"
+        );
+
+        let segment = segment_with(serde_json::json!({"exchange_rate": 0.14}));
+        assert_eq!(
+            segment.format_cost_with_conversion(0.0),
+            segment.format_cost(0.0)
+        );
+    }
+
+    #[test]
+    fn response_mapping_reads_a_nested_fork_layout() {
+        let _cache = isolated_cache();
+        let server =
+            MockServer::json(r#"{"ok":true,"error":"","result":{"usage":{"quota":"1500000"}}}"#);
+        let segment = segment_for(
+            &server,
+            serde_json::json!({"response_mapping": {
+                "quota": "result.usage.quota",
+                "success": "ok",
+                "message": "error",
+            }}),
+        );
+
+        assert_eq!(segment.fetch_cost_between(100, 200), Some(3.0));
+    }
+
+    #[test]
+    fn response_mapping_reads_array_items_without_a_success_flag() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(r#"{"code":0,"data":{"stats":[{"quota":500000}]}}"#);
+        let segment = segment_for(
+            &server,
+            serde_json::json!({"response_mapping": {
+                "quota": "data.stats.0.quota",
+                "success": "",
+            }}),
+        );
+
+        assert_eq!(segment.fetch_cost_between(100, 200), Some(1.0));
+    }
+
+    #[test]
+    fn response_mapping_reports_the_mapped_message_on_failure() {
+        let mapping = ResponseMapping::from_value(&serde_json::json!({
+            "quota": "result.quota",
+            "success": "ok",
+            "message": "error.detail",
+        }))
+        .unwrap();
+
+        let failed = serde_json::json!({"ok": false, "error": {"detail": "bad token"}});
+        assert_eq!(mapping.parse(&failed), Err("bad token".to_string()));
+        let missing = serde_json::json!({"ok": true, "result": {}});
+        assert_eq!(
+            mapping.parse(&missing),
+            Err("no value at `result.quota`".to_string())
+        );
+        // The default layout still applies when no mapping is configured
+        let default = serde_json::json!({"success": true, "data": {"quota": 42}});
+        assert_eq!(ResponseMapping::default().parse(&default), Ok(42.0));
+    }
+
+    #[test]
+    fn cost_bands_map_to_moods() {
+        let segment = NewApiCostSegment::new();
+        let mood = |cost: f64| segment.mood_emoji(segment.budget_band(cost, 20.0));
+
+        assert_eq!(mood(5.0), "😌");
+        assert_eq!(mood(15.9), "😌");
+        assert_eq!(mood(16.0), "😐");
+        assert_eq!(mood(20.0), "😰");
+        assert_eq!(mood(35.0), "😰");
+    }
+
+    #[test]
+    fn mood_emojis_are_configurable() {
+        let segment = segment_with(serde_json::json!({
+            "mood_emojis": ["🟢", "🟡", "🔴"],
+            "warning_threshold": 0.5,
+        }));
+        let mood = |cost: f64| segment.mood_emoji(segment.budget_band(cost, 20.0));
+
+        assert_eq!(mood(9.0), "🟢");
+        assert_eq!(mood(10.0), "🟡");
+        assert_eq!(mood(21.0), "🔴");
+    }
+
+    #[test]
+    fn mood_prefixes_or_replaces_the_budget_text() {
+        let _cache = isolated_cache();
+        let data = collect_cost(
+            18.0,
+            serde_json::json!({"daily_budget": 20, "mood": "prefix"}),
+        );
+        assert_eq!(data.primary, "😐 90% of ¥20");
+
+        let data = collect_cost(
+            25.0,
+            serde_json::json!({"daily_budget": 20, "mood": "replace"}),
+        );
+        assert_eq!(data.primary, "😰");
+        assert_eq!(data.secondary, "¥25.00");
+    }
+
+    #[test]
+    fn stat_type_goes_into_the_query_string() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(&stat_body(1.0));
+
+        segment_for(&server, serde_json::json!({})).fetch_cost_between(100, 200);
+        segment_for(&server, serde_json::json!({"stat_type": 5})).fetch_cost_between(100, 200);
+
+        let requests = server.requests();
+        assert_eq!(requests[0].query("type"), Some("2"));
+        assert_eq!(requests[1].query("type"), Some("5"));
+    }
+
+    #[test]
+    fn cost_bands_map_to_indicator_colors() {
+        let segment = NewApiCostSegment::new();
+        let color = |cost: f64| segment.indicator_band_color(segment.budget_band(cost, 20.0));
+
+        assert_eq!(color(5.0), AnsiColor::Color16 { c16: 10 });
+        assert_eq!(color(17.0), AnsiColor::Color16 { c16: 11 });
+        assert_eq!(color(25.0), AnsiColor::Color16 { c16: 9 });
+
+        let custom = segment_with(serde_json::json!({
+            "indicator_color": {"c256": 34},
+            "warning_color": {"c256": 214},
+            "critical_color": {"c256": 196},
+        }));
+        let color = |cost: f64| custom.indicator_band_color(custom.budget_band(cost, 20.0));
+        assert_eq!(color(5.0), AnsiColor::Color256 { c256: 34 });
+        assert_eq!(color(17.0), AnsiColor::Color256 { c256: 214 });
+        assert_eq!(color(25.0), AnsiColor::Color256 { c256: 196 });
+    }
+
+    #[test]
+    fn indicator_only_shows_the_colored_glyph_and_keeps_the_value() {
+        let _cache = isolated_cache();
+        let data = collect_cost(
+            25.0,
+            serde_json::json!({"daily_budget": 20, "indicator_only": true, "indicator_glyph": "◆"}),
+        );
+
+        assert_eq!(data.primary, "◆");
+        assert_eq!(data.secondary, "");
+        let color: AnsiColor = serde_json::from_str(&data.metadata["dynamic_text_color"]).unwrap();
+        assert_eq!(color, AnsiColor::Color16 { c16: 9 });
+        assert_eq!(data.metadata["display"], "125% of ¥20");
+    }
+
+    #[test]
+    fn indicator_without_a_budget_stays_green() {
+        let _cache = isolated_cache();
+        let data = collect_cost(25.0, serde_json::json!({"indicator_only": true}));
+
+        assert_eq!(data.primary, "●");
+        let color: AnsiColor = serde_json::from_str(&data.metadata["dynamic_text_color"]).unwrap();
+        assert_eq!(color, AnsiColor::Color16 { c16: 10 });
+        assert_eq!(data.metadata["display"], "¥25.00");
+    }
+
+    #[test]
+    fn read_body_keeps_only_the_mapped_paths() {
+        let body = r#"{
+            "success": true,
+            "message": "",
+            "data": {"quota": 1500, "rpm": 3, "logs": [{"id": 1}, {"id": 2}]},
+            "trace": {"spans": [1, 2, 3]}
+        }"#;
+
+        let kept = ResponseMapping::default()
+            .read_body(body.as_bytes())
+            .unwrap();
+        assert_eq!(
+            kept,
+            serde_json::json!({"success": true, "message": "", "data": {"quota": 1500}})
+        );
+    }
+
+    #[test]
+    fn read_body_rejects_truncated_and_trailing_input() {
+        let mapping = ResponseMapping::default();
+
+        assert!(mapping
+            .read_body(r#"{"success":true,"data":{"quota":1"#.as_bytes())
+            .is_err());
+        assert!(mapping
+            .read_body(r#"{"success":true} {"#.as_bytes())
+            .is_err());
+    }
+
+    #[test]
+    fn fetch_streams_a_large_response() {
+        let _cache = isolated_cache();
+        let logs: Vec<String> = (0..20_000)
+            .map(|id| format!(r#"{{"id":{},"content":"request"}}"#, id))
+            .collect();
+        let body = format!(
+            r#"{{"success":true,"data":{{"logs":[{}],"quota":1000000}}}}"#,
+            logs.join(",")
+        );
+        assert!(body.len() > 500_000);
+        let server = MockServer::json(&body);
+
+        let segment = segment_for(&server, serde_json::json!({}));
+        assert_eq!(segment.fetch_cost_between(0, 1), Some(2.0));
+    }
+
+    const BALANCE_BODY: &str = r#"{"success":true,"data":{"quota":6000000}}"#;
+
+    #[test]
+    fn balance_is_fetched_with_the_same_credentials() {
+        let _cache = isolated_cache();
+        let server = MockServer::start(vec![
+            MockResponse::json(&stat_body(2.0)),
+            MockResponse::json(BALANCE_BODY),
+        ]);
+        let data = segment_for(
+            &server,
+            serde_json::json!({"balance_path": "/api/user/self"}),
+        )
+        .collect(&input_data(serde_json::json!({})))
+        .unwrap();
+
+        assert_eq!(data.primary, "¥2.00");
+        assert!(data.secondary.contains("¥12 left"), "{}", data.secondary);
+        assert_eq!(data.metadata["balance"], "12");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].path, "/api/user/self");
+        assert_eq!(requests[1].header("Authorization"), Some("Bearer sk-test"));
+        assert_eq!(requests[1].header("New-Api-User"), Some("42"));
+    }
+
+    #[test]
+    fn balance_field_picks_the_remaining_quota() {
+        let _cache = isolated_cache();
+        let server = MockServer::start(vec![
+            MockResponse::json(&stat_body(2.0)),
+            MockResponse::json(r#"{"success":true,"data":{"remaining":250000}}"#),
+        ]);
+        let data = segment_for(
+            &server,
+            serde_json::json!({"balance_path": "api/user/self", "balance_field": "data.remaining"}),
+        )
+        .collect(&input_data(serde_json::json!({})))
+        .unwrap();
+
+        assert!(data.secondary.contains("¥0.50 left"), "{}", data.secondary);
+    }
+
+    #[test]
+    fn failed_balance_still_renders_the_cost() {
+        for balance in [
+            MockResponse::new(500, "oops"),
+            MockResponse::json(r#"{"success":false,"message":"no access"}"#),
+        ] {
+            let _cache = isolated_cache();
+            let server = MockServer::start(vec![MockResponse::json(&stat_body(2.0)), balance]);
+            let data = segment_for(
+                &server,
+                serde_json::json!({"balance_path": "/api/user/self"}),
+            )
+            .collect(&input_data(serde_json::json!({})))
+            .expect("cost without balance");
+
+            assert_eq!(data.primary, "¥2.00");
+            assert!(!data.secondary.contains("left"), "{}", data.secondary);
+            assert!(!data.metadata.contains_key("balance"));
+            assert_eq!(server.requests().len(), 2);
+        }
+    }
+
+    #[test]
+    fn no_balance_request_without_balance_path() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(&stat_body(2.0));
+        segment_for(&server, serde_json::json!({}))
+            .collect(&input_data(serde_json::json!({})))
+            .unwrap();
+
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn velocity_is_the_rise_per_minute_since_the_last_drop() {
+        assert_eq!(cost_velocity(&[1.0, 2.0, 4.0], &[0, 60, 120]), Some(1.5));
+        // A drop (window reset) starts the run over
+        assert_eq!(cost_velocity(&[9.0, 1.0, 3.0], &[0, 60, 120]), Some(2.0));
+        // Entries from before timestamps were kept are skipped
+        assert_eq!(cost_velocity(&[0.0, 5.0, 6.0], &[0, 60]), Some(1.0));
+        assert_eq!(cost_velocity(&[5.0], &[0]), None);
+        assert_eq!(cost_velocity(&[5.0, 6.0], &[]), None);
+    }
+
+    /// Cache with a cost history of `(minutes ago, cost)` samples, oldest first
+    fn seed_history(samples: &[(i64, f64)]) {
+        let now = Utc::now().timestamp();
+        NewApiCostSegment::save_cache(&NewApiCostCache {
+            history: samples.iter().map(|(_, cost)| *cost).collect(),
+            history_times: samples
+                .iter()
+                .map(|(minutes_ago, _)| now - minutes_ago * 60)
+                .collect(),
+            ..NewApiCostCache::default()
+        });
+    }
+
+    fn text_color(data: &SegmentData) -> Option<AnsiColor> {
+        data.metadata
+            .get("dynamic_text_color")
+            .map(|color| serde_json::from_str(color).unwrap())
+    }
+
+    #[test]
+    fn fast_rising_cost_is_colored_by_velocity() {
+        let _cache = isolated_cache();
+        seed_history(&[(2, 1.0), (1, 2.0)]);
+        let data = collect_cost(5.0, serde_json::json!({"color_by": "velocity"}));
+
+        assert_eq!(data.metadata["velocity_band"], "Critical");
+        assert_eq!(text_color(&data), Some(AnsiColor::Color16 { c16: 9 }));
+        let velocity: f64 = data.metadata["cost_velocity"].parse().unwrap();
+        assert!((velocity - 2.0).abs() < 0.1, "{velocity}");
+    }
+
+    #[test]
+    fn slow_rising_cost_stays_uncolored_even_over_budget() {
+        let _cache = isolated_cache();
+        seed_history(&[(2, 4.9), (1, 4.95)]);
+        let data = collect_cost(
+            5.0,
+            serde_json::json!({"color_by": "velocity", "daily_budget": 4}),
+        );
+
+        assert_eq!(data.metadata["velocity_band"], "Normal");
+        assert_eq!(data.metadata["budget_band"], "Critical");
+        assert_eq!(text_color(&data), None);
+    }
+
+    #[test]
+    fn absolute_coloring_ignores_velocity() {
+        let _cache = isolated_cache();
+        seed_history(&[(2, 1.0), (1, 2.0)]);
+        let data = collect_cost(5.0, serde_json::json!({"daily_budget": 20}));
+
+        assert!(!data.metadata.contains_key("velocity_band"));
+        assert_eq!(text_color(&data), None);
+    }
+
+    /// A time on 2026-03-10, UTC
+    fn march_10(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 10, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn projection_extrapolates_over_the_rest_of_the_day() {
+        let segment = segment_with(serde_json::json!({"tz": "utc"}));
+
+        assert_eq!(segment.projected_total(3.0, march_10(6, 0)), Some(12.0));
+        assert_eq!(segment.projected_total(10.0, march_10(12, 0)), Some(20.0));
+        assert_eq!(segment.projected_total(23.0, march_10(23, 0)), Some(24.0));
+    }
+
+    #[test]
+    fn projection_is_hidden_early_in_the_day() {
+        let segment = segment_with(serde_json::json!({"tz": "utc"}));
+        assert_eq!(segment.projected_total(0.5, march_10(0, 10)), None);
+        assert_eq!(segment.projected_total(1.0, march_10(1, 59)), None);
+        assert_eq!(segment.projected_total(2.0, march_10(2, 0)), Some(24.0));
+
+        let eager = segment_with(serde_json::json!({"tz": "utc", "projection_min_hours": 0}));
+        assert_eq!(eager.projected_total(0.5, march_10(0, 30)), Some(24.0));
+    }
+
+    #[test]
+    fn projection_follows_the_custom_day_window() {
+        let segment = segment_with(serde_json::json!({"tz": "utc", "day_start_hour": 6}));
+
+        // Six hours into a day starting at 06:00
+        assert_eq!(segment.projected_total(5.0, march_10(12, 0)), Some(20.0));
+        // 03:00 belongs to the day that started yesterday at 06:00
+        assert_eq!(segment.projected_total(21.0, march_10(3, 0)), Some(24.0));
+        assert_eq!(segment.projected_total(1.0, march_10(7, 0)), None);
+
+        let fixed = segment_with(serde_json::json!({"tz": "+08:00"}));
+        // 04:00 UTC is noon at +08:00
+        assert_eq!(fixed.projected_total(10.0, march_10(4, 0)), Some(20.0));
+    }
+
+    #[test]
+    fn projection_only_applies_to_today() {
+        let segment = segment_with(serde_json::json!({"tz": "utc", "range": "week"}));
+        assert_eq!(segment.projected_total(10.0, march_10(12, 0)), None);
+    }
+
+    #[test]
+    fn show_projection_adds_the_pace_to_secondary() {
+        let _cache = isolated_cache();
+        let options = serde_json::json!({"show_projection": true, "projection_min_hours": 0});
+        let data = collect_cost(5.0, options);
+
+        let projected: f64 = data.metadata["projected_cost"].parse().unwrap();
+        assert!(projected >= 5.0, "{}", projected);
+        assert!(data.secondary.contains("~¥"), "{}", data.secondary);
+
+        let data = collect_cost(5.0, serde_json::json!({"projection_min_hours": 0}));
+        assert!(!data.secondary.contains('~'), "{}", data.secondary);
+    }
+
+    #[test]
+    fn rotation_advances_and_wraps() {
+        assert_eq!(NewApiCostSegment::rotation(0, 3), (0, 1));
+        assert_eq!(NewApiCostSegment::rotation(1, 3), (1, 2));
+        assert_eq!(NewApiCostSegment::rotation(2, 3), (2, 0));
+        // The list got shorter since the index was stored
+        assert_eq!(NewApiCostSegment::rotation(5, 3), (0, 1));
+        assert_eq!(NewApiCostSegment::rotation(0, 1), (0, 0));
+    }
+
+    fn bearer_tokens(server: &MockServer) -> Vec<String> {
+        server
+            .requests()
+            .iter()
+            .filter_map(|request| request.header("Authorization"))
+            .map(|header| header.trim_start_matches("Bearer ").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn round_robin_uses_the_next_token_on_each_render() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(&stat_body(1.0));
+        let segment = segment_for(
+            &server,
+            serde_json::json!({"user_token": ["a", "b", "c"], "token_selection": "round_robin"}),
+        );
+
+        for _ in 0..4 {
+            segment.collect(&input_data(serde_json::json!({}))).unwrap();
+        }
+        assert_eq!(bearer_tokens(&server), ["a", "b", "c", "a"]);
+    }
+
+    #[test]
+    fn round_robin_keeps_each_tokens_cost_state_apart() {
+        let _cache = isolated_cache();
+        let server = MockServer::start(vec![
+            MockResponse::json(&stat_body(1.0)),
+            MockResponse::json(&stat_body(3.0)),
+            MockResponse::json(&stat_body(1.5)),
+            MockResponse::json(&stat_body(3.0)),
+        ]);
+        let segment = segment_for(
+            &server,
+            serde_json::json!({"user_token": ["a", "b"], "token_selection": "round_robin"}),
+        );
+        let renders: Vec<SegmentData> = (0..4)
+            .map(|_| segment.collect(&input_data(serde_json::json!({}))).unwrap())
+            .collect();
+        assert_eq!(bearer_tokens(&server), ["a", "b", "a", "b"]);
+
+        // Each token's first render has nothing of its own to compare against
+        assert!(!renders[0].metadata.contains_key("cost_delta"));
+        assert!(!renders[1].metadata.contains_key("cost_delta"));
+        // Token a grew by 0.5; token b didn't change
+        let delta: f64 = renders[2].metadata["cost_delta"].parse().unwrap();
+        assert!((delta - 0.5).abs() < 1e-9, "{}", delta);
+        assert!(!renders[3].metadata.contains_key("cost_delta"));
+        // Token a's high-water mark ignores token b's larger total
+        assert_eq!(renders[2].metadata["max_cost"], "1.5");
+        assert_eq!(renders[3].metadata["max_cost"], "3");
+    }
+
+    #[test]
+    fn token_state_key_is_pinned() {
+        // Stored in the cache file, so it must not change between builds
+        assert_eq!(NewApiCostSegment::token_state_key("a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn first_selection_always_uses_the_first_token() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(&stat_body(1.0));
+        let segment = segment_for(&server, serde_json::json!({"user_token": ["a", "b"]}));
+
+        for _ in 0..2 {
+            segment.collect(&input_data(serde_json::json!({}))).unwrap();
+        }
+        assert_eq!(bearer_tokens(&server), ["a", "a"]);
+    }
+
+    #[test]
+    fn aggregate_sums_every_token() {
+        let _cache = isolated_cache();
+        let server = MockServer::start(vec![
+            MockResponse::json(&stat_body(1.0)),
+            MockResponse::json(&stat_body(2.5)),
+            MockResponse::json(BALANCE_BODY),
+        ]);
+        let data = segment_for(
+            &server,
+            serde_json::json!({
+                "user_token": ["a", "b"],
+                "token_selection": "aggregate",
+                "balance_path": "/api/user/self",
+            }),
+        )
+        .collect(&input_data(serde_json::json!({})))
+        .unwrap();
+
+        assert_eq!(data.primary, "¥3.50");
+        assert_eq!(data.metadata["balance"], "24");
+        assert_eq!(bearer_tokens(&server), ["a", "b", "a", "b"]);
+    }
+
+    #[test]
+    fn aggregate_fails_when_any_token_fails() {
+        let _cache = isolated_cache();
+        let server = MockServer::start(vec![
+            MockResponse::json(&stat_body(1.0)),
+            MockResponse::new(500, "oops"),
+        ]);
+        let data = segment_for(
+            &server,
+            serde_json::json!({"user_token": ["a", "b"], "token_selection": "aggregate"}),
+        )
+        .collect(&input_data(serde_json::json!({})))
+        .unwrap();
+
+        assert!(data.is_error(), "{:?}", data.metadata);
+    }
+
+    #[test]
+    fn checkpoint_records_the_cost_and_later_renders_show_the_spend_since() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(&stat_body(1.8));
+        assert_eq!(
+            segment_for(&server, serde_json::json!({})).set_checkpoint(),
+            Some(1.8)
+        );
+
+        let data = collect_cost(5.0, serde_json::json!({"show_since_checkpoint": true}));
+        assert_eq!(data.metadata["checkpoint"], "1.8");
+        let spent: f64 = data.metadata["since_checkpoint"].parse().unwrap();
+        assert!((spent - 3.2).abs() < 1e-9, "{}", spent);
+        assert!(
+            data.secondary.contains("+¥3.20 since mark"),
+            "{}",
+            data.secondary
+        );
+
+        // Without the display option the delta is only in the metadata
+        let data = collect_cost(5.0, serde_json::json!({}));
+        assert!(data.metadata.contains_key("since_checkpoint"));
+        assert!(!data.secondary.contains("since mark"), "{}", data.secondary);
+    }
+
+    #[test]
+    fn aggregate_checkpoint_marks_the_summed_cost() {
+        let _cache = isolated_cache();
+        let options = serde_json::json!({"user_token": ["a", "b"], "token_selection": "aggregate"});
+        let server = MockServer::start(vec![
+            MockResponse::json(&stat_body(1.0)),
+            MockResponse::json(&stat_body(2.5)),
+        ]);
+        assert_eq!(
+            segment_for(&server, options.clone()).set_checkpoint(),
+            Some(3.5)
+        );
+        assert_eq!(bearer_tokens(&server), ["a", "b"]);
+
+        let server = MockServer::start(vec![
+            MockResponse::json(&stat_body(1.0)),
+            MockResponse::json(&stat_body(2.5)),
+        ]);
+        let data = segment_for(&server, options)
+            .collect(&input_data(serde_json::json!({})))
+            .unwrap();
+        assert_eq!(data.metadata["since_checkpoint"], "0");
+    }
+
+    #[test]
+    fn round_robin_checkpoint_marks_each_token() {
+        let _cache = isolated_cache();
+        let options =
+            serde_json::json!({"user_token": ["a", "b"], "token_selection": "round_robin"});
+        let server = MockServer::start(vec![
+            MockResponse::json(&stat_body(1.0)),
+            MockResponse::json(&stat_body(3.0)),
+        ]);
+        assert_eq!(
+            segment_for(&server, options.clone()).set_checkpoint(),
+            Some(4.0)
+        );
+
+        let server = MockServer::start(vec![
+            MockResponse::json(&stat_body(1.0)),
+            MockResponse::json(&stat_body(3.0)),
+        ]);
+        let segment = segment_for(&server, options);
+        for _ in 0..2 {
+            let data = segment.collect(&input_data(serde_json::json!({}))).unwrap();
+            assert_eq!(data.metadata["since_checkpoint"], "0");
+        }
+        assert_eq!(bearer_tokens(&server), ["a", "b"]);
+
+        assert!(NewApiCostSegment::clear_checkpoint());
+        assert!(!NewApiCostSegment::clear_checkpoint());
+    }
+
+    #[test]
+    fn spend_since_checkpoint_never_goes_negative() {
+        assert_eq!(NewApiCostSegment::since_checkpoint(5.0, 1.5), 3.5);
+        assert_eq!(NewApiCostSegment::since_checkpoint(1.0, 2.0), 0.0);
+    }
+
+    #[test]
+    fn failed_fetch_records_no_checkpoint() {
+        let _cache = isolated_cache();
+        let server = MockServer::start(vec![MockResponse::new(500, "oops")]);
+        assert_eq!(
+            segment_for(&server, serde_json::json!({})).set_checkpoint(),
+            None
+        );
+        assert!(!NewApiCostSegment::clear_checkpoint());
+    }
+
+    #[test]
+    fn checkpoint_from_an_earlier_window_is_ignored() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(&stat_body(1.0));
+        segment_for(&server, serde_json::json!({}))
+            .set_checkpoint()
+            .unwrap();
+
+        let mut cache = NewApiCostSegment::load_cache().unwrap();
+        cache.checkpoint_window -= 86400;
+        NewApiCostSegment::save_cache(&cache);
+
+        let data = collect_cost(5.0, serde_json::json!({"show_since_checkpoint": true}));
+        assert!(!data.metadata.contains_key("checkpoint"));
+        assert!(!data.secondary.contains("since mark"), "{}", data.secondary);
+    }
+
+    #[test]
+    fn cleared_checkpoint_stops_the_delta() {
+        let _cache = isolated_cache();
+        let server = MockServer::json(&stat_body(1.0));
+        segment_for(&server, serde_json::json!({}))
+            .set_checkpoint()
+            .unwrap();
+
+        assert!(NewApiCostSegment::clear_checkpoint());
+        assert!(!NewApiCostSegment::clear_checkpoint());
+        let data = collect_cost(5.0, serde_json::json!({"show_since_checkpoint": true}));
+        assert!(!data.metadata.contains_key("checkpoint"));
     }
 }
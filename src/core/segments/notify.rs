@@ -0,0 +1,113 @@
+use super::count_source::CountSource;
+use super::{Segment, SegmentData};
+use crate::config::{InputData, SegmentId};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Shows an unread notification or mail count, hidden at zero
+#[derive(Default)]
+pub struct NotifySegment {
+    source: Option<CountSource>,
+    /// Limit on the count command (default `DEFAULT_COMMAND_TIMEOUT`)
+    timeout: Option<Duration>,
+}
+
+impl NotifySegment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_source(mut self, source: Option<CountSource>) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl Segment for NotifySegment {
+    fn collect(&self, input: &InputData) -> Option<SegmentData> {
+        let source = self.source.as_ref()?;
+        let timeout = self.timeout.unwrap_or(super::DEFAULT_COMMAND_TIMEOUT);
+        let count = source.read_count(Path::new(&input.workspace.current_dir), timeout)?;
+        if count == 0 {
+            return None;
+        }
+
+        let mut metadata = HashMap::new();
+        metadata.insert("count".to_string(), count.to_string());
+
+        Some(SegmentData {
+            primary: count.to_string(),
+            secondary: String::new(),
+            metadata,
+        })
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::Notify
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(30))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{input_data, TempDir};
+
+    fn collect_in(dir: &TempDir, source: CountSource) -> Option<SegmentData> {
+        let input = input_data(serde_json::json!({
+            "workspace": {"current_dir": dir.path()},
+        }));
+        NotifySegment::new()
+            .with_source(Some(source))
+            .collect(&input)
+    }
+
+    #[test]
+    fn command_source_reads_leading_count() {
+        let dir = TempDir::new("notify");
+        let data = collect_in(&dir, CountSource::Command("echo '5 unread'".into())).unwrap();
+        assert_eq!(data.primary, "5");
+        assert_eq!(data.metadata["count"], "5");
+    }
+
+    #[test]
+    fn command_source_runs_in_the_workspace() {
+        let dir = TempDir::new("notify");
+        dir.write("count.txt", "7\n");
+        let data = collect_in(&dir, CountSource::Command("cat count.txt".into())).unwrap();
+        assert_eq!(data.primary, "7");
+    }
+
+    #[test]
+    fn failing_command_hides_the_segment() {
+        let dir = TempDir::new("notify");
+        assert!(collect_in(&dir, CountSource::Command("echo 3; exit 1".into())).is_none());
+    }
+
+    #[test]
+    fn file_source_resolves_relative_to_workspace() {
+        let dir = TempDir::new("notify");
+        dir.write("mail/unread", "12 messages\n");
+        let data = collect_in(&dir, CountSource::File("mail/unread".into())).unwrap();
+        assert_eq!(data.primary, "12");
+    }
+
+    #[test]
+    fn zero_or_unreadable_count_is_hidden() {
+        let dir = TempDir::new("notify");
+        dir.write("unread", "0\n");
+        assert!(collect_in(&dir, CountSource::File("unread".into())).is_none());
+        assert!(collect_in(&dir, CountSource::File("missing".into())).is_none());
+        dir.write("garbage", "lots\n");
+        assert!(collect_in(&dir, CountSource::File("garbage".into())).is_none());
+    }
+}
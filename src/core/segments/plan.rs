@@ -0,0 +1,176 @@
+use super::{Segment, SegmentData};
+use crate::config::{InputData, SegmentId};
+use std::collections::HashMap;
+
+/// Shows the active Claude plan tier (`Pro`, `Max`, ...)
+/// Sources in order: a stdin JSON field, an environment variable, a static `value`
+pub struct PlanSegment {
+    /// Dotted path below the top level of the stdin JSON (default `plan`)
+    input_field: String,
+    /// Environment variable name (default `CCLINE_PLAN`)
+    env: String,
+    value: Option<String>,
+}
+
+impl Default for PlanSegment {
+    fn default() -> Self {
+        Self {
+            input_field: "plan".to_string(),
+            env: "CCLINE_PLAN".to_string(),
+            value: None,
+        }
+    }
+}
+
+impl PlanSegment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load settings from segment options HashMap
+    pub fn with_config_from_options(
+        mut self,
+        options: &HashMap<String, serde_json::Value>,
+    ) -> Self {
+        let option = |key: &str| {
+            options
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+        };
+
+        if let Some(field) = option("input_field") {
+            self.input_field = field;
+        }
+        if let Some(env) = option("env") {
+            self.env = env;
+        }
+        self.value = option("value");
+        self
+    }
+
+    /// Plan name and the source it came from (`input`, `env` or `config`)
+    pub fn resolve(&self, input: &InputData) -> Option<(String, &'static str)> {
+        let from_input = || {
+            let mut parts = self.input_field.split('.');
+            let mut value = input.extra.get(parts.next()?)?;
+            for part in parts {
+                value = value.get(part)?;
+            }
+            value.as_str().map(str::to_string)
+        };
+        let from_env = || std::env::var(&self.env).ok();
+
+        let clean = |plan: Option<String>| {
+            plan.map(|plan| plan.trim().to_string())
+                .filter(|plan| !plan.is_empty())
+        };
+
+        clean(from_input())
+            .map(|plan| (plan, "input"))
+            .or_else(|| clean(from_env()).map(|plan| (plan, "env")))
+            .or_else(|| clean(self.value.clone()).map(|plan| (plan, "config")))
+    }
+
+    /// `max` → `Max`; names that already have capitals are kept as they are
+    fn display_name(plan: &str) -> String {
+        if plan.chars().any(char::is_uppercase) {
+            return plan.to_string();
+        }
+        let mut chars = plan.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    }
+}
+
+impl Segment for PlanSegment {
+    fn collect(&self, input: &InputData) -> Option<SegmentData> {
+        let (plan, source) = self.resolve(input)?;
+
+        let mut metadata = HashMap::new();
+        metadata.insert("plan".to_string(), plan.clone());
+        metadata.insert("source".to_string(), source.to_string());
+
+        Some(SegmentData {
+            primary: Self::display_name(&plan),
+            secondary: String::new(),
+            metadata,
+        })
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::Plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{input_data, EnvGuard};
+
+    fn plan_with(options: serde_json::Value) -> PlanSegment {
+        let options: HashMap<String, serde_json::Value> = serde_json::from_value(options).unwrap();
+        PlanSegment::new().with_config_from_options(&options)
+    }
+
+    #[test]
+    fn input_field_wins_over_env_and_config() {
+        let mut env = EnvGuard::new();
+        env.set("CCLINE_PLAN", "team");
+        let segment = plan_with(serde_json::json!({"value": "enterprise"}));
+
+        let data = segment
+            .collect(&input_data(serde_json::json!({"plan": "max"})))
+            .unwrap();
+        assert_eq!(data.primary, "Max");
+        assert_eq!(data.metadata["plan"], "max");
+        assert_eq!(data.metadata["source"], "input");
+    }
+
+    #[test]
+    fn nested_input_field() {
+        let _env = EnvGuard::new();
+        let segment = plan_with(serde_json::json!({"input_field": "account.tier"}));
+        let input = input_data(serde_json::json!({"account": {"tier": "Pro"}}));
+
+        let (plan, source) = segment.resolve(&input).unwrap();
+        assert_eq!((plan.as_str(), source), ("Pro", "input"));
+    }
+
+    #[test]
+    fn env_var_wins_over_config() {
+        let mut env = EnvGuard::new();
+        env.set("TEAM_PLAN", "pro");
+        let segment = plan_with(serde_json::json!({"env": "TEAM_PLAN", "value": "enterprise"}));
+
+        // A blank input field falls through to the next source
+        let input = input_data(serde_json::json!({"plan": "  "}));
+        let data = segment.collect(&input).unwrap();
+        assert_eq!(data.primary, "Pro");
+        assert_eq!(data.metadata["source"], "env");
+    }
+
+    #[test]
+    fn static_value_is_the_last_resort() {
+        let mut env = EnvGuard::new();
+        env.remove("CCLINE_PLAN");
+        let segment = plan_with(serde_json::json!({"value": "Max 20x"}));
+
+        let data = segment.collect(&input_data(serde_json::json!({}))).unwrap();
+        assert_eq!(data.primary, "Max 20x");
+        assert_eq!(data.metadata["source"], "config");
+    }
+
+    #[test]
+    fn no_source_hides_the_segment() {
+        let mut env = EnvGuard::new();
+        env.set("CCLINE_PLAN", "");
+        let input = input_data(serde_json::json!({"plan": 3}));
+
+        assert!(PlanSegment::new().collect(&input).is_none());
+    }
+}
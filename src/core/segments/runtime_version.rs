@@ -0,0 +1,279 @@
+use super::{Segment, SegmentData};
+use crate::config::{InputData, SegmentId};
+use crate::utils::process::output_with_timeout;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Detected version per runtime and directory
+type ProbeCache = HashMap<(Runtime, PathBuf), Option<String>>;
+
+/// Probe results cached for the lifetime of the process
+static PROBE_CACHE: OnceLock<Mutex<ProbeCache>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Runtime {
+    Python,
+    Node,
+    Rust,
+}
+
+impl Runtime {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "python" | "py" => Some(Runtime::Python),
+            "node" | "nodejs" => Some(Runtime::Node),
+            "rust" => Some(Runtime::Rust),
+            _ => None,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Runtime::Python => "py",
+            Runtime::Node => "node",
+            Runtime::Rust => "rust",
+        }
+    }
+
+    /// Files whose presence makes this runtime relevant to a directory
+    fn marker_files(&self) -> &'static [&'static str] {
+        match self {
+            Runtime::Python => &[
+                ".python-version",
+                "pyproject.toml",
+                "requirements.txt",
+                "setup.py",
+                "Pipfile",
+            ],
+            Runtime::Node => &[".nvmrc", ".node-version", "package.json"],
+            Runtime::Rust => &["rust-toolchain.toml", "rust-toolchain", "Cargo.toml"],
+        }
+    }
+
+    /// Command used when no version file pins the runtime
+    fn version_command(&self) -> (&'static str, &'static str) {
+        match self {
+            Runtime::Python => ("python3", "--version"),
+            Runtime::Node => ("node", "--version"),
+            Runtime::Rust => ("rustc", "--version"),
+        }
+    }
+}
+
+/// Shows the active Python/Node/Rust version for the working directory
+#[derive(Default)]
+pub struct RuntimeVersionSegment {
+    runtime: Option<Runtime>,
+    /// Limit on the `--version` probe (default `DEFAULT_COMMAND_TIMEOUT`)
+    timeout: Option<Duration>,
+}
+
+impl RuntimeVersionSegment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restrict detection to one runtime; without it the first relevant runtime is used
+    pub fn with_runtime(mut self, runtime: Option<Runtime>) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    fn is_relevant(runtime: Runtime, dir: &Path) -> bool {
+        runtime
+            .marker_files()
+            .iter()
+            .any(|file| dir.join(file).exists())
+    }
+
+    /// Read a version pinned by a file in the directory
+    pub fn version_from_files(runtime: Runtime, dir: &Path) -> Option<String> {
+        match runtime {
+            Runtime::Python => Self::read_first_line(&dir.join(".python-version")),
+            Runtime::Node => Self::read_first_line(&dir.join(".nvmrc"))
+                .or_else(|| Self::read_first_line(&dir.join(".node-version")))
+                .map(|version| version.trim_start_matches('v').to_string()),
+            Runtime::Rust => {
+                let toml_path = dir.join("rust-toolchain.toml");
+                if let Ok(content) = std::fs::read_to_string(&toml_path) {
+                    let value: toml::Value = toml::from_str(&content).ok()?;
+                    return value
+                        .get("toolchain")
+                        .and_then(|toolchain| toolchain.get("channel"))
+                        .and_then(|channel| channel.as_str())
+                        .map(|channel| channel.to_string());
+                }
+                Self::read_first_line(&dir.join("rust-toolchain"))
+            }
+        }
+    }
+
+    fn read_first_line(path: &Path) -> Option<String> {
+        let content = std::fs::read_to_string(path).ok()?;
+        let line = content.lines().next()?.trim();
+        if line.is_empty() {
+            None
+        } else {
+            Some(line.to_string())
+        }
+    }
+
+    /// Ask the runtime binary for its version (e.g. "Python 3.11.4" -> "3.11.4")
+    fn version_from_binary(runtime: Runtime, dir: &Path, timeout: Duration) -> Option<String> {
+        let (program, arg) = runtime.version_command();
+        let output =
+            output_with_timeout(Command::new(program).arg(arg).current_dir(dir), timeout).ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        // Older Python versions print the version to stderr
+        let text = if output.stdout.is_empty() {
+            String::from_utf8_lossy(&output.stderr).to_string()
+        } else {
+            String::from_utf8_lossy(&output.stdout).to_string()
+        };
+
+        text.split_whitespace()
+            .map(|token| token.trim_start_matches('v'))
+            .find(|token| token.starts_with(|c: char| c.is_ascii_digit()))
+            .map(|token| token.to_string())
+    }
+
+    /// Detect the version for a relevant runtime, caching the probe per invocation
+    fn detect(runtime: Runtime, dir: &Path, timeout: Duration) -> Option<String> {
+        if !Self::is_relevant(runtime, dir) {
+            return None;
+        }
+
+        let key = (runtime, dir.to_path_buf());
+        let cache = PROBE_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+        if let Ok(cache) = cache.lock() {
+            if let Some(cached) = cache.get(&key) {
+                return cached.clone();
+            }
+        }
+
+        let version = Self::version_from_files(runtime, dir)
+            .or_else(|| Self::version_from_binary(runtime, dir, timeout));
+
+        if let Ok(mut cache) = cache.lock() {
+            cache.insert(key, version.clone());
+        }
+
+        version
+    }
+}
+
+impl Segment for RuntimeVersionSegment {
+    fn collect(&self, input: &InputData) -> Option<SegmentData> {
+        let dir = Path::new(&input.workspace.current_dir);
+        let timeout = self.timeout.unwrap_or(super::DEFAULT_COMMAND_TIMEOUT);
+
+        let candidates = match self.runtime {
+            Some(runtime) => vec![runtime],
+            None => vec![Runtime::Python, Runtime::Node, Runtime::Rust],
+        };
+
+        let (runtime, version) = candidates.into_iter().find_map(|runtime| {
+            Self::detect(runtime, dir, timeout).map(|version| (runtime, version))
+        })?;
+
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            "runtime".to_string(),
+            format!("{:?}", runtime).to_lowercase(),
+        );
+        metadata.insert("version".to_string(), version.clone());
+
+        Some(SegmentData {
+            primary: format!("{} {}", runtime.label(), version),
+            secondary: String::new(),
+            metadata,
+        })
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::RuntimeVersion
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(300))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{input_data, TempDir};
+
+    fn collect_in(dir: &TempDir, runtime: Option<Runtime>) -> Option<SegmentData> {
+        RuntimeVersionSegment::new()
+            .with_runtime(runtime)
+            .collect(&input_data(serde_json::json!({
+                "workspace": {"current_dir": dir.path()}
+            })))
+    }
+
+    #[test]
+    fn python_version_file() {
+        let dir = TempDir::new("runtime-py");
+        dir.write(".python-version", "3.11.4\n");
+
+        let data = collect_in(&dir, None).unwrap();
+        assert_eq!(data.primary, "py 3.11.4");
+        assert_eq!(data.metadata["runtime"], "python");
+    }
+
+    #[test]
+    fn node_version_files() {
+        let dir = TempDir::new("runtime-node");
+        dir.write(".nvmrc", "v20.11.0\n");
+        assert_eq!(
+            RuntimeVersionSegment::version_from_files(Runtime::Node, dir.path()).as_deref(),
+            Some("20.11.0")
+        );
+
+        let dir = TempDir::new("runtime-node");
+        dir.write(".node-version", "18.19.1");
+        assert_eq!(
+            collect_in(&dir, Some(Runtime::Node)).unwrap().primary,
+            "node 18.19.1"
+        );
+    }
+
+    #[test]
+    fn rust_toolchain_files() {
+        let dir = TempDir::new("runtime-rust");
+        dir.write("rust-toolchain.toml", "[toolchain]\nchannel = \"1.79.0\"\n");
+        assert_eq!(
+            collect_in(&dir, Some(Runtime::Rust)).unwrap().primary,
+            "rust 1.79.0"
+        );
+
+        let dir = TempDir::new("runtime-rust");
+        dir.write("rust-toolchain", "nightly-2024-05-01\n");
+        assert_eq!(
+            RuntimeVersionSegment::version_from_files(Runtime::Rust, dir.path()).as_deref(),
+            Some("nightly-2024-05-01")
+        );
+    }
+
+    #[test]
+    fn irrelevant_directory_shows_nothing() {
+        let dir = TempDir::new("runtime-none");
+        dir.write("README.md", "# nothing to see");
+
+        assert!(collect_in(&dir, None).is_none());
+        assert!(collect_in(&dir, Some(Runtime::Rust)).is_none());
+    }
+}
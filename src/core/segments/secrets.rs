@@ -0,0 +1,129 @@
+use super::{Segment, SegmentData};
+use crate::config::{InputData, SegmentId};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Files treated as sensitive when no `files` option is given
+const DEFAULT_FILES: &[&str] = &[".env", ".env.local", ".env.production", "credentials.json"];
+
+/// Warns when the current directory holds a secrets file such as `.env`
+pub struct SecretsSegment {
+    files: Vec<String>,
+}
+
+impl Default for SecretsSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SecretsSegment {
+    pub fn new() -> Self {
+        Self {
+            files: DEFAULT_FILES.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    pub fn with_files(mut self, files: Vec<String>) -> Self {
+        self.files = files;
+        self
+    }
+
+    /// Load the watched file list from segment options HashMap
+    pub fn with_config_from_options(self, options: &HashMap<String, serde_json::Value>) -> Self {
+        match options.get("files").and_then(|v| v.as_array()) {
+            Some(files) => self.with_files(
+                files
+                    .iter()
+                    .filter_map(|f| f.as_str().map(|s| s.to_string()))
+                    .collect(),
+            ),
+            None => self,
+        }
+    }
+
+    /// Watched files present in `dir`, in configured order
+    fn present_files(&self, dir: &Path) -> Vec<&str> {
+        self.files
+            .iter()
+            .filter(|name| dir.join(name).is_file())
+            .map(|name| name.as_str())
+            .collect()
+    }
+}
+
+impl Segment for SecretsSegment {
+    fn collect(&self, input: &InputData) -> Option<SegmentData> {
+        let found = self.present_files(Path::new(&input.workspace.current_dir));
+        let (first, rest) = found.split_first()?;
+
+        let mut metadata = HashMap::new();
+        metadata.insert("files".to_string(), found.join(","));
+
+        Some(SegmentData {
+            primary: first.to_string(),
+            secondary: if rest.is_empty() {
+                String::new()
+            } else {
+                format!("+{}", rest.len())
+            },
+            metadata,
+        })
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::Secrets
+    }
+
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(30))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{input_data, TempDir};
+
+    fn collect_in(segment: &SecretsSegment, dir: &TempDir) -> Option<SegmentData> {
+        segment.collect(&input_data(
+            serde_json::json!({"workspace": {"current_dir": dir.path()}}),
+        ))
+    }
+
+    #[test]
+    fn directory_without_secrets_renders_nothing() {
+        let dir = TempDir::new("secrets");
+        dir.write("README.md", "");
+        // A directory named like a watched file doesn't count
+        std::fs::create_dir(dir.join(".env")).unwrap();
+
+        assert!(collect_in(&SecretsSegment::new(), &dir).is_none());
+    }
+
+    #[test]
+    fn matching_files_are_reported_in_configured_order() {
+        let dir = TempDir::new("secrets");
+        dir.write("credentials.json", "{}");
+        dir.write(".env", "TOKEN=x");
+
+        let data = collect_in(&SecretsSegment::new(), &dir).unwrap();
+        assert_eq!(data.primary, ".env");
+        assert_eq!(data.secondary, "+1");
+        assert_eq!(data.metadata["files"], ".env,credentials.json");
+    }
+
+    #[test]
+    fn files_option_replaces_the_defaults() {
+        let dir = TempDir::new("secrets");
+        dir.write(".env", "TOKEN=x");
+        dir.write("id_rsa", "");
+        let options = HashMap::from([("files".to_string(), serde_json::json!(["id_rsa"]))]);
+        let segment = SecretsSegment::new().with_config_from_options(&options);
+
+        let data = collect_in(&segment, &dir).unwrap();
+        assert_eq!(data.primary, "id_rsa");
+        assert_eq!(data.secondary, "");
+    }
+}
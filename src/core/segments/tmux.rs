@@ -0,0 +1,187 @@
+use super::{Segment, SegmentData};
+use crate::config::{InputData, SegmentId};
+use crate::utils::process::output_with_timeout;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+/// Shows the tmux `session:window` the statusline runs in
+pub struct TmuxSegment {
+    show_window: bool,
+    timeout: Duration,
+    /// tmux executable to query
+    program: PathBuf,
+}
+
+impl Default for TmuxSegment {
+    fn default() -> Self {
+        Self {
+            show_window: true,
+            timeout: super::DEFAULT_COMMAND_TIMEOUT,
+            program: PathBuf::from("tmux"),
+        }
+    }
+}
+
+impl TmuxSegment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run this tmux executable instead of the one on `PATH`
+    pub fn with_program(mut self, program: impl Into<PathBuf>) -> Self {
+        self.program = program.into();
+        self
+    }
+
+    /// Load settings from segment options HashMap
+    pub fn with_config_from_options(
+        mut self,
+        options: &HashMap<String, serde_json::Value>,
+    ) -> Self {
+        if let Some(value) = options.get("show_window").and_then(|v| v.as_bool()) {
+            self.show_window = value;
+        }
+        self.timeout = super::io_timeout(options, self.timeout);
+        self
+    }
+
+    /// Value of a non-empty environment variable
+    fn env(name: &str) -> Option<String> {
+        std::env::var(name).ok().filter(|value| !value.is_empty())
+    }
+
+    /// Session and window names of the current pane
+    /// tmux only exports the socket and pane id, so the names are looked up
+    /// with `tmux display-message`; outside tmux nothing is run
+    fn session_and_window(&self, pane: Option<&str>) -> Option<(String, String)> {
+        let mut command = Command::new(&self.program);
+        command.arg("display-message").arg("-p");
+        if let Some(pane) = pane {
+            command.arg("-t").arg(pane);
+        }
+        let output = output_with_timeout(command.arg("#S\t#W"), self.timeout).ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let text = String::from_utf8(output.stdout).ok()?;
+        let (session, window) = text.trim_end_matches(['\r', '\n']).split_once('\t')?;
+        (!session.is_empty()).then(|| (session.to_string(), window.to_string()))
+    }
+}
+
+impl Segment for TmuxSegment {
+    fn collect(&self, _input: &InputData) -> Option<SegmentData> {
+        // `$TMUX` is `socket,pid,session`; its absence means we're not in tmux
+        let socket = Self::env("TMUX")?;
+        let pane = Self::env("TMUX_PANE");
+        let (session, window) = self.session_and_window(pane.as_deref())?;
+
+        let primary = if self.show_window && !window.is_empty() {
+            format!("{}:{}", session, window)
+        } else {
+            session.clone()
+        };
+
+        let mut metadata = HashMap::new();
+        metadata.insert("session".to_string(), session);
+        metadata.insert("window".to_string(), window);
+        if let Some(socket) = socket.split(',').next() {
+            metadata.insert("socket".to_string(), socket.to_string());
+        }
+        if let Some(pane) = pane {
+            metadata.insert("pane".to_string(), pane);
+        }
+
+        Some(SegmentData {
+            primary,
+            secondary: String::new(),
+            metadata,
+        })
+    }
+
+    fn id(&self) -> SegmentId {
+        SegmentId::Tmux
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{input_data, EnvGuard, TempDir};
+
+    /// A fake `tmux` that logs its arguments and prints a session;
+    /// returns the script and its argument log
+    #[cfg(unix)]
+    fn fake_tmux(dir: &TempDir) -> (PathBuf, PathBuf) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let log = dir.join("args.log");
+        let script = dir.write(
+            "tmux",
+            &format!(
+                "#!/bin/sh\necho \"$@\" >> {:?}\nprintf 'work\\teditor\\n'\n",
+                log
+            ),
+        );
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        (script, log)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn inside_tmux_shows_session_and_window() {
+        let bin = TempDir::new("tmux-bin");
+        let (tmux, log) = fake_tmux(&bin);
+        let mut env = EnvGuard::new();
+        env.set("TMUX", "/tmp/tmux-1000/default,4242,0")
+            .set("TMUX_PANE", "%3");
+
+        let data = TmuxSegment::new()
+            .with_program(tmux)
+            .collect(&input_data(serde_json::json!({})))
+            .unwrap();
+        assert_eq!(data.primary, "work:editor");
+        assert_eq!(data.metadata["session"], "work");
+        assert_eq!(data.metadata["socket"], "/tmp/tmux-1000/default");
+        assert_eq!(data.metadata["pane"], "%3");
+
+        let args = std::fs::read_to_string(log).unwrap();
+        assert!(args.contains("-t %3"), "{}", args);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn show_window_false_shows_only_the_session() {
+        let bin = TempDir::new("tmux-bin");
+        let (tmux, _) = fake_tmux(&bin);
+        let mut env = EnvGuard::new();
+        env.set("TMUX", "/tmp/tmux-1000/default,4242,0")
+            .remove("TMUX_PANE");
+
+        let options = HashMap::from([("show_window".to_string(), serde_json::json!(false))]);
+        let data = TmuxSegment::new()
+            .with_program(tmux)
+            .with_config_from_options(&options)
+            .collect(&input_data(serde_json::json!({})))
+            .unwrap();
+        assert_eq!(data.primary, "work");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn outside_tmux_renders_nothing_without_running_tmux() {
+        let bin = TempDir::new("tmux-bin");
+        let (tmux, log) = fake_tmux(&bin);
+        let mut env = EnvGuard::new();
+        env.remove("TMUX").remove("TMUX_PANE");
+
+        assert!(TmuxSegment::new()
+            .with_program(tmux)
+            .collect(&input_data(serde_json::json!({})))
+            .is_none());
+        assert!(!log.exists());
+    }
+}
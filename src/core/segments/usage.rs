@@ -1,7 +1,7 @@
 use super::{Segment, SegmentData};
 use crate::config::{InputData, SegmentId};
-use crate::utils::credentials;
-use chrono::{DateTime, Datelike, Duration, Local, Timelike, Utc};
+use crate::utils::{credentials, Cache};
+use chrono::{DateTime, Datelike, Duration, Local, Timelike};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -17,20 +17,48 @@ struct UsagePeriod {
     resets_at: Option<String>,
 }
 
+/// Last usage response, kept per API base URL; its age decides whether it's still fresh
 #[derive(Debug, Serialize, Deserialize)]
 struct ApiUsageCache {
     five_hour_utilization: f64,
     seven_day_utilization: f64,
     resets_at: Option<String>,
-    cached_at: String,
 }
 
-#[derive(Default)]
-pub struct UsageSegment;
+pub struct UsageSegment {
+    api_base_url: String,
+    cache_duration: u64,
+    timeout: std::time::Duration,
+}
+
+impl Default for UsageSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl UsageSegment {
     pub fn new() -> Self {
-        Self
+        Self {
+            api_base_url: "https://api.anthropic.com".to_string(),
+            cache_duration: 300,
+            timeout: std::time::Duration::from_secs(2),
+        }
+    }
+
+    /// Load settings from segment options HashMap
+    pub fn with_config_from_options(
+        mut self,
+        options: &HashMap<String, serde_json::Value>,
+    ) -> Self {
+        if let Some(api_base_url) = options.get("api_base_url").and_then(|v| v.as_str()) {
+            self.api_base_url = api_base_url.to_string();
+        }
+        if let Some(cache_duration) = options.get("cache_duration").and_then(|v| v.as_u64()) {
+            self.cache_duration = cache_duration;
+        }
+        self.timeout = super::io_timeout(options, self.timeout);
+        self
     }
 
     fn get_circle_icon(utilization: f64) -> String {
@@ -52,7 +80,7 @@ impl UsageSegment {
             if let Ok(dt) = DateTime::parse_from_rfc3339(time_str) {
                 let mut local_dt = dt.with_timezone(&Local);
                 if local_dt.minute() > 45 {
-                    local_dt = local_dt + Duration::hours(1);
+                    local_dt += Duration::hours(1);
                 }
                 return format!(
                     "{}-{}-{}",
@@ -65,44 +93,17 @@ impl UsageSegment {
         "?".to_string()
     }
 
-    fn get_cache_path() -> Option<std::path::PathBuf> {
-        let home = dirs::home_dir()?;
-        Some(
-            home.join(".claude")
-                .join("ccline")
-                .join(".api_usage_cache.json"),
-        )
+    fn cache() -> Cache<String, ApiUsageCache> {
+        Cache::new("usage")
     }
 
-    fn load_cache(&self) -> Option<ApiUsageCache> {
-        let cache_path = Self::get_cache_path()?;
-        if !cache_path.exists() {
-            return None;
-        }
-
-        let content = std::fs::read_to_string(&cache_path).ok()?;
-        serde_json::from_str(&content).ok()
+    /// Last stored response for this API and how old it is
+    fn load_cache(&self) -> Option<(ApiUsageCache, std::time::Duration)> {
+        Self::cache().get_with_age(&self.api_base_url)
     }
 
     fn save_cache(&self, cache: &ApiUsageCache) {
-        if let Some(cache_path) = Self::get_cache_path() {
-            if let Some(parent) = cache_path.parent() {
-                let _ = std::fs::create_dir_all(parent);
-            }
-            if let Ok(json) = serde_json::to_string_pretty(cache) {
-                let _ = std::fs::write(&cache_path, json);
-            }
-        }
-    }
-
-    fn is_cache_valid(&self, cache: &ApiUsageCache, cache_duration: u64) -> bool {
-        if let Ok(cached_at) = DateTime::parse_from_rfc3339(&cache.cached_at) {
-            let now = Utc::now();
-            let elapsed = now.signed_duration_since(cached_at.with_timezone(&Utc));
-            elapsed.num_seconds() < cache_duration as i64
-        } else {
-            false
-        }
+        let _ = Self::cache().insert(self.api_base_url.clone(), cache);
     }
 
     fn get_claude_code_version() -> String {
@@ -147,7 +148,7 @@ impl UsageSegment {
         &self,
         api_base_url: &str,
         token: &str,
-        timeout_secs: u64,
+        timeout: std::time::Duration,
     ) -> Option<ApiUsageResponse> {
         let url = format!("{}/api/oauth/usage", api_base_url);
         let user_agent = Self::get_claude_code_version();
@@ -168,7 +169,7 @@ impl UsageSegment {
             .set("Authorization", &format!("Bearer {}", token))
             .set("anthropic-beta", "oauth-2025-04-20")
             .set("User-Agent", &user_agent)
-            .timeout(std::time::Duration::from_secs(timeout_secs))
+            .timeout(timeout)
             .call()
             .ok()?;
 
@@ -184,30 +185,15 @@ impl Segment for UsageSegment {
     fn collect(&self, _input: &InputData) -> Option<SegmentData> {
         let token = credentials::get_oauth_token()?;
 
-        // Load config from file to get segment options
-        let config = crate::config::Config::load().ok()?;
-        let segment_config = config.segments.iter().find(|s| s.id == SegmentId::Usage);
-
-        let api_base_url = segment_config
-            .and_then(|sc| sc.options.get("api_base_url"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("https://api.anthropic.com");
-
-        let cache_duration = segment_config
-            .and_then(|sc| sc.options.get("cache_duration"))
-            .and_then(|v| v.as_u64())
-            .unwrap_or(300);
-
-        let timeout = segment_config
-            .and_then(|sc| sc.options.get("timeout"))
-            .and_then(|v| v.as_u64())
-            .unwrap_or(2);
+        let api_base_url = self.api_base_url.as_str();
+        let cache_duration = self.cache_duration;
+        let timeout = self.timeout;
 
-        let cached_data = self.load_cache();
-        let use_cached = cached_data
+        let cached = self.load_cache();
+        let use_cached = cached
             .as_ref()
-            .map(|cache| self.is_cache_valid(cache, cache_duration))
-            .unwrap_or(false);
+            .is_some_and(|(_, age)| age.as_secs() < cache_duration);
+        let cached_data = cached.map(|(cache, _)| cache);
 
         let (five_hour_util, seven_day_util, resets_at) = if use_cached {
             let cache = cached_data.unwrap();
@@ -223,7 +209,6 @@ impl Segment for UsageSegment {
                         five_hour_utilization: response.five_hour.utilization,
                         seven_day_utilization: response.seven_day.utilization,
                         resets_at: response.seven_day.resets_at.clone(),
-                        cached_at: Utc::now().to_rfc3339(),
                     };
                     self.save_cache(&cache);
                     (
@@ -240,7 +225,7 @@ impl Segment for UsageSegment {
                             cache.resets_at,
                         )
                     } else {
-                        return None;
+                        return Some(SegmentData::error("usage request failed"));
                     }
                 }
             }
@@ -272,4 +257,34 @@ impl Segment for UsageSegment {
     fn id(&self) -> SegmentId {
         SegmentId::Usage
     }
+
+    fn refresh_interval(&self) -> Option<std::time::Duration> {
+        Some(std::time::Duration::from_secs(60))
+    }
+
+    fn uses_network(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::isolated_cache;
+
+    #[test]
+    fn usage_cache_lives_in_the_shared_cache_dir() {
+        let dir = isolated_cache();
+        let segment = UsageSegment::new();
+        segment.save_cache(&ApiUsageCache {
+            five_hour_utilization: 12.0,
+            seven_day_utilization: 34.0,
+            resets_at: None,
+        });
+
+        assert!(dir.join("usage.json").exists());
+        let (cache, age) = segment.load_cache().unwrap();
+        assert_eq!(cache.seven_day_utilization, 34.0);
+        assert!(age.as_secs() < segment.cache_duration);
+    }
 }
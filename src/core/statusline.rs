@@ -1,49 +1,377 @@
-use crate::config::{AnsiColor, Config, SegmentConfig, StyleMode};
-use crate::core::segments::SegmentData;
-
-/// Strip ANSI escape sequences and return visible text length
-fn visible_width(text: &str) -> usize {
-    let mut visible = String::new();
-    let mut in_escape = false;
-    let mut chars = text.chars().peekable();
-
-    while let Some(ch) = chars.next() {
-        if ch == '\x1b' {
-            // Start of ANSI escape sequence
-            in_escape = true;
-            // Skip the [ character
-            if chars.peek() == Some(&'[') {
-                chars.next();
+use crate::config::{
+    AnsiColor, Config, GradientTarget, InputData, SegmentConfig, SegmentId, StyleMode,
+};
+use crate::core::palette;
+use crate::core::segments::{OnError, Segment, SegmentData};
+use crate::core::visibility;
+use crate::utils::errors::{self, ErrorCode, ReportedError};
+use crate::utils::width::strip_ansi;
+use crate::utils::{display_width, truncate_to_width, TruncateStrategy};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+
+/// Linearly interpolate between two RGB colors for segment `index` of `count`
+pub fn interpolate_rgb(
+    start: (u8, u8, u8),
+    end: (u8, u8, u8),
+    index: usize,
+    count: usize,
+) -> (u8, u8, u8) {
+    let t = if count <= 1 {
+        0.0
+    } else {
+        index.min(count - 1) as f64 / (count - 1) as f64
+    };
+    let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+
+    (
+        lerp(start.0, end.0),
+        lerp(start.1, end.1),
+        lerp(start.2, end.2),
+    )
+}
+
+/// A piece of a whole-line `format` string
+#[derive(Debug, PartialEq)]
+enum FormatToken<'a> {
+    Literal(&'a str),
+    Segment(&'a str),
+}
+
+/// Split a format string into literals and `{segment_id}` placeholders
+/// An unclosed `{` is kept as literal text
+fn parse_format(format: &str) -> Vec<FormatToken<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = format;
+
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}').map(|i| open + i) else {
+            break;
+        };
+        if open > 0 {
+            tokens.push(FormatToken::Literal(&rest[..open]));
+        }
+        tokens.push(FormatToken::Segment(rest[open + 1..close].trim()));
+        rest = &rest[close + 1..];
+    }
+    if !rest.is_empty() {
+        tokens.push(FormatToken::Literal(rest));
+    }
+
+    tokens
+}
+
+/// Fill a format string with rendered segments keyed by segment id
+/// Unknown, disabled and empty segments render as nothing, and the literal text
+/// between placeholders only appears between two segments that rendered: the
+/// first literal after a rendered segment joins it to the next rendered one.
+/// Text before the first and after the last placeholder is always kept.
+fn render_format(format: &str, rendered: &HashMap<String, String>) -> String {
+    let tokens = parse_format(format);
+    let first = tokens
+        .iter()
+        .position(|token| matches!(token, FormatToken::Segment(_)));
+    let last = tokens
+        .iter()
+        .rposition(|token| matches!(token, FormatToken::Segment(_)));
+
+    let mut output = String::new();
+    let mut emitted_any = false;
+    let mut separator: Option<&str> = None;
+
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            FormatToken::Literal(text) => {
+                let outside = match (first, last) {
+                    (Some(first), Some(last)) => index < first || index > last,
+                    _ => true,
+                };
+                if outside {
+                    output.push_str(text);
+                } else if emitted_any && separator.is_none() {
+                    separator = Some(*text);
+                }
             }
-        } else if in_escape {
-            // Skip until we find the end of the escape sequence (letter)
-            if ch.is_alphabetic() {
-                in_escape = false;
+            FormatToken::Segment(id) => {
+                let Some(text) = rendered.get(*id).filter(|text| !text.is_empty()) else {
+                    continue;
+                };
+                if emitted_any {
+                    output.push_str(separator.take().unwrap_or(""));
+                }
+                output.push_str(text);
+                emitted_any = true;
             }
-        } else {
-            // Regular character
-            visible.push(ch);
         }
     }
 
-    visible.chars().count()
+    output
+}
+
+/// Apply the segment's `max_width` / `truncate` options to its main text
+/// With `number_format = "adaptive"`, the segment's shorter primary
+/// alternatives are tried before truncating
+fn truncate_main_text(config: &SegmentConfig, data: &SegmentData, text: &str) -> String {
+    match config.options.get("max_width").and_then(|v| v.as_u64()) {
+        Some(max_width) => {
+            let max_width = max_width as usize;
+            if display_width(text) <= max_width {
+                return text.to_string();
+            }
+
+            let adaptive =
+                config.options.get("number_format").and_then(|v| v.as_str()) == Some("adaptive");
+            if adaptive && text == data.primary {
+                if let Some(fitting) = data
+                    .primary_alternatives()
+                    .into_iter()
+                    .find(|alternative| display_width(alternative) <= max_width)
+                {
+                    return fitting;
+                }
+            }
+
+            let strategy = config
+                .options
+                .get("truncate")
+                .and_then(|v| v.as_str())
+                .and_then(TruncateStrategy::parse)
+                .unwrap_or_default();
+            truncate_to_width(text, max_width, strategy)
+        }
+        None => text.to_string(),
+    }
+}
+
+/// Priority from the segment's `priority` option (default 0); higher is kept longer
+fn segment_priority(config: &SegmentConfig) -> i64 {
+    config
+        .options
+        .get("priority")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(0)
+}
+
+/// Row a segment is drawn on: 1 (default) or 2, from its `line` option
+fn segment_line(config: &SegmentConfig) -> u64 {
+    match config.options.get("line").and_then(|v| v.as_u64()) {
+        Some(2) => 2,
+        _ => 1,
+    }
+}
+
+/// Config name of a segment id (e.g. `new_api_cost`), as used in format strings
+pub fn segment_name(id: SegmentId) -> String {
+    id.name()
+}
+
+/// Check whether the terminal advertises 24-bit color support
+fn truecolor_supported() -> bool {
+    std::env::var("COLORTERM")
+        .map(|value| value == "truecolor" || value == "24bit")
+        .unwrap_or(false)
+}
+
+/// Order of a segment's primary and secondary text, set with the `layout` option
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SegmentLayout {
+    #[default]
+    PrimarySecondary,
+    SecondaryPrimary,
+    PrimaryOnly,
+    /// Falls back to the primary text when the secondary is empty
+    SecondaryOnly,
+}
+
+impl SegmentLayout {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "primary_secondary" => Some(SegmentLayout::PrimarySecondary),
+            "secondary_primary" => Some(SegmentLayout::SecondaryPrimary),
+            "primary_only" => Some(SegmentLayout::PrimaryOnly),
+            "secondary_only" => Some(SegmentLayout::SecondaryOnly),
+            _ => None,
+        }
+    }
+
+    fn from_options(config: &SegmentConfig) -> Self {
+        config
+            .options
+            .get("layout")
+            .and_then(|v| v.as_str())
+            .and_then(Self::parse)
+            .unwrap_or_default()
+    }
+
+    /// The text shown right after the icon and the text shown after it (may be empty)
+    pub fn arrange<'a>(&self, data: &'a SegmentData) -> (&'a str, &'a str) {
+        let primary = data.primary.as_str();
+        let secondary = data.secondary.as_str();
+        match self {
+            SegmentLayout::PrimarySecondary => (primary, secondary),
+            SegmentLayout::SecondaryPrimary if !secondary.is_empty() => (secondary, primary),
+            SegmentLayout::SecondaryPrimary | SegmentLayout::PrimaryOnly => (primary, ""),
+            SegmentLayout::SecondaryOnly if !secondary.is_empty() => (secondary, ""),
+            SegmentLayout::SecondaryOnly => (primary, ""),
+        }
+    }
 }
 
 pub struct StatusLineGenerator {
     config: Config,
+    use_color: bool,
 }
 
 impl StatusLineGenerator {
     pub fn new(config: Config) -> Self {
-        Self { config }
+        Self {
+            config,
+            use_color: true,
+        }
     }
 
+    /// Turn escape codes off (`--no-color` / `NO_COLOR`)
+    pub fn with_color(mut self, use_color: bool) -> Self {
+        self.use_color = use_color;
+        self
+    }
+
+    /// Render the statusline, or `empty_placeholder` when nothing would show
     pub fn generate(&self, segments: Vec<(SegmentConfig, SegmentData)>) -> String {
-        let mut output = Vec::new();
+        let line = self.compose(segments);
+        let line = match &self.config.empty_placeholder {
+            Some(placeholder) if line.is_empty() => placeholder.clone(),
+            _ => line,
+        };
+        if self.use_color {
+            line
+        } else {
+            strip_ansi(&line)
+        }
+    }
+
+    fn compose(&self, segments: Vec<(SegmentConfig, SegmentData)>) -> String {
+        // Disabled, empty and `hide_when`-hidden segments are skipped alike,
+        // so separators only ever appear between segments that actually render
         let enabled_segments: Vec<_> = segments
             .into_iter()
-            .filter(|(config, _)| config.enabled)
+            .filter(|(config, data)| {
+                config.enabled && !data.is_empty() && !visibility::is_hidden(config, data)
+            })
             .collect();
+        let enabled_segments = self.apply_groups(self.apply_gradient(enabled_segments));
+
+        if let Some(format) = self.config.format.as_deref() {
+            let rendered: HashMap<String, String> = enabled_segments
+                .iter()
+                .map(|(config, data)| (config.key(), self.render_segment(config, data)))
+                .collect();
+            return render_format(format, &rendered);
+        }
+
+        // Each row is laid out on its own; rows with nothing to show are dropped
+        let (first_line, second_line): (Vec<_>, Vec<_>) = enabled_segments
+            .into_iter()
+            .partition(|(config, _)| segment_line(config) == 1);
+
+        [first_line, second_line]
+            .into_iter()
+            .map(|segments| self.generate_line(segments))
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Terminal title escape (`ESC ] 0 ; title BEL`) filled from `title_format`
+    /// with each segment's plain text; None when no title format is set
+    pub fn generate_title(&self, segments: &[(SegmentConfig, SegmentData)]) -> Option<String> {
+        let format = self.config.title_format.as_deref()?;
+        let texts: HashMap<String, String> = segments
+            .iter()
+            .filter(|(config, data)| {
+                config.enabled && !data.is_empty() && !visibility::is_hidden(config, data)
+            })
+            .map(|(config, data)| {
+                let (main_text, extra_text) = SegmentLayout::from_options(config).arrange(data);
+                let text = if extra_text.is_empty() {
+                    main_text.to_string()
+                } else {
+                    format!("{} {}", main_text, extra_text)
+                };
+                (config.key(), text)
+            })
+            .collect();
+
+        // Control characters would end the escape sequence early
+        let title: String = render_format(format, &texts)
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect();
+        Some(format!("\x1b]0;{}\x07", title))
+    }
+
+    /// Render one statusline row, degrading it to fit `max_total_width` if set
+    fn generate_line(&self, segments: Vec<(SegmentConfig, SegmentData)>) -> String {
+        let line = self.join_line(&segments);
+        match self.config.style.max_total_width {
+            Some(max_width) if display_width(&line) > max_width => {
+                self.fit_line(segments, max_width)
+            }
+            _ => line,
+        }
+    }
+
+    /// Shrink an over-long row step by step: drop every secondary text, then
+    /// the lowest-priority segments (rightmost first among equals), and
+    /// finally truncate the main text of the last segment standing
+    fn fit_line(
+        &self,
+        mut segments: Vec<(SegmentConfig, SegmentData)>,
+        max_width: usize,
+    ) -> String {
+        for (_, data) in segments.iter_mut() {
+            data.secondary.clear();
+        }
+        let mut line = self.join_line(&segments);
+
+        while display_width(&line) > max_width && segments.len() > 1 {
+            let lowest = segments
+                .iter()
+                .enumerate()
+                .rev()
+                .min_by_key(|(_, (config, _))| segment_priority(config))
+                .map(|(index, _)| index)
+                .unwrap_or(segments.len() - 1);
+            segments.remove(lowest);
+            line = self.join_line(&segments);
+        }
+
+        let overflow = display_width(&line).saturating_sub(max_width);
+        if overflow > 0 {
+            if let Some((config, data)) = segments.first_mut() {
+                let main_width = config
+                    .options
+                    .get("max_width")
+                    .and_then(|v| v.as_u64())
+                    .map_or(display_width(&data.primary), |limit| {
+                        display_width(&data.primary).min(limit as usize)
+                    });
+                config.options.insert(
+                    "max_width".to_string(),
+                    serde_json::Value::from(main_width.saturating_sub(overflow)),
+                );
+            }
+            line = self.join_line(&segments);
+        }
+
+        line
+    }
+
+    /// Render and join a row's segments with the configured separator
+    fn join_line(&self, enabled_segments: &[(SegmentConfig, SegmentData)]) -> String {
+        let mut output = Vec::new();
 
         for (config, data) in enabled_segments.iter() {
             let rendered = self.render_segment(config, data);
@@ -58,7 +386,7 @@ impl StatusLineGenerator {
 
         // Handle Powerline arrow separators with color transition
         if self.config.style.separator == "\u{e0b0}" {
-            self.join_with_powerline_arrows(&output, &enabled_segments)
+            self.join_with_powerline_arrows(&output, enabled_segments)
         } else {
             // For all other separators, use white color and simple join
             self.join_with_white_separators(&output)
@@ -88,139 +416,143 @@ impl StatusLineGenerator {
         Line::from(vec![Span::raw(full_output)])
     }
 
-    /// Generate TUI-optimized text with intelligent wrapping by segment for preview
+    /// Generate the TUI preview text: exactly what `generate` prints (same
+    /// visibility, rows, `format` and `max_total_width`), converted for ratatui
+    /// Rows wider than the preview pane are wrapped by the preview widget
     pub fn generate_for_tui_preview(
         &self,
         segments: Vec<(SegmentConfig, SegmentData)>,
-        max_width: u16,
-    ) -> ratatui::text::Text<'_> {
+    ) -> ratatui::text::Text<'static> {
         use ansi_to_tui::IntoText;
-        use ratatui::text::{Line, Span, Text};
+        use ratatui::text::{Line, Text};
 
-        let enabled_segments: Vec<_> = segments
-            .into_iter()
-            .filter(|(config, _)| config.enabled)
-            .collect();
+        let output = self.generate(segments);
+        let mut text = output
+            .into_text()
+            .unwrap_or_else(|_| Text::raw(output.clone()));
 
-        if enabled_segments.is_empty() {
-            return Text::from(vec![Line::default()]);
-        }
-
-        // Render each segment individually
-        let mut rendered_segments = Vec::new();
-        let mut segment_configs = Vec::new();
-
-        for (config, data) in &enabled_segments {
-            let rendered = self.render_segment(config, data);
-            if !rendered.is_empty() {
-                rendered_segments.push(rendered);
-                segment_configs.push(config.clone());
-            }
+        // Ensure we have at least one line
+        if text.lines.is_empty() {
+            text.lines.push(Line::default());
         }
+        text
+    }
 
-        if rendered_segments.is_empty() {
-            return Text::from(vec![Line::default()]);
-        }
-
-        // Pre-calculate separators between segments
-        let mut separators = Vec::new();
-        for i in 0..rendered_segments.len().saturating_sub(1) {
-            let separator = if self.config.style.separator == "\u{e0b0}" {
-                // Powerline arrows with color transition
-                let prev_bg = segment_configs
-                    .get(i)
-                    .and_then(|config| config.colors.background.as_ref());
-                let curr_bg = segment_configs
-                    .get(i + 1)
-                    .and_then(|config| config.colors.background.as_ref());
-                self.create_powerline_arrow(prev_bg, curr_bg)
-            } else {
-                // Regular separators with white color
-                format!("\x1b[37m{}\x1b[0m", self.config.style.separator)
+    /// Give consecutive segments of the same group the background of the group's first segment
+    fn apply_groups(
+        &self,
+        mut segments: Vec<(SegmentConfig, SegmentData)>,
+    ) -> Vec<(SegmentConfig, SegmentData)> {
+        let mut group_background: Option<(String, Option<AnsiColor>)> = None;
+
+        for (config, _) in segments.iter_mut() {
+            group_background = match (config.group.as_ref(), group_background.take()) {
+                (Some(group), Some((current, background))) if *group == current => {
+                    config.colors.background = background.clone();
+                    Some((current, background))
+                }
+                (Some(group), _) => Some((group.clone(), config.colors.background.clone())),
+                (None, _) => None,
             };
-            separators.push(separator);
         }
 
-        // Intelligent line wrapping by segment
-        let mut lines: Vec<String> = Vec::new();
-        let mut current_line = String::new();
-        let mut current_width = 0usize;
-        let max_w = max_width as usize;
+        segments
+    }
 
-        for i in 0..rendered_segments.len() {
-            let segment = &rendered_segments[i];
-            let segment_width = visible_width(segment);
+    /// Replace segment colors with gradient colors when a gradient is enabled
+    /// Only applies to RGB endpoints on truecolor terminals; flat theme colors are kept otherwise
+    fn apply_gradient(
+        &self,
+        segments: Vec<(SegmentConfig, SegmentData)>,
+    ) -> Vec<(SegmentConfig, SegmentData)> {
+        let gradient = match &self.config.style.gradient {
+            Some(gradient) if gradient.enabled && truecolor_supported() => gradient,
+            _ => return segments,
+        };
 
-            // Check if adding this segment would exceed max_width
-            if current_width > 0 && current_width + segment_width > max_w {
-                // Current line would overflow, start a new line
-                lines.push(current_line.clone());
-                current_line.clear();
-                current_width = 0;
-            }
+        let (start, end) = match (&gradient.start, &gradient.end) {
+            (
+                AnsiColor::Rgb { r, g, b },
+                AnsiColor::Rgb {
+                    r: r2,
+                    g: g2,
+                    b: b2,
+                },
+            ) => ((*r, *g, *b), (*r2, *g2, *b2)),
+            _ => return segments,
+        };
 
-            // Add the segment to current line
-            current_line.push_str(segment);
-            current_width += segment_width;
-
-            // Handle separator if not the last segment
-            if i < separators.len() {
-                let separator = &separators[i];
-                let separator_width = visible_width(separator);
-
-                // Check if next segment exists
-                if i + 1 < rendered_segments.len() {
-                    let next_segment = &rendered_segments[i + 1];
-                    let next_width = visible_width(next_segment);
-
-                    // Check if separator AND next segment both fit
-                    if current_width + separator_width + next_width <= max_w {
-                        // Both fit, add separator and continue on same line
-                        current_line.push_str(separator);
-                        current_width += separator_width;
-                    } else {
-                        // Separator and/or next segment don't fit
-                        // Don't add separator, just break line
-                        lines.push(current_line.clone());
-                        current_line.clear();
-                        current_width = 0;
+        let count = segments.len();
+        segments
+            .into_iter()
+            .enumerate()
+            .map(|(index, (mut config, data))| {
+                let (r, g, b) = interpolate_rgb(start, end, index, count);
+                let color = AnsiColor::Rgb { r, g, b };
+                match gradient.target {
+                    GradientTarget::Foreground => {
+                        config.colors.icon = Some(color.clone());
+                        config.colors.text = Some(color);
+                    }
+                    GradientTarget::Background => {
+                        config.colors.background = Some(color);
                     }
                 }
-            }
-        }
-
-        // Add the last line if it's not empty
-        if !current_line.is_empty() {
-            lines.push(current_line);
-        }
+                (config, data)
+            })
+            .collect()
+    }
 
-        // Convert string lines to ratatui Text
-        let mut tui_lines = Vec::new();
-        for line in lines {
-            if let Ok(text) = line.into_text() {
-                for tui_line in text.lines {
-                    tui_lines.push(tui_line);
-                }
-            } else {
-                tui_lines.push(Line::from(vec![Span::raw(line)]));
-            }
-        }
+    /// Render the segment with this generator's config (icon, colors, layout)
+    /// but without separators, so a theme's look can be checked in isolation
+    pub fn render_by_id(&self, id: SegmentId, data: &SegmentData) -> Option<String> {
+        let config = self.config.segments.iter().find(|s| s.id == id)?;
+        Some(self.render_segment(config, data))
+    }
 
-        // Ensure we have at least one line
-        if tui_lines.is_empty() {
-            tui_lines.push(Line::default());
+    /// With `auto_color`, a text color derived from the segment's `label`
+    /// metadata (or its id) so each instance keeps its own palette color
+    fn auto_color(&self, config: &SegmentConfig, data: &SegmentData) -> Option<AnsiColor> {
+        if !config
+            .options
+            .get("auto_color")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            return None;
         }
-
-        Text::from(tui_lines)
+        let label = data
+            .metadata
+            .get("label")
+            .cloned()
+            .unwrap_or_else(|| config.key());
+        Some(palette::color_for_label(&self.config, &label))
     }
 
-    fn render_segment(&self, config: &SegmentConfig, data: &SegmentData) -> String {
+    /// Render one segment, styled by `config`, without separators
+    pub fn render_segment(&self, config: &SegmentConfig, data: &SegmentData) -> String {
         let icon = if let Some(dynamic_icon) = data.metadata.get("dynamic_icon") {
             dynamic_icon.clone()
         } else {
-            self.get_icon(config)
+            self.get_dynamic_icon(data)
+                .unwrap_or_else(|| self.get_icon(config))
         };
 
+        // Segments may override the text color at runtime (e.g. budget bands)
+        let text_color = data
+            .metadata
+            .get("dynamic_text_color")
+            .and_then(|color| serde_json::from_str::<AnsiColor>(color).ok())
+            .or_else(|| self.auto_color(config, data))
+            .or_else(|| config.colors.text.clone());
+
+        let (main_text, extra_text) = SegmentLayout::from_options(config).arrange(data);
+        let main_text = truncate_main_text(config, data, main_text);
+        let main_text = self.apply_secondary_colors(&main_text, data, text_color.as_ref());
+        let main_text = main_text.as_str();
+        let extra_text = self.apply_secondary_colors(extra_text, data, text_color.as_ref());
+        let extra_text = extra_text.as_str();
+
         // Apply background color to the entire segment if set
         if let Some(bg_color) = &config.colors.background {
             let bg_code = self.apply_background_color(bg_color);
@@ -234,22 +566,14 @@ impl StatusLineGenerator {
             };
 
             let text_styled = self
-                .apply_style(
-                    &data.primary,
-                    config.colors.text.as_ref(),
-                    config.styles.text_bold,
-                )
+                .apply_style(main_text, text_color.as_ref(), config.styles.text_bold)
                 .replace("\x1b[0m", "");
 
             let mut segment_content = format!(" {} {} ", icon_colored, text_styled);
 
-            if !data.secondary.is_empty() {
+            if !extra_text.is_empty() {
                 let secondary_styled = self
-                    .apply_style(
-                        &data.secondary,
-                        config.colors.text.as_ref(),
-                        config.styles.text_bold,
-                    )
+                    .apply_style(extra_text, text_color.as_ref(), config.styles.text_bold)
                     .replace("\x1b[0m", "");
                 segment_content.push_str(&format!("{} ", secondary_styled));
             }
@@ -259,22 +583,15 @@ impl StatusLineGenerator {
         } else {
             // No background color, use original logic
             let icon_colored = self.apply_color(&icon, config.colors.icon.as_ref());
-            let text_styled = self.apply_style(
-                &data.primary,
-                config.colors.text.as_ref(),
-                config.styles.text_bold,
-            );
+            let text_styled =
+                self.apply_style(main_text, text_color.as_ref(), config.styles.text_bold);
 
             let mut segment = format!("{} {}", icon_colored, text_styled);
 
-            if !data.secondary.is_empty() {
+            if !extra_text.is_empty() {
                 segment.push_str(&format!(
                     " {}",
-                    self.apply_style(
-                        &data.secondary,
-                        config.colors.text.as_ref(),
-                        config.styles.text_bold
-                    )
+                    self.apply_style(extra_text, text_color.as_ref(), config.styles.text_bold)
                 ));
             }
 
@@ -282,6 +599,36 @@ impl StatusLineGenerator {
         }
     }
 
+    /// Color the words of `text` listed in the segment's `secondary_colors`
+    /// metadata (`[[word, color], ..]`), going back to `text_color` after each
+    fn apply_secondary_colors(
+        &self,
+        text: &str,
+        data: &SegmentData,
+        text_color: Option<&AnsiColor>,
+    ) -> String {
+        let Some(colors) = data
+            .metadata
+            .get("secondary_colors")
+            .and_then(|colors| serde_json::from_str::<Vec<(String, AnsiColor)>>(colors).ok())
+        else {
+            return text.to_string();
+        };
+
+        let restore = text_color
+            .map(AnsiColor::foreground_code)
+            .unwrap_or_else(|| "\x1b[39m".to_string());
+        text.split(' ')
+            .map(
+                |word| match colors.iter().find(|(colored, _)| colored == word) {
+                    Some((_, color)) => format!("{}{}{}", color.foreground_code(), word, restore),
+                    None => word.to_string(),
+                },
+            )
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
     fn get_icon(&self, config: &SegmentConfig) -> String {
         match self.config.style.mode {
             StyleMode::Plain => config.icon.plain.clone(),
@@ -290,6 +637,16 @@ impl StatusLineGenerator {
         }
     }
 
+    /// Runtime icon override for the current icon mode
+    /// (`dynamic_icon_plain` / `dynamic_icon_nerd_font` metadata)
+    fn get_dynamic_icon(&self, data: &SegmentData) -> Option<String> {
+        let key = match self.config.style.mode {
+            StyleMode::Plain => "dynamic_icon_plain",
+            StyleMode::NerdFont | StyleMode::Powerline => "dynamic_icon_nerd_font",
+        };
+        data.metadata.get(key).cloned()
+    }
+
     fn apply_color(&self, text: &str, color: Option<&AnsiColor>) -> String {
         match color {
             Some(AnsiColor::Color16 { c16 }) => {
@@ -363,9 +720,26 @@ impl StatusLineGenerator {
             return String::new();
         }
 
-        // Use white color for separator
-        let white_separator = format!("\x1b[37m{}\x1b[0m", self.config.style.separator);
-        rendered_segments.join(&white_separator)
+        // White unless the style sets separator colors
+        let fg_code = self
+            .config
+            .style
+            .separator_fg
+            .as_ref()
+            .map(|color| self.color_to_foreground_code(color))
+            .unwrap_or_else(|| "\x1b[37m".to_string());
+        let bg_code = self
+            .config
+            .style
+            .separator_bg
+            .as_ref()
+            .map(|color| self.apply_background_color(color))
+            .unwrap_or_default();
+        let separator = format!(
+            "{}{}{}\x1b[0m",
+            bg_code, fg_code, self.config.style.separator
+        );
+        rendered_segments.join(&separator)
     }
 
     /// Join segments with Powerline arrow separators with proper color transitions
@@ -385,15 +759,11 @@ impl StatusLineGenerator {
         let mut result = rendered_segments[0].clone();
 
         for (i, _) in rendered_segments.iter().enumerate().skip(1) {
-            let prev_bg = segment_configs
-                .get(i - 1)
-                .and_then(|(config, _)| config.colors.background.as_ref());
-            let curr_bg = segment_configs
-                .get(i)
-                .and_then(|(config, _)| config.colors.background.as_ref());
+            let prev = segment_configs.get(i - 1).map(|(config, _)| config);
+            let curr = segment_configs.get(i).map(|(config, _)| config);
 
             // Create Powerline arrow with color transition
-            let arrow = self.create_powerline_arrow(prev_bg, curr_bg);
+            let arrow = self.create_powerline_separator(prev, curr);
 
             result.push_str(&arrow);
             result.push_str(&rendered_segments[i]);
@@ -404,6 +774,44 @@ impl StatusLineGenerator {
         result
     }
 
+    /// Create the separator between two Powerline segments
+    /// Neighbors in the same group, or ungrouped neighbors sharing a background,
+    /// get a thin subseparator so the boundary stays visible
+    fn create_powerline_separator(
+        &self,
+        prev: Option<&SegmentConfig>,
+        curr: Option<&SegmentConfig>,
+    ) -> String {
+        let prev_bg = prev.and_then(|config| config.colors.background.as_ref());
+        let curr_bg = curr.and_then(|config| config.colors.background.as_ref());
+
+        // Grouped neighbors are joined by their group; otherwise by a shared background
+        let prev_group = prev.and_then(|config| config.group.as_ref());
+        let curr_group = curr.and_then(|config| config.group.as_ref());
+        let joined = match (prev_group, curr_group) {
+            (Some(prev_group), Some(curr_group)) => prev_group == curr_group,
+            _ => prev_bg.is_some() && prev_bg == curr_bg,
+        };
+
+        if joined {
+            let subseparator = self.config.style.subseparator.as_deref().unwrap_or("│");
+            let fg_code = self
+                .config
+                .style
+                .separator_fg
+                .as_ref()
+                .or_else(|| prev.and_then(|config| config.colors.text.as_ref()))
+                .map(|color| self.color_to_foreground_code(color))
+                .unwrap_or_default();
+            let bg_code = curr_bg
+                .map(|color| self.apply_background_color(color))
+                .unwrap_or_default();
+            return format!("{}{}{}\x1b[0m", bg_code, fg_code, subseparator);
+        }
+
+        self.create_powerline_arrow(prev_bg, curr_bg)
+    }
+
     /// Create a Powerline arrow with proper color transition
     fn create_powerline_arrow(
         &self,
@@ -439,83 +847,1214 @@ impl StatusLineGenerator {
 
     /// Convert AnsiColor to foreground color code
     fn color_to_foreground_code(&self, color: &AnsiColor) -> String {
-        match color {
-            AnsiColor::Color16 { c16 } => {
-                let code = if *c16 < 8 { 30 + c16 } else { 90 + (c16 - 8) };
-                format!("\x1b[{}m", code)
-            }
-            AnsiColor::Color256 { c256 } => {
-                format!("\x1b[38;5;{}m", c256)
-            }
-            AnsiColor::Rgb { r, g, b } => {
-                format!("\x1b[38;2;{};{};{}m", r, g, b)
+        color.foreground_code()
+    }
+}
+
+/// Replace failed segment data according to the segment's `on_error` option
+fn resolve_segment_error(config: &SegmentConfig, mut data: SegmentData) -> Option<SegmentData> {
+    let option = |key: &str| config.options.get(key).and_then(|v| v.as_str());
+    let on_error = option("on_error")
+        .and_then(OnError::parse)
+        .unwrap_or_default();
+
+    match on_error {
+        OnError::Hide => return None,
+        OnError::Glyph => {
+            data.primary = option("error_glyph").unwrap_or("✗").to_string();
+            let color = config
+                .options
+                .get("error_color")
+                .and_then(|v| serde_json::from_value::<AnsiColor>(v.clone()).ok())
+                .unwrap_or(AnsiColor::Color16 { c16: 9 });
+            if let Ok(color_json) = serde_json::to_string(&color) {
+                data.metadata
+                    .insert("dynamic_text_color".to_string(), color_json);
             }
         }
+        OnError::Placeholder => {
+            data.primary = option("error_placeholder").unwrap_or("--").to_string();
+        }
     }
+
+    data.secondary.clear();
+    Some(data)
+}
+
+/// Collect one segment, applying its `on_error` handling; None if it renders nothing
+fn collect_segment(
+    segment: &dyn Segment,
+    config: &SegmentConfig,
+    input: &InputData,
+) -> Option<SegmentData> {
+    segment
+        .collect(input)
+        .and_then(|data| {
+            if data.is_error() {
+                let code = if segment.uses_network() {
+                    ErrorCode::Network
+                } else {
+                    ErrorCode::Segment
+                };
+                let message = data.metadata.get("error").cloned().unwrap_or_default();
+                errors::report_recoverable(
+                    &ReportedError::new(code, message).with_segment(config.key()),
+                );
+                resolve_segment_error(config, data)
+            } else {
+                Some(data)
+            }
+        })
+        .filter(|data| !data.is_empty())
+}
+
+/// Build and collect one configured segment, logging how long it took
+/// Applies `on_error` but not `enabled`; None when the segment shows nothing
+pub fn collect_configured(
+    segment_config: &SegmentConfig,
+    input: &InputData,
+) -> Option<SegmentData> {
+    let started = Instant::now();
+    let segment_data = crate::core::registry::create_segment(segment_config)
+        .and_then(|segment| collect_segment(segment.as_ref(), segment_config, input));
+    crate::log_debug!(
+        "{} collected in {}ms ({})",
+        segment_config.key(),
+        started.elapsed().as_millis(),
+        if segment_data.is_some() {
+            "shown"
+        } else {
+            "hidden"
+        }
+    );
+    segment_data
+}
+
+/// Number of segments collected at once: `max_concurrency`, or by default
+/// one worker per network segment plus two for the local ones
+fn collection_concurrency(config: &Config) -> usize {
+    let limit = config.max_concurrency.unwrap_or_else(|| {
+        let network = config
+            .segments
+            .iter()
+            .filter_map(crate::core::registry::create_segment)
+            .filter(|segment| segment.uses_network())
+            .count();
+        network + 2
+    });
+    limit.clamp(1, config.segments.len().max(1))
 }
 
+/// Collect every configured segment, in config order, on a small worker pool
 pub fn collect_all_segments(
     config: &Config,
-    input: &crate::config::InputData,
+    input: &InputData,
 ) -> Vec<(SegmentConfig, SegmentData)> {
-    use crate::core::segments::*;
+    let workers = collection_concurrency(config);
+    let collected: Vec<Option<SegmentData>> = if workers == 1 {
+        config
+            .segments
+            .iter()
+            .map(|segment_config| collect_configured(segment_config, input))
+            .collect()
+    } else {
+        // Workers pull the next segment index until none are left; segments
+        // aren't Send, so each one is built on the worker that collects it
+        let next = AtomicUsize::new(0);
+        let slots: Vec<Mutex<Option<SegmentData>>> =
+            config.segments.iter().map(|_| Mutex::new(None)).collect();
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(segment_config) = config.segments.get(index) else {
+                        break;
+                    };
+                    *lock(&slots[index]) = collect_configured(segment_config, input);
+                });
+            }
+        });
+        slots
+            .into_iter()
+            .map(|slot| slot.into_inner().unwrap_or_else(|e| e.into_inner()))
+            .collect()
+    };
+
+    config
+        .segments
+        .iter()
+        .zip(collected)
+        .filter_map(|(segment_config, data)| data.map(|data| (segment_config.clone(), data)))
+        .collect()
+}
 
-    let mut results = Vec::new();
+/// Collected data per segment, with when it was collected
+type CollectedCache = HashMap<String, (Instant, Option<SegmentData>)>;
+
+/// Segment collection for long-lived modes (`--watch`)
+/// Each segment is re-collected only once its `refresh_interval` has elapsed;
+/// until then the previous data is reused
+#[derive(Default)]
+pub struct SegmentCollector {
+    cache: Arc<Mutex<CollectedCache>>,
+    /// Segments with a background refresh currently running
+    in_flight: Arc<Mutex<HashSet<String>>>,
+    background: bool,
+}
 
-    for segment_config in &config.segments {
-        let segment_data = match segment_config.id {
-            crate::config::SegmentId::Model => {
-                let segment = ModelSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Directory => {
-                let segment = DirectorySegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Git => {
-                let show_sha = segment_config
-                    .options
-                    .get("show_sha")
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(false);
-                let segment = GitSegment::new().with_sha(show_sha);
-                segment.collect(input)
-            }
-            crate::config::SegmentId::ContextWindow => {
-                let segment = ContextWindowSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Usage => {
-                let segment = UsageSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Cost => {
-                let segment = CostSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::Session => {
-                let segment = SessionSegment::new();
-                segment.collect(input)
-            }
-            crate::config::SegmentId::OutputStyle => {
-                let segment = OutputStyleSegment::new();
-                segment.collect(input)
+impl SegmentCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refresh network segments on background threads: a stale network
+    /// segment keeps its previous data (or stays hidden until the first
+    /// fetch lands) while a fresh value is fetched
+    pub fn with_background_refresh(mut self) -> Self {
+        self.background = true;
+        self
+    }
+
+    pub fn collect(
+        &mut self,
+        config: &Config,
+        input: &InputData,
+    ) -> Vec<(SegmentConfig, SegmentData)> {
+        self.collect_at(config, input, Instant::now())
+    }
+
+    /// Collect as of `now`, reusing data that is still fresh at that moment
+    pub fn collect_at(
+        &mut self,
+        config: &Config,
+        input: &InputData,
+        now: Instant,
+    ) -> Vec<(SegmentConfig, SegmentData)> {
+        let mut results = Vec::new();
+
+        for segment_config in &config.segments {
+            let segment = match crate::core::registry::create_segment(segment_config) {
+                Some(segment) => segment,
+                None => continue,
+            };
+
+            let cached = lock(&self.cache).get(&segment_config.key()).cloned();
+            let fresh = match (segment.refresh_interval(), &cached) {
+                (Some(interval), Some((collected_at, data))) => {
+                    (now.saturating_duration_since(*collected_at) < interval).then(|| data.clone())
+                }
+                _ => None,
+            };
+
+            let segment_data = match fresh {
+                Some(data) => data,
+                None if self.background && segment.uses_network() => {
+                    self.refresh_in_background(segment_config, input);
+                    cached.and_then(|(_, data)| data)
+                }
+                None => {
+                    let data = collect_segment(segment.as_ref(), segment_config, input);
+                    lock(&self.cache).insert(segment_config.key(), (now, data.clone()));
+                    data
+                }
+            };
+
+            if let Some(data) = segment_data {
+                results.push((segment_config.clone(), data));
             }
-            crate::config::SegmentId::Update => {
-                let segment = UpdateSegment::new();
-                segment.collect(input)
+        }
+
+        results
+    }
+
+    /// Re-collect a segment on its own thread, unless a refresh is already running
+    fn refresh_in_background(&self, config: &SegmentConfig, input: &InputData) {
+        if !lock(&self.in_flight).insert(config.key()) {
+            return;
+        }
+
+        let config = config.clone();
+        let input = input.clone();
+        let cache = Arc::clone(&self.cache);
+        let in_flight = Arc::clone(&self.in_flight);
+        std::thread::spawn(move || {
+            // Segments aren't Send, so the segment is built on this thread
+            let data = crate::core::registry::create_segment(&config)
+                .and_then(|segment| collect_segment(segment.as_ref(), &config, &input));
+            let key = config.key();
+            lock(&cache).insert(key.clone(), (Instant::now(), data));
+            lock(&in_flight).remove(&key);
+        });
+    }
+}
+
+/// Lock a mutex, recovering the data if another thread panicked while holding it
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{segment_config, segment_data, strip_ansi};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Default config in plain icon mode with no segments of its own
+    fn config_with_separator(separator: &str) -> Config {
+        let mut config = Config::default();
+        config.style.mode = StyleMode::Plain;
+        config.style.separator = separator.to_string();
+        config.segments.clear();
+        config
+    }
+
+    fn with_background(id: SegmentId, c16: u8) -> SegmentConfig {
+        let mut config = segment_config(id);
+        config.colors.background = Some(AnsiColor::Color16 { c16 });
+        config
+    }
+
+    #[test]
+    fn tui_preview_hides_what_the_statusline_hides() {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        let mut git = segment_config(SegmentId::Git);
+        git.options.insert(
+            "hide_when".to_string(),
+            serde_json::json!("primary == main"),
+        );
+        let segments = vec![
+            (segment_config(SegmentId::Model), segment_data("Sonnet", "")),
+            (git, segment_data("main", "")),
+            (segment_config(SegmentId::Session), segment_data("3m", "")),
+        ];
+
+        let preview: String = generator
+            .generate_for_tui_preview(segments.clone())
+            .lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(
+            preview.contains("Sonnet") && preview.contains("3m"),
+            "{}",
+            preview
+        );
+        assert!(!preview.contains("main"), "{}", preview);
+        assert_eq!(preview, strip_ansi(&generator.generate(segments)));
+    }
+
+    #[test]
+    fn same_background_neighbors_get_subseparator() {
+        let generator = StatusLineGenerator::new(config_with_separator("\u{e0b0}"));
+        let line = generator.generate(vec![
+            (with_background(SegmentId::Model, 4), segment_data("a", "")),
+            (with_background(SegmentId::Git, 4), segment_data("b", "")),
+        ]);
+
+        assert!(line.contains('│'));
+        assert!(!line.contains('\u{e0b0}'));
+    }
+
+    #[test]
+    fn different_background_neighbors_keep_arrow() {
+        let generator = StatusLineGenerator::new(config_with_separator("\u{e0b0}"));
+        let line = generator.generate(vec![
+            (with_background(SegmentId::Model, 4), segment_data("a", "")),
+            (with_background(SegmentId::Git, 2), segment_data("b", "")),
+        ]);
+
+        assert!(line.contains('\u{e0b0}'));
+        assert!(!line.contains('│'));
+        // Arrow drawn in the previous background over the next one
+        assert!(line.contains("\x1b[42m\x1b[34m\u{e0b0}"));
+    }
+
+    #[test]
+    fn configured_subseparator_is_used() {
+        let mut config = config_with_separator("\u{e0b0}");
+        config.style.subseparator = Some("¦".to_string());
+        let generator = StatusLineGenerator::new(config);
+        let line = generator.generate(vec![
+            (with_background(SegmentId::Model, 4), segment_data("a", "")),
+            (with_background(SegmentId::Git, 4), segment_data("b", "")),
+        ]);
+
+        assert_eq!(strip_ansi(&line), " * a ¦ * b ");
+    }
+
+    #[test]
+    fn empty_segment_is_skipped_like_none() {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        let with_empty = generator.generate(vec![
+            (segment_config(SegmentId::Model), segment_data("a", "")),
+            (segment_config(SegmentId::Git), SegmentData::empty()),
+            (segment_config(SegmentId::Cost), segment_data("b", "")),
+        ]);
+        let without = generator.generate(vec![
+            (segment_config(SegmentId::Model), segment_data("a", "")),
+            (segment_config(SegmentId::Cost), segment_data("b", "")),
+        ]);
+
+        assert_eq!(with_empty, without);
+        assert_eq!(strip_ansi(&with_empty), "* a | * b");
+    }
+
+    #[test]
+    fn only_empty_segments_render_nothing() {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        let line = generator.generate(vec![
+            (segment_config(SegmentId::Model), SegmentData::empty()),
+            (
+                segment_config(SegmentId::Git),
+                segment_data("", "secondary only"),
+            ),
+        ]);
+
+        assert_eq!(line, "");
+    }
+
+    #[test]
+    fn interpolation_spans_endpoints_evenly() {
+        let (start, end) = ((0, 0, 0), (200, 100, 50));
+        let colors: Vec<_> = (0..5).map(|i| interpolate_rgb(start, end, i, 5)).collect();
+
+        assert_eq!(
+            colors,
+            vec![
+                (0, 0, 0),
+                (50, 25, 13),
+                (100, 50, 25),
+                (150, 75, 38),
+                (200, 100, 50)
+            ]
+        );
+    }
+
+    #[test]
+    fn interpolation_handles_single_segment_and_descending_channels() {
+        assert_eq!(
+            interpolate_rgb((10, 20, 30), (90, 80, 70), 0, 1),
+            (10, 20, 30)
+        );
+        assert_eq!(
+            interpolate_rgb((255, 0, 0), (0, 0, 255), 1, 3),
+            (128, 0, 128)
+        );
+        // Indices past the end clamp to the end color
+        assert_eq!(interpolate_rgb((0, 0, 0), (9, 9, 9), 7, 3), (9, 9, 9));
+    }
+
+    #[test]
+    fn gradient_applies_only_on_truecolor() {
+        use crate::config::GradientConfig;
+
+        let mut env = crate::test_support::EnvGuard::new();
+        let mut config = config_with_separator(" ");
+        config.style.gradient = Some(GradientConfig {
+            enabled: true,
+            start: AnsiColor::Rgb { r: 0, g: 0, b: 0 },
+            end: AnsiColor::Rgb { r: 200, g: 0, b: 0 },
+            target: GradientTarget::Foreground,
+        });
+        let generator = StatusLineGenerator::new(config);
+        let segments = || {
+            vec![
+                (segment_config(SegmentId::Model), segment_data("a", "")),
+                (segment_config(SegmentId::Git), segment_data("b", "")),
+            ]
+        };
+
+        env.set("COLORTERM", "truecolor");
+        let line = generator.generate(segments());
+        assert!(line.contains("\x1b[38;2;0;0;0ma"), "{:?}", line);
+        assert!(line.contains("\x1b[38;2;200;0;0mb"), "{:?}", line);
+
+        env.remove("COLORTERM");
+        assert!(!generator.generate(segments()).contains("38;2"));
+    }
+
+    fn grouped(id: SegmentId, c16: u8, group: Option<&str>) -> SegmentConfig {
+        let mut config = with_background(id, c16);
+        config.group = group.map(str::to_string);
+        config
+    }
+
+    #[test]
+    fn grouped_segments_share_the_first_background() {
+        let generator = StatusLineGenerator::new(config_with_separator("\u{e0b0}"));
+        let grouped = generator.apply_groups(vec![
+            (
+                grouped(SegmentId::Model, 4, Some("info")),
+                segment_data("a", ""),
+            ),
+            (
+                grouped(SegmentId::Git, 2, Some("info")),
+                segment_data("b", ""),
+            ),
+            (
+                grouped(SegmentId::Cost, 3, Some("money")),
+                segment_data("c", ""),
+            ),
+            (
+                grouped(SegmentId::Usage, 5, Some("money")),
+                segment_data("d", ""),
+            ),
+        ]);
+
+        let backgrounds: Vec<_> = grouped
+            .iter()
+            .map(|(config, _)| config.colors.background.clone())
+            .collect();
+        assert_eq!(
+            backgrounds,
+            vec![
+                Some(AnsiColor::Color16 { c16: 4 }),
+                Some(AnsiColor::Color16 { c16: 4 }),
+                Some(AnsiColor::Color16 { c16: 3 }),
+                Some(AnsiColor::Color16 { c16: 3 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn groups_split_by_subseparator_and_join_by_arrow() {
+        let generator = StatusLineGenerator::new(config_with_separator("\u{e0b0}"));
+        let line = generator.generate(vec![
+            (
+                grouped(SegmentId::Model, 4, Some("info")),
+                segment_data("a", ""),
+            ),
+            (
+                grouped(SegmentId::Git, 2, Some("info")),
+                segment_data("b", ""),
+            ),
+            (grouped(SegmentId::Cost, 3, None), segment_data("c", "")),
+        ]);
+
+        assert_eq!(line.matches('│').count(), 1);
+        assert_eq!(line.matches('\u{e0b0}').count(), 1);
+    }
+
+    #[test]
+    fn ungrouped_segment_ends_the_group() {
+        let generator = StatusLineGenerator::new(config_with_separator("\u{e0b0}"));
+        let grouped = generator.apply_groups(vec![
+            (
+                grouped(SegmentId::Model, 4, Some("info")),
+                segment_data("a", ""),
+            ),
+            (grouped(SegmentId::Cost, 3, None), segment_data("b", "")),
+            (
+                grouped(SegmentId::Git, 2, Some("info")),
+                segment_data("c", ""),
+            ),
+        ]);
+
+        assert_eq!(
+            grouped[2].0.colors.background,
+            Some(AnsiColor::Color16 { c16: 2 })
+        );
+    }
+
+    struct FailingSegment;
+
+    impl Segment for FailingSegment {
+        fn collect(&self, _input: &InputData) -> Option<SegmentData> {
+            let mut data = SegmentData::error("connection refused");
+            data.secondary = "provider".to_string();
+            Some(data)
+        }
+
+        fn id(&self) -> SegmentId {
+            SegmentId::NewApiCost
+        }
+    }
+
+    fn collect_failing(options: serde_json::Value) -> Option<SegmentData> {
+        let mut config = segment_config(SegmentId::NewApiCost);
+        config.options = serde_json::from_value(options).unwrap();
+        collect_segment(
+            &FailingSegment,
+            &config,
+            &crate::test_support::input_data(serde_json::json!({})),
+        )
+    }
+
+    #[test]
+    fn failed_segment_is_hidden_by_default() {
+        assert!(collect_failing(serde_json::json!({})).is_none());
+        assert!(collect_failing(serde_json::json!({"on_error": "hide"})).is_none());
+        assert!(collect_failing(serde_json::json!({"on_error": "bogus"})).is_none());
+    }
+
+    #[test]
+    fn glyph_mode_shows_glyph_in_error_color() {
+        let data = collect_failing(serde_json::json!({"on_error": "glyph"})).unwrap();
+        assert_eq!(data.primary, "✗");
+        assert_eq!(data.secondary, "");
+        assert_eq!(data.metadata["dynamic_text_color"], r#"{"c16":9}"#);
+
+        let data = collect_failing(serde_json::json!({
+            "on_error": "glyph",
+            "error_glyph": "!",
+            "error_color": {"c256": 196},
+        }))
+        .unwrap();
+        assert_eq!(data.primary, "!");
+        assert_eq!(data.metadata["dynamic_text_color"], r#"{"c256":196}"#);
+    }
+
+    #[test]
+    fn placeholder_mode_keeps_normal_colors() {
+        let data = collect_failing(serde_json::json!({"on_error": "placeholder"})).unwrap();
+        assert_eq!(data.primary, "--");
+        assert!(!data.metadata.contains_key("dynamic_text_color"));
+
+        let data = collect_failing(serde_json::json!({
+            "on_error": "placeholder",
+            "error_placeholder": "n/a",
+        }))
+        .unwrap();
+        assert_eq!(data.primary, "n/a");
+    }
+
+    static SLOW_COLLECTS: AtomicUsize = AtomicUsize::new(0);
+
+    /// Counts its collections and asks to be refreshed once a minute
+    struct SlowSegment;
+
+    impl Segment for SlowSegment {
+        fn collect(&self, _input: &InputData) -> Option<SegmentData> {
+            let n = SLOW_COLLECTS.fetch_add(1, Ordering::SeqCst) + 1;
+            Some(segment_data(&n.to_string(), ""))
+        }
+
+        fn id(&self) -> SegmentId {
+            SegmentId::Custom
+        }
+
+        fn refresh_interval(&self) -> Option<Duration> {
+            Some(Duration::from_secs(60))
+        }
+    }
+
+    #[test]
+    fn collector_reuses_data_until_refresh_interval_elapses() {
+        crate::core::registry::register_segment("test_slow", |_| Box::new(SlowSegment));
+        let mut config = config_with_separator(" | ");
+        let mut slow = segment_config(SegmentId::Custom);
+        slow.name = Some("test_slow".to_string());
+        config.segments.push(slow);
+        let input = crate::test_support::input_data(serde_json::json!({}));
+
+        let mut collector = SegmentCollector::new();
+        let start = Instant::now();
+        let primary = |collected: Vec<(SegmentConfig, SegmentData)>| collected[0].1.primary.clone();
+
+        assert_eq!(primary(collector.collect_at(&config, &input, start)), "1");
+        let later = start + Duration::from_secs(59);
+        assert_eq!(primary(collector.collect_at(&config, &input, later)), "1");
+        let expired = start + Duration::from_secs(61);
+        assert_eq!(primary(collector.collect_at(&config, &input, expired)), "2");
+        assert_eq!(SLOW_COLLECTS.load(Ordering::SeqCst), 2);
+    }
+
+    fn render_with_layout(layout: &str, data: &SegmentData) -> String {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        let mut config = segment_config(SegmentId::NewApiCost);
+        config
+            .options
+            .insert("layout".to_string(), serde_json::json!(layout));
+        strip_ansi(&generator.render_segment(&config, data))
+    }
+
+    #[test]
+    fn each_layout_arranges_primary_and_secondary() {
+        let data = segment_data("¥12.50", "newapi");
+
+        assert_eq!(
+            render_with_layout("primary_secondary", &data),
+            "* ¥12.50 newapi"
+        );
+        assert_eq!(
+            render_with_layout("secondary_primary", &data),
+            "* newapi ¥12.50"
+        );
+        assert_eq!(render_with_layout("primary_only", &data), "* ¥12.50");
+        assert_eq!(render_with_layout("secondary_only", &data), "* newapi");
+        assert_eq!(render_with_layout("bogus", &data), "* ¥12.50 newapi");
+    }
+
+    #[test]
+    fn layouts_fall_back_to_primary_without_secondary() {
+        let data = segment_data("¥12.50", "");
+
+        for layout in ["primary_secondary", "secondary_primary", "secondary_only"] {
+            assert_eq!(render_with_layout(layout, &data), "* ¥12.50", "{layout}");
+        }
+    }
+
+    fn rendered(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(id, text)| (id.to_string(), text.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn format_places_segments_by_id() {
+        let segments = rendered(&[("model", "M"), ("git", "G"), ("cost", "C")]);
+        assert_eq!(
+            render_format("[{model} | {git} {cost}]", &segments),
+            "[M | G C]"
+        );
+    }
+
+    #[test]
+    fn format_collapses_literals_around_missing_segments() {
+        let format = "[{model} | {git} {cost}]";
+
+        let segments = rendered(&[("model", "M"), ("cost", "C")]);
+        assert_eq!(render_format(format, &segments), "[M | C]");
+
+        let segments = rendered(&[("git", "G"), ("cost", "C")]);
+        assert_eq!(render_format(format, &segments), "[G C]");
+
+        let segments = rendered(&[("model", "M"), ("git", "")]);
+        assert_eq!(render_format(format, &segments), "[M]");
+
+        assert_eq!(render_format(format, &HashMap::new()), "[]");
+    }
+
+    #[test]
+    fn format_unknown_ids_render_empty() {
+        let segments = rendered(&[("model", "M"), ("cost", "C")]);
+        assert_eq!(
+            render_format("{model} | {weather} | {cost}", &segments),
+            "M | C"
+        );
+    }
+
+    #[test]
+    fn generator_uses_format_when_set() {
+        let mut config = config_with_separator(" | ");
+        config.format = Some("<{cost} / {model}>".to_string());
+        let line = StatusLineGenerator::new(config).generate(vec![
+            (segment_config(SegmentId::Model), segment_data("a", "")),
+            (segment_config(SegmentId::Git), SegmentData::empty()),
+            (segment_config(SegmentId::Cost), segment_data("b", "")),
+        ]);
+
+        assert_eq!(strip_ansi(&line), "<* b / * a>");
+    }
+
+    fn on_line(id: SegmentId, line: u64) -> SegmentConfig {
+        let mut config = segment_config(id);
+        config
+            .options
+            .insert("line".to_string(), serde_json::json!(line));
+        config
+    }
+
+    #[test]
+    fn segments_split_across_two_lines() {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        let line = generator.generate(vec![
+            (on_line(SegmentId::Model, 1), segment_data("a", "")),
+            (on_line(SegmentId::Git, 2), segment_data("b", "")),
+            (segment_config(SegmentId::Cost), segment_data("c", "")),
+            (on_line(SegmentId::Usage, 2), segment_data("d", "")),
+        ]);
+
+        assert_eq!(strip_ansi(&line), "* a | * c\n* b | * d");
+    }
+
+    #[test]
+    fn empty_line_is_dropped() {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        let line = generator.generate(vec![
+            (on_line(SegmentId::Model, 1), segment_data("a", "")),
+            (on_line(SegmentId::Git, 2), SegmentData::empty()),
+        ]);
+        assert_eq!(strip_ansi(&line), "* a");
+
+        let line = generator.generate(vec![
+            (on_line(SegmentId::Model, 1), SegmentData::empty()),
+            (on_line(SegmentId::Git, 2), segment_data("b", "")),
+        ]);
+        assert_eq!(strip_ansi(&line), "* b");
+    }
+
+    #[test]
+    fn max_width_option_truncates_with_the_configured_strategy() {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        let mut config = segment_config(SegmentId::Directory);
+        config
+            .options
+            .insert("max_width".to_string(), serde_json::json!(12));
+        let data = segment_data("~/projects/ccline/src/core", "");
+
+        assert_eq!(
+            strip_ansi(&generator.render_segment(&config, &data)),
+            "* ~/projects/…"
+        );
+        config
+            .options
+            .insert("truncate".to_string(), serde_json::json!("start"));
+        assert_eq!(
+            strip_ansi(&generator.render_segment(&config, &data)),
+            "* …ne/src/core"
+        );
+    }
+
+    #[test]
+    fn adaptive_numbers_pick_the_most_precise_form_that_fits() {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        let locale = crate::utils::locale::Locale::from_name("en").unwrap();
+        let candidates = locale.currency_candidates(12600.0, "¥", 2);
+        let mut data = segment_data(&candidates[0], "");
+        data.set_primary_alternatives(&candidates);
+
+        let render = |max_width: u64, format: &str| {
+            let mut config = segment_config(SegmentId::Cost);
+            config
+                .options
+                .insert("max_width".to_string(), serde_json::json!(max_width));
+            config
+                .options
+                .insert("number_format".to_string(), serde_json::json!(format));
+            strip_ansi(&generator.render_segment(&config, &data))
+        };
+
+        assert_eq!(render(20, "adaptive"), "* ¥12,600.00");
+        assert_eq!(render(8, "adaptive"), "* ¥12,600");
+        assert_eq!(render(6, "adaptive"), "* ¥12.6k");
+        assert_eq!(render(4, "adaptive"), "* ¥13k");
+        // Nothing fits: fall back to truncating the full value
+        assert_eq!(render(3, "adaptive"), "* ¥1…");
+        // Fixed mode never swaps representations
+        assert_eq!(render(8, "fixed"), "* ¥12,600…");
+    }
+
+    #[test]
+    fn max_total_width_degrades_in_stages() {
+        let with_priority = |id: SegmentId, priority: i64| {
+            let mut config = segment_config(id);
+            config
+                .options
+                .insert("priority".to_string(), serde_json::json!(priority));
+            config
+        };
+        let segments = vec![
+            (
+                with_priority(SegmentId::Model, 2),
+                segment_data("Sonnet", "4.5"),
+            ),
+            (
+                with_priority(SegmentId::Directory, 1),
+                segment_data("ccline", "src"),
+            ),
+            (with_priority(SegmentId::Git, 0), segment_data("main", "✓")),
+        ];
+        let render = |max_total_width: usize| {
+            let mut config = config_with_separator(" | ");
+            config.style.max_total_width = Some(max_total_width);
+            strip_ansi(&StatusLineGenerator::new(config).generate(segments.clone()))
+        };
+
+        // Fits: untouched
+        assert_eq!(render(40), "* Sonnet 4.5 | * ccline src | * main ✓");
+        // Stage 1: every secondary text goes
+        assert_eq!(render(30), "* Sonnet | * ccline | * main");
+        // Stage 2: lowest priority segments go, one at a time
+        assert_eq!(render(20), "* Sonnet | * ccline");
+        assert_eq!(render(10), "* Sonnet");
+        // Stage 3: the last segment's main text is truncated
+        assert_eq!(render(6), "* Son…");
+    }
+
+    #[test]
+    fn max_total_width_drops_the_rightmost_of_equal_priority() {
+        let mut config = config_with_separator(" | ");
+        config.style.max_total_width = Some(13);
+        let line = StatusLineGenerator::new(config).generate(vec![
+            (segment_config(SegmentId::Model), segment_data("aaa", "")),
+            (
+                segment_config(SegmentId::Directory),
+                segment_data("bbb", ""),
+            ),
+            (segment_config(SegmentId::Git), segment_data("ccc", "")),
+        ]);
+        assert_eq!(strip_ansi(&line), "* aaa | * bbb");
+    }
+
+    #[test]
+    fn render_by_id_differs_between_themes() {
+        use crate::ui::themes::ThemePresets;
+
+        let data = segment_data("Sonnet 4", "");
+        let render = |theme: Config| {
+            StatusLineGenerator::new(theme)
+                .render_by_id(SegmentId::Model, &data)
+                .expect("model segment in theme")
+        };
+        let cometix = render(ThemePresets::get_cometix());
+        let powerline = render(ThemePresets::get_powerline_dark());
+
+        assert_ne!(cometix, powerline);
+        assert!(strip_ansi(&cometix).contains("Sonnet 4"), "{cometix:?}");
+        assert!(strip_ansi(&powerline).contains("Sonnet 4"), "{powerline:?}");
+        // Only the powerline theme paints a background behind the segment
+        assert!(!cometix.contains("\x1b[48;"), "{cometix:?}");
+        assert!(powerline.contains("\x1b[48;"), "{powerline:?}");
+        // Rendering is deterministic for a given theme
+        assert_eq!(render(ThemePresets::get_cometix()), cometix);
+    }
+
+    #[test]
+    fn render_by_id_is_none_for_a_segment_missing_from_the_theme() {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        assert_eq!(
+            generator.render_by_id(SegmentId::Model, &segment_data("x", "")),
+            None
+        );
+    }
+
+    static NETWORK_FETCHES: AtomicUsize = AtomicUsize::new(0);
+    static NETWORK_RELEASED: std::sync::atomic::AtomicBool =
+        std::sync::atomic::AtomicBool::new(false);
+
+    /// A network segment whose fetch blocks until `NETWORK_RELEASED` is set
+    struct BlockedNetworkSegment;
+
+    impl Segment for BlockedNetworkSegment {
+        fn collect(&self, _input: &InputData) -> Option<SegmentData> {
+            NETWORK_FETCHES.fetch_add(1, Ordering::SeqCst);
+            while !NETWORK_RELEASED.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(5));
             }
-            crate::config::SegmentId::NewApiCost => {
-                let segment = NewApiCostSegment::new()
-                    .with_config_from_options(&segment_config.options);
-                segment.collect(input)
+            Some(segment_data("fetched", ""))
+        }
+
+        fn id(&self) -> SegmentId {
+            SegmentId::Custom
+        }
+
+        fn refresh_interval(&self) -> Option<Duration> {
+            Some(Duration::from_secs(60))
+        }
+
+        fn uses_network(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn background_refresh_keeps_renders_flowing_during_a_slow_fetch() {
+        crate::core::registry::register_segment("test_blocked_network", |_| {
+            Box::new(BlockedNetworkSegment)
+        });
+        let mut config = config_with_separator(" | ");
+        config.segments.push(segment_config(SegmentId::Model));
+        let mut network = segment_config(SegmentId::Custom);
+        network.name = Some("test_blocked_network".to_string());
+        config.segments.push(network);
+        let input = crate::test_support::input_data(serde_json::json!({}));
+        let mut collector = SegmentCollector::new().with_background_refresh();
+
+        // While the fetch is stuck, every render returns at once without it
+        for _ in 0..5 {
+            let started = Instant::now();
+            let collected = collector.collect(&config, &input);
+            assert!(started.elapsed() < Duration::from_millis(500));
+            assert_eq!(collected.len(), 1);
+            assert_eq!(collected[0].0.id, SegmentId::Model);
+        }
+        // ...and only one background fetch was started for it
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while NETWORK_FETCHES.load(Ordering::SeqCst) == 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        collector.collect(&config, &input);
+        assert_eq!(NETWORK_FETCHES.load(Ordering::SeqCst), 1);
+
+        NETWORK_RELEASED.store(true, Ordering::SeqCst);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let collected = loop {
+            let collected = collector.collect(&config, &input);
+            if collected.len() == 2 || Instant::now() > deadline {
+                break collected;
             }
+            std::thread::sleep(Duration::from_millis(10));
         };
+        assert_eq!(collected[1].1.primary, "fetched");
+        assert_eq!(NETWORK_FETCHES.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn title_is_templated_from_plain_segment_text() {
+        let segments = vec![
+            (
+                segment_config(SegmentId::Model),
+                segment_data("Sonnet\x074", ""),
+            ),
+            (
+                segment_config(SegmentId::Directory),
+                segment_data("ccline", "src"),
+            ),
+        ];
+        let mut config = config_with_separator(" | ");
+        assert_eq!(
+            StatusLineGenerator::new(config.clone()).generate_title(&segments),
+            None
+        );
+
+        config.title_format = Some("{directory} — {model}".to_string());
+        assert_eq!(
+            StatusLineGenerator::new(config).generate_title(&segments),
+            // A control character would end the escape early, so it is dropped
+            Some("\x1b]0;ccline src — Sonnet4\x07".to_string())
+        );
+    }
+
+    static ACTIVE_COLLECTS: AtomicUsize = AtomicUsize::new(0);
+    static PEAK_COLLECTS: AtomicUsize = AtomicUsize::new(0);
+
+    /// Records how many instances are collecting at the same moment
+    struct InstrumentedSegment;
+
+    impl Segment for InstrumentedSegment {
+        fn collect(&self, _input: &InputData) -> Option<SegmentData> {
+            let active = ACTIVE_COLLECTS.fetch_add(1, Ordering::SeqCst) + 1;
+            PEAK_COLLECTS.fetch_max(active, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(20));
+            ACTIVE_COLLECTS.fetch_sub(1, Ordering::SeqCst);
+            Some(segment_data("ok", ""))
+        }
+
+        fn id(&self) -> SegmentId {
+            SegmentId::Custom
+        }
+    }
 
-        if let Some(data) = segment_data {
-            results.push((segment_config.clone(), data));
+    #[test]
+    fn collection_never_exceeds_max_concurrency() {
+        let mut config = config_with_separator(" | ");
+        for n in 0..8 {
+            let name = format!("test_instrumented_{n}");
+            crate::core::registry::register_segment(&name, |_| Box::new(InstrumentedSegment));
+            let mut segment = segment_config(SegmentId::Custom);
+            segment.name = Some(name);
+            config.segments.push(segment);
         }
+        config.max_concurrency = Some(3);
+        let input = crate::test_support::input_data(serde_json::json!({}));
+
+        let collected = collect_all_segments(&config, &input);
+
+        let peak = PEAK_COLLECTS.load(Ordering::SeqCst);
+        assert!(peak <= 3, "{peak} segments collected at once");
+        assert!(peak > 1, "collection didn't run in parallel");
+        let names: Vec<String> = collected.iter().map(|(config, _)| config.key()).collect();
+        let expected: Vec<String> = (0..8).map(|n| format!("test_instrumented_{n}")).collect();
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn default_concurrency_is_network_segments_plus_two() {
+        let mut config = config_with_separator(" | ");
+        assert_eq!(collection_concurrency(&config), 1);
+
+        for id in [SegmentId::Model, SegmentId::Directory, SegmentId::Git] {
+            config.segments.push(segment_config(id));
+        }
+        assert_eq!(collection_concurrency(&config), 2);
+        // Usage fetches over the network, so it gets a worker of its own
+        config.segments.push(segment_config(SegmentId::Usage));
+        assert_eq!(collection_concurrency(&config), 3);
+        config.max_concurrency = Some(0);
+        assert_eq!(collection_concurrency(&config), 1);
+        config.max_concurrency = Some(100);
+        assert_eq!(collection_concurrency(&config), 4);
+    }
+
+    fn auto_colored(id: SegmentId) -> SegmentConfig {
+        let mut config = segment_config(id);
+        config.colors.text = Some(AnsiColor::Color16 { c16: 7 });
+        config
+            .options
+            .insert("auto_color".to_string(), serde_json::Value::Bool(true));
+        config
+    }
+
+    #[test]
+    fn auto_color_uses_the_label_color() {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        let mut data = segment_data("$1.00", "");
+        data.metadata
+            .insert("label".to_string(), "api-prod".to_string());
+
+        let color = palette::color_for_label(&generator.config, "api-prod");
+        let expected = generator.apply_color("$1.00", Some(&color));
+        let rendered = generator.render_segment(&auto_colored(SegmentId::Usage), &data);
+        assert!(rendered.contains(&expected), "{:?}", rendered);
+
+        // Rendering again, or from a fresh generator, gives the same bytes
+        let again = StatusLineGenerator::new(config_with_separator(" | "));
+        assert_eq!(
+            again.render_segment(&auto_colored(SegmentId::Usage), &data),
+            rendered
+        );
+    }
+
+    #[test]
+    fn dynamic_text_color_beats_auto_color() {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        let mut data = segment_data("$1.00", "");
+        data.metadata
+            .insert("label".to_string(), "api-prod".to_string());
+        let red = AnsiColor::Color16 { c16: 9 };
+        data.metadata.insert(
+            "dynamic_text_color".to_string(),
+            serde_json::to_string(&red).unwrap(),
+        );
+
+        let rendered = generator.render_segment(&auto_colored(SegmentId::Usage), &data);
+        assert!(rendered.contains(&generator.apply_color("$1.00", Some(&red))));
+    }
+
+    #[test]
+    fn auto_color_is_off_by_default() {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        let mut config = auto_colored(SegmentId::Usage);
+        config.options.remove("auto_color");
+
+        assert_eq!(generator.auto_color(&config, &segment_data("x", "")), None);
+    }
+
+    #[test]
+    fn placeholder_replaces_only_an_empty_line() {
+        let mut config = config_with_separator(" | ");
+        config.empty_placeholder = Some("—".to_string());
+        let generator = StatusLineGenerator::new(config);
+
+        assert_eq!(generator.generate(vec![]), "—");
+        assert_eq!(
+            generator.generate(vec![(
+                segment_config(SegmentId::Model),
+                segment_data("", "")
+            )]),
+            "—"
+        );
+        let mut disabled = segment_config(SegmentId::Model);
+        disabled.enabled = false;
+        assert_eq!(
+            generator.generate(vec![(disabled, segment_data("x", ""))]),
+            "—"
+        );
+
+        let line = generator.generate(vec![(
+            segment_config(SegmentId::Model),
+            segment_data("x", ""),
+        )]);
+        assert_eq!(strip_ansi(&line), "* x");
+    }
+
+    #[test]
+    fn empty_line_stays_empty_without_a_placeholder() {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        assert_eq!(generator.generate(vec![]), "");
+    }
+
+    fn two_segments() -> Vec<(SegmentConfig, SegmentData)> {
+        vec![
+            (segment_config(SegmentId::Model), segment_data("a", "")),
+            (segment_config(SegmentId::Git), segment_data("b", "")),
+        ]
+    }
+
+    #[test]
+    fn plain_separator_uses_the_configured_colors() {
+        let mut config = config_with_separator(" | ");
+        config.style.separator_fg = Some(AnsiColor::Color256 { c256: 244 });
+        config.style.separator_bg = Some(AnsiColor::Color16 { c16: 4 });
+        let line = StatusLineGenerator::new(config).generate(two_segments());
+
+        assert!(
+            line.contains("\x1b[44m\x1b[38;5;244m | \x1b[0m"),
+            "{:?}",
+            line
+        );
+        assert!(!line.contains("\x1b[37m"), "{:?}", line);
+    }
+
+    #[test]
+    fn plain_separator_stays_white_by_default() {
+        let line = StatusLineGenerator::new(config_with_separator(" | ")).generate(two_segments());
+        assert!(line.contains("\x1b[37m | \x1b[0m"), "{:?}", line);
+    }
+
+    #[test]
+    fn subseparator_uses_separator_fg() {
+        let mut config = config_with_separator("\u{e0b0}");
+        config.style.separator_fg = Some(AnsiColor::Color16 { c16: 3 });
+        let line = StatusLineGenerator::new(config).generate(vec![
+            (with_background(SegmentId::Model, 4), segment_data("a", "")),
+            (with_background(SegmentId::Git, 4), segment_data("b", "")),
+        ]);
+
+        assert!(line.contains("\x1b[44m\x1b[33m│\x1b[0m"), "{:?}", line);
     }
 
-    results
+    fn with_secondary_colors(secondary: &str, colors: serde_json::Value) -> SegmentData {
+        let mut data = segment_data("main", secondary);
+        data.metadata
+            .insert("secondary_colors".to_string(), colors.to_string());
+        data
+    }
+
+    #[test]
+    fn secondary_colors_color_their_words_and_restore_the_text_color() {
+        let generator = StatusLineGenerator::new(config_with_separator(" | "));
+        let data = with_secondary_colors("● +3 ~5", serde_json::json!([["+3", {"c16": 10}]]));
+
+        let mut config = segment_config(SegmentId::Git);
+        let rendered = generator.render_segment(&config, &data);
+        assert!(
+            rendered.contains("● \x1b[92m+3\x1b[39m ~5"),
+            "{:?}",
+            rendered
+        );
+
+        config.colors.text = Some(AnsiColor::Color256 { c256: 250 });
+        let rendered = generator.render_segment(&config, &data);
+        assert!(
+            rendered.contains("\x1b[92m+3\x1b[38;5;250m ~5"),
+            "{:?}",
+            rendered
+        );
+        assert_eq!(strip_ansi(&rendered), "* main ● +3 ~5");
+    }
+
+    #[test]
+    fn no_color_strips_every_escape() {
+        let data = with_secondary_colors("+3", serde_json::json!([["+3", {"c16": 10}]]));
+        let mut config = segment_config(SegmentId::Git);
+        config.colors.text = Some(AnsiColor::Color16 { c16: 4 });
+        let segments = vec![
+            (config, data),
+            (segment_config(SegmentId::Model), segment_data("m", "")),
+        ];
+
+        let colored =
+            StatusLineGenerator::new(config_with_separator(" | ")).generate(segments.clone());
+        assert!(colored.contains('\x1b'));
+
+        let plain = StatusLineGenerator::new(config_with_separator(" | "))
+            .with_color(false)
+            .generate(segments);
+        assert_eq!(plain, "* main +3 | * m");
+    }
 }
@@ -0,0 +1,116 @@
+//! `min_render_interval_ms`: when ccline runs again within the interval, the
+//! previous output is printed as-is instead of collecting every segment again.
+
+use crate::utils::Cache;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Renders older than this are dropped from the cache file
+const STALE_AFTER: Duration = Duration::from_secs(3600);
+
+#[derive(Serialize, Deserialize)]
+struct RenderedLine {
+    /// When the line was rendered (milliseconds since epoch)
+    rendered_at_ms: u64,
+    output: String,
+}
+
+/// Reuses a recent render made with the same inputs
+pub struct RenderThrottle {
+    key: String,
+    interval: Duration,
+}
+
+impl RenderThrottle {
+    /// None unless `interval_ms` is set and non-zero
+    /// `inputs` is everything the output depends on (effective config, output
+    /// flags, session); renders only stand in for each other when all of it matches
+    pub fn new(interval_ms: Option<u64>, inputs: &[&str]) -> Option<Self> {
+        let interval = Duration::from_millis(interval_ms.filter(|ms| *ms > 0)?);
+
+        let mut hasher = DefaultHasher::new();
+        inputs.hash(&mut hasher);
+        Some(Self {
+            key: format!("{:016x}", hasher.finish()),
+            interval,
+        })
+    }
+
+    fn cache() -> Cache<String, RenderedLine> {
+        Cache::new("render").with_ttl(STALE_AFTER)
+    }
+
+    /// Output of the last matching render, if it is younger than the interval
+    pub fn cached(&self) -> Option<String> {
+        let line = Self::cache().get(&self.key)?;
+        let age = now_ms().saturating_sub(line.rendered_at_ms);
+        (age < self.interval.as_millis() as u64).then_some(line.output)
+    }
+
+    /// Remember a freshly rendered output
+    pub fn store(&self, output: &str) {
+        let line = RenderedLine {
+            rendered_at_ms: now_ms(),
+            output: output.to_string(),
+        };
+        let _ = Self::cache().insert(self.key.clone(), &line);
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::isolated_cache;
+
+    #[test]
+    fn unset_or_zero_interval_disables_throttling() {
+        assert!(RenderThrottle::new(None, &["config"]).is_none());
+        assert!(RenderThrottle::new(Some(0), &["config"]).is_none());
+        assert!(RenderThrottle::new(Some(500), &["config"]).is_some());
+    }
+
+    #[test]
+    fn stored_line_is_reused_within_the_interval() {
+        let _cache = isolated_cache();
+        let throttle = RenderThrottle::new(Some(60_000), &["config", "session"]).unwrap();
+        assert_eq!(throttle.cached(), None);
+
+        throttle.store("\x1b[1mline\x1b[0m\n");
+        assert_eq!(throttle.cached().as_deref(), Some("\x1b[1mline\x1b[0m\n"));
+    }
+
+    #[test]
+    fn different_inputs_do_not_share_a_line() {
+        let _cache = isolated_cache();
+        RenderThrottle::new(Some(60_000), &["config", "session"])
+            .unwrap()
+            .store("line");
+
+        let other_theme = RenderThrottle::new(Some(60_000), &["other config", "session"]).unwrap();
+        assert_eq!(other_theme.cached(), None);
+    }
+
+    #[test]
+    fn line_older_than_the_interval_is_not_reused() {
+        let _cache = isolated_cache();
+        let throttle = RenderThrottle::new(Some(1_000), &["config"]).unwrap();
+        let line = RenderedLine {
+            rendered_at_ms: now_ms() - 1_000,
+            output: "line".to_string(),
+        };
+        RenderThrottle::cache()
+            .insert(throttle.key.clone(), &line)
+            .unwrap();
+
+        assert_eq!(throttle.cached(), None);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::config::SegmentConfig;
+use crate::core::segments::SegmentData;
+
+/// Comparison operator of a `hide_when` condition
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    /// Symbols of every operator; parsing prefers the longer of two symbols
+    /// starting at the same position, so `<=` isn't read as `<`
+    const ALL: [(&'static str, CompareOp); 6] = [
+        ("==", CompareOp::Eq),
+        ("!=", CompareOp::Ne),
+        ("<=", CompareOp::Le),
+        (">=", CompareOp::Ge),
+        ("<", CompareOp::Lt),
+        (">", CompareOp::Gt),
+    ];
+
+    fn holds(self, ordering: std::cmp::Ordering) -> bool {
+        use std::cmp::Ordering::*;
+        match self {
+            CompareOp::Eq => ordering == Equal,
+            CompareOp::Ne => ordering != Equal,
+            CompareOp::Lt => ordering == Less,
+            CompareOp::Le => ordering != Greater,
+            CompareOp::Gt => ordering == Greater,
+            CompareOp::Ge => ordering != Less,
+        }
+    }
+}
+
+/// One parsed `hide_when` condition
+///
+/// Grammar:
+/// - `primary_empty` / `secondary_empty`
+/// - `<field> <op> <value>`, where field is `primary`, `secondary` or
+///   `metadata.<key>`, op is one of `== != < <= > >=`, and value is a number
+///   or text (optionally in double quotes). Both sides are compared as numbers
+///   when they parse as such, otherwise as text; a missing field never matches
+#[derive(Debug, Clone, PartialEq)]
+pub enum HideCondition {
+    PrimaryEmpty,
+    SecondaryEmpty,
+    Compare {
+        field: String,
+        op: CompareOp,
+        value: String,
+    },
+}
+
+impl HideCondition {
+    pub fn parse(condition: &str) -> Option<Self> {
+        let condition = condition.trim();
+        match condition {
+            "primary_empty" => return Some(HideCondition::PrimaryEmpty),
+            "secondary_empty" => return Some(HideCondition::SecondaryEmpty),
+            _ => {}
+        }
+
+        let (index, symbol, op) = CompareOp::ALL
+            .iter()
+            .filter_map(|(symbol, op)| condition.find(symbol).map(|index| (index, *symbol, *op)))
+            .min_by_key(|(index, symbol, _)| (*index, std::cmp::Reverse(symbol.len())))?;
+
+        let field = condition[..index].trim();
+        let value = condition[index + symbol.len()..].trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        if field.is_empty() {
+            return None;
+        }
+
+        Some(HideCondition::Compare {
+            field: field.to_string(),
+            op,
+            value: value.to_string(),
+        })
+    }
+
+    pub fn matches(&self, data: &SegmentData) -> bool {
+        match self {
+            HideCondition::PrimaryEmpty => data.primary.is_empty(),
+            HideCondition::SecondaryEmpty => data.secondary.is_empty(),
+            HideCondition::Compare { field, op, value } => {
+                let actual = match field.as_str() {
+                    "primary" => Some(data.primary.as_str()),
+                    "secondary" => Some(data.secondary.as_str()),
+                    _ => field
+                        .strip_prefix("metadata.")
+                        .and_then(|key| data.metadata.get(key))
+                        .map(|v| v.as_str()),
+                };
+                let Some(actual) = actual else {
+                    return false;
+                };
+
+                let ordering = match (actual.trim().parse::<f64>(), value.parse::<f64>()) {
+                    (Ok(actual), Ok(expected)) => match actual.partial_cmp(&expected) {
+                        Some(ordering) => ordering,
+                        None => return false,
+                    },
+                    _ => actual.cmp(value.as_str()),
+                };
+                op.holds(ordering)
+            }
+        }
+    }
+}
+
+/// Whether any of the segment's `hide_when` conditions (a string or a list of
+/// strings) holds for its data; unparsable conditions are ignored
+pub fn is_hidden(config: &SegmentConfig, data: &SegmentData) -> bool {
+    let Some(value) = config.options.get("hide_when") else {
+        return false;
+    };
+    let conditions: Vec<&str> = match value {
+        serde_json::Value::String(condition) => vec![condition.as_str()],
+        serde_json::Value::Array(items) => items.iter().filter_map(|v| v.as_str()).collect(),
+        _ => return false,
+    };
+
+    conditions
+        .into_iter()
+        .filter_map(HideCondition::parse)
+        .any(|condition| condition.matches(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SegmentId;
+    use crate::test_support::{segment_config, segment_data};
+
+    fn with_metadata(key: &str, value: &str) -> SegmentData {
+        let mut data = segment_data("main", "");
+        data.metadata.insert(key.to_string(), value.to_string());
+        data
+    }
+
+    fn hidden(hide_when: serde_json::Value, data: &SegmentData) -> bool {
+        let mut config = segment_config(SegmentId::Git);
+        config.options.insert("hide_when".to_string(), hide_when);
+        is_hidden(&config, data)
+    }
+
+    #[test]
+    fn parses_the_grammar() {
+        assert_eq!(
+            HideCondition::parse(" primary_empty "),
+            Some(HideCondition::PrimaryEmpty)
+        );
+        assert_eq!(
+            HideCondition::parse("metadata.cost <= 0.01"),
+            Some(HideCondition::Compare {
+                field: "metadata.cost".to_string(),
+                op: CompareOp::Le,
+                value: "0.01".to_string(),
+            })
+        );
+        assert_eq!(
+            HideCondition::parse(r#"primary == "no git""#),
+            Some(HideCondition::Compare {
+                field: "primary".to_string(),
+                op: CompareOp::Eq,
+                value: "no git".to_string(),
+            })
+        );
+        assert_eq!(HideCondition::parse("== 3"), None);
+        assert_eq!(HideCondition::parse("cost"), None);
+    }
+
+    #[test]
+    fn numeric_conditions_compare_as_numbers() {
+        let cases = [
+            ("metadata.cost < 0.01", "cost", "0.005", true),
+            ("metadata.cost < 0.01", "cost", "0.01", false),
+            ("metadata.cost >= 10", "cost", "9.99", false),
+            ("metadata.cost >= 10", "cost", "10", true),
+            ("metadata.ahead == 0", "ahead", "0", true),
+            ("metadata.ahead != 0", "ahead", "0", false),
+            // "9" > "10" as text, but not as numbers
+            ("metadata.ahead > 10", "ahead", "9", false),
+        ];
+        for (condition, key, value, expected) in cases {
+            let data = with_metadata(key, value);
+            assert_eq!(
+                HideCondition::parse(condition).unwrap().matches(&data),
+                expected,
+                "{condition} with {value}"
+            );
+        }
+    }
+
+    #[test]
+    fn emptiness_conditions() {
+        assert!(hidden(
+            serde_json::json!("secondary_empty"),
+            &segment_data("a", "")
+        ));
+        assert!(!hidden(
+            serde_json::json!("secondary_empty"),
+            &segment_data("a", "b")
+        ));
+        assert!(hidden(
+            serde_json::json!("primary_empty"),
+            &segment_data("", "b")
+        ));
+        assert!(!hidden(
+            serde_json::json!("primary_empty"),
+            &segment_data("a", "")
+        ));
+    }
+
+    #[test]
+    fn any_listed_condition_hides_and_missing_fields_never_match() {
+        let data = with_metadata("behind", "0");
+        assert!(hidden(
+            serde_json::json!(["metadata.ahead > 0", "metadata.behind == 0"]),
+            &data
+        ));
+        assert!(!hidden(serde_json::json!("metadata.ahead == 0"), &data));
+        assert!(!hidden(serde_json::json!("not a condition"), &data));
+        assert!(!hidden(serde_json::json!(42), &data));
+    }
+}
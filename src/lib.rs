@@ -4,5 +4,8 @@ pub mod core;
 pub mod ui;
 pub mod utils;
 
+#[cfg(test)]
+mod test_support;
+
 #[cfg(feature = "self-update")]
 pub mod updater;
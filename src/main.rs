@@ -1,33 +1,132 @@
 use ccometixline::cli::Cli;
-use ccometixline::config::{Config, InputData};
-use ccometixline::core::{collect_all_segments, StatusLineGenerator};
-use std::io::{self, IsTerminal};
+use ccometixline::config::{Config, InputData, SegmentConfig, SegmentId};
+use ccometixline::core::output::{
+    render_json, render_segment_json, render_segment_report, OutputFormat,
+};
+use ccometixline::core::recording::{record, Replay};
+use ccometixline::core::segments::{NewApiCostSegment, SegmentData};
+use ccometixline::core::statusline::{collect_configured, segment_name};
+use ccometixline::core::throttle::RenderThrottle;
+use ccometixline::core::{collect_all_segments, SegmentCollector, StatusLineGenerator};
+use ccometixline::ui::themes::{theme_default, ThemePresets};
+use ccometixline::utils::errors::{self, ErrorCode, ReportedError};
+use ccometixline::utils::write_atomic;
+use std::io::{self, IsTerminal, Read, Write};
+use std::time::{Duration, Instant};
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() {
+    ccometixline::utils::log::start_clock();
     let cli = Cli::parse_args();
+    errors::set_json_errors(cli.json_errors);
+    if let Some(path) = &cli.log_file {
+        ccometixline::utils::log::set_log_file(path);
+    }
+
+    if let Err(error) = run(cli) {
+        errors::report_fatal(&ReportedError::from_boxed(error));
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    // Apply config path override before any config operation
+    if let Some(config_path) = &cli.config_path {
+        Config::set_config_path(config_path);
+    }
 
     // Handle configuration commands
     if cli.init {
-        Config::init()?;
+        Config::init_at(Config::get_config_path(), cli.minify, cli.force)?;
         return Ok(());
     }
 
     if cli.print {
-        let mut config = Config::load().unwrap_or_else(|_| Config::default());
+        let config = load_config(&cli);
+
+        // Apply theme override (--theme or CCLINE_THEME)
+        let mut config = ThemePresets::apply_theme_override(config, cli.theme.as_deref())?;
+        apply_order(&cli, &mut config)?;
+
+        config.print(cli.minify, cli.sort_keys)?;
+        return Ok(());
+    }
+
+    if cli.schema {
+        let schema = ccometixline::config::schema::config_schema();
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        return Ok(());
+    }
+
+    if cli.print_input_schema {
+        let example = ccometixline::config::schema::input_example()?;
+        println!("{}", serde_json::to_string_pretty(&example)?);
+        return Ok(());
+    }
 
-        // Apply theme override if provided
-        if let Some(theme) = cli.theme {
-            config = ccometixline::ui::themes::ThemePresets::get_theme(&theme);
+    if let Some(path) = &cli.init_example_input {
+        let example = ccometixline::config::schema::sample_input(&std::env::current_dir()?)?;
+        write_atomic(
+            path,
+            format!("{}\n", serde_json::to_string_pretty(&example)?).as_bytes(),
+        )?;
+        println!(
+            "Wrote example input to {} (try: ccline < {})",
+            Config::display_path(path),
+            path.display()
+        );
+        return Ok(());
+    }
+
+    if cli.doctor {
+        if cli.no_config {
+            println!("Using config: none (--no-config)");
+        } else {
+            println!("{}", Config::config_report());
+        }
+        return Ok(());
+    }
+
+    if cli.cache_status || cli.cache_clear {
+        // Loading the config applies its `cache_dir`
+        load_config(&cli);
+        let dir = ccometixline::utils::cache::cache_dir().ok_or("no cache directory")?;
+        if cli.cache_clear {
+            let removed = ccometixline::utils::cache::clear_cache(&dir)?;
+            println!("Removed {} cache files from {}", removed, dir.display());
+        } else {
+            println!("{}", ccometixline::utils::cache::cache_status(&dir)?);
         }
+        return Ok(());
+    }
+
+    if cli.color_test {
+        let config = load_config(&cli);
 
-        config.print()?;
+        // Apply theme override (--theme or CCLINE_THEME)
+        let config = ThemePresets::apply_theme_override(config, cli.theme.as_deref())?;
+
+        let use_color = !cli.no_color && std::env::var_os("NO_COLOR").is_none();
+        println!(
+            "{}",
+            ccometixline::core::palette::render_color_test(&config, use_color)
+        );
         return Ok(());
     }
 
     if cli.check {
-        let config = Config::load()?;
-        config.check()?;
-        println!("✓ Configuration valid");
+        let config_path = Config::get_config_path();
+        let config = Config::load_from(&config_path)
+            .map_err(|e| ReportedError::new(ErrorCode::ConfigLoad, e.to_string()))?;
+        config
+            .check()
+            .map_err(|e| ReportedError::new(ErrorCode::ConfigInvalid, e.to_string()))?;
+        for warning in config.warnings() {
+            eprintln!("⚠️ {}", warning);
+        }
+        println!(
+            "✓ Configuration valid ({})",
+            Config::display_path(&config_path)
+        );
         return Ok(());
     }
 
@@ -63,14 +162,40 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("🔧 Claude Code Context Warning Disabler");
         println!("Target file: {}", claude_path);
 
+        if cli.dry_run {
+            // Same modifications on an in-memory copy: no lock, backup or write
+            let mut patcher = ClaudeCodePatcher::preview(&claude_path)?;
+            println!("\n🔍 Dry run, nothing will be written");
+
+            let results = [
+                ("Verbose property", patcher.write_verbose_property(true)),
+                (
+                    "Context low warnings",
+                    patcher.disable_context_low_warnings(),
+                ),
+                (
+                    "ESC interrupt display",
+                    patcher.disable_esc_interrupt_display(),
+                ),
+            ];
+            println!();
+            for (name, result) in results {
+                match result {
+                    Ok(()) => println!("✅ {}: found, would be patched", name),
+                    Err(e) => println!("❌ {}: not found ({})", name, e),
+                }
+            }
+            return Ok(());
+        }
+
+        // Lock and load first, so the backup can't race another patch run
+        let mut patcher = ClaudeCodePatcher::new(&claude_path)?;
+
         // Create backup in same directory
         let backup_path = format!("{}.backup", claude_path);
         std::fs::copy(&claude_path, &backup_path)?;
         println!("📦 Created backup: {}", backup_path);
 
-        // Load and patch
-        let mut patcher = ClaudeCodePatcher::new(&claude_path)?;
-
         // Apply all modifications
         println!("\n🔄 Applying patches...");
 
@@ -97,12 +222,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Load configuration
-    let mut config = Config::load().unwrap_or_else(|_| Config::default());
+    let config = load_config(&cli);
 
-    // Apply theme override if provided
-    if let Some(theme) = cli.theme {
-        config = ccometixline::ui::themes::ThemePresets::get_theme(&theme);
-    }
+    // Apply theme override (--theme or CCLINE_THEME)
+    let mut config = ThemePresets::apply_theme_override(config, cli.theme.as_deref())?;
+    apply_order(&cli, &mut config)?;
 
     // Apply NewApi Cost CLI parameter overrides
     if cli.newapi_base_url.is_some()
@@ -112,46 +236,99 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         || cli.newapi_provider.is_some()
         || cli.newapi_quota_per_unit.is_some()
     {
-        if let Some(segment_config) = config
+        let segment_index = match config
             .segments
-            .iter_mut()
-            .find(|s| s.id == ccometixline::config::SegmentId::NewApiCost)
+            .iter()
+            .position(|s| s.id == SegmentId::NewApiCost)
         {
-            if let Some(base_url) = &cli.newapi_base_url {
-                segment_config
-                    .options
-                    .insert("base_url".to_string(), serde_json::json!(base_url));
-            }
-            if let Some(user_token) = &cli.newapi_user_token {
-                segment_config
-                    .options
-                    .insert("user_token".to_string(), serde_json::json!(user_token));
-            }
-            if let Some(user_id) = &cli.newapi_user_id {
-                segment_config
-                    .options
-                    .insert("user_id".to_string(), serde_json::json!(user_id));
-            }
-            if let Some(token_name) = &cli.newapi_token_name {
-                segment_config
-                    .options
-                    .insert("token_name".to_string(), serde_json::json!(token_name));
-            }
-            if let Some(provider) = &cli.newapi_provider {
-                segment_config
-                    .options
-                    .insert("provider".to_string(), serde_json::json!(provider));
-            }
-            if let Some(quota_per_unit) = cli.newapi_quota_per_unit {
-                segment_config
-                    .options
-                    .insert("quota_per_unit".to_string(), serde_json::json!(quota_per_unit));
+            Some(index) => index,
+            None => {
+                // The flags alone are enough: add the segment with the theme's look
+                let segment = ThemePresets::get_theme(&config.theme)
+                    .segments
+                    .into_iter()
+                    .find(|s| s.id == SegmentId::NewApiCost)
+                    .unwrap_or_else(theme_default::newapi_cost_segment);
+                config.segments.push(segment);
+                config.segments.len() - 1
             }
+        };
+        let segment_config = &mut config.segments[segment_index];
+        // Passing the flags asks for the segment, even where the config disables it
+        segment_config.enabled = true;
+
+        if let Some(base_url) = &cli.newapi_base_url {
+            segment_config
+                .options
+                .insert("base_url".to_string(), serde_json::json!(base_url));
+        }
+        if let Some(user_token) = &cli.newapi_user_token {
+            segment_config
+                .options
+                .insert("user_token".to_string(), serde_json::json!(user_token));
+        }
+        if let Some(user_id) = &cli.newapi_user_id {
+            segment_config
+                .options
+                .insert("user_id".to_string(), serde_json::json!(user_id));
+        }
+        if let Some(token_name) = &cli.newapi_token_name {
+            segment_config
+                .options
+                .insert("token_name".to_string(), serde_json::json!(token_name));
+        }
+        if let Some(provider) = &cli.newapi_provider {
+            segment_config
+                .options
+                .insert("provider".to_string(), serde_json::json!(provider));
+        }
+        if let Some(quota_per_unit) = cli.newapi_quota_per_unit {
+            segment_config.options.insert(
+                "quota_per_unit".to_string(),
+                serde_json::json!(quota_per_unit),
+            );
+        }
+    }
+
+    if let Some(dir) = &cli.replay {
+        let replay = Replay::load(dir)?;
+        let output = emit_statusline(&cli, &replay.config, replay.collect())?;
+        if Replay::recorded_output(dir).is_ok_and(|recorded| recorded != output) {
+            eprintln!("ccline: replayed output differs from the recording");
+        }
+        return Ok(());
+    }
+
+    if cli.ping {
+        let healthy = ping(&cli, &config);
+        std::process::exit(if healthy { 0 } else { 1 });
+    }
+
+    if let Some(iterations) = cli.bench_network {
+        bench_network(&config, iterations.max(1))?;
+        return Ok(());
+    }
+
+    if cli.checkpoint_cost {
+        let segment = configured_newapi_segment(&config)?;
+        let cost = segment
+            .set_checkpoint()
+            .ok_or("NewApi cost request failed; no checkpoint recorded")?;
+        println!("Checkpoint set at {}", segment.format_cost(cost));
+        return Ok(());
+    }
+
+    if cli.clear_checkpoint {
+        if NewApiCostSegment::clear_checkpoint() {
+            println!("Checkpoint cleared");
+        } else {
+            println!("No checkpoint set");
         }
+        return Ok(());
     }
 
-    // Check if stdin has data
-    if io::stdin().is_terminal() {
+    // Check if stdin has data (--dry-run renders the sample input instead)
+    if !cli.dry_run && io::stdin().is_terminal() {
         // No input data available, show main menu
         #[cfg(feature = "tui")]
         {
@@ -169,6 +346,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     MenuResult::CheckConfig => {
                         let config = ccometixline::config::Config::load()?;
                         config.check()?;
+                        for warning in config.warnings() {
+                            eprintln!("⚠️ {}", warning);
+                        }
                         println!("Configuration is valid!");
                     }
                     MenuResult::Exit => {
@@ -186,18 +366,347 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
-    // Read Claude Code data from stdin
-    let stdin = io::stdin();
-    let input: InputData = serde_json::from_reader(stdin.lock())?;
+    // Read Claude Code data from stdin, or use the --init-example-input sample with --dry-run
+    let content = if cli.dry_run {
+        let sample = ccometixline::config::schema::sample_input(&std::env::current_dir()?)?;
+        serde_json::to_string_pretty(&sample)?
+    } else {
+        let mut content = String::new();
+        io::stdin().lock().read_to_string(&mut content)?;
+        content
+    };
+    let input = InputData::parse(&content, cli.strict_input)
+        .map_err(|e| ReportedError::new(ErrorCode::InputParse, e.to_string()))?;
+    #[cfg(feature = "tui")]
+    if !cli.dry_run && config.preview_real_input == Some(true) {
+        InputData::record_last_seen(&content);
+    }
+
+    if let Some(name) = &cli.segment_json {
+        let segment_config =
+            find_segment_config(&config, name).ok_or_else(|| unknown_segment(name))?;
+        let data = collect_configured(&segment_config, &input);
+        println!("{}", render_segment_json(&config, data.as_ref()));
+        return Ok(());
+    }
+
+    if let Some(name) = &cli.validate_segment {
+        let segment_config =
+            find_segment_config(&config, name).ok_or_else(|| unknown_segment(name))?;
+        let from_config = config
+            .segments
+            .iter()
+            .any(|segment| segment.key() == segment_config.key());
+        let started = Instant::now();
+        let data = collect_configured(&segment_config, &input);
+        println!(
+            "{}",
+            render_segment_report(
+                &config,
+                &segment_config,
+                from_config,
+                data.as_ref(),
+                started.elapsed()
+            )
+        );
+        if data.is_none() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Long-lived mode: re-render periodically, re-collecting segments as they go stale
+    if let Some(interval_secs) = cli.watch {
+        let mut collector = SegmentCollector::new().with_background_refresh();
+        loop {
+            let segments_data = collector.collect(&config, &input);
+            emit_statusline(&cli, &config, segments_data)?;
+            std::thread::sleep(Duration::from_secs(interval_secs.max(1)));
+        }
+    }
+
+    // Rapid redraws of the same session reuse the previous line; the effective
+    // config (after --theme) and output flags are part of what must match
+    let throttle = match (&cli.record, config.min_render_interval_ms) {
+        (None, Some(_)) => {
+            // Through a JSON value, whose maps are sorted, so segment options
+            // serialize the same in every run
+            let config_key = serde_json::to_value(&config)?.to_string();
+            let flags = format!(
+                "{:?} {} {} {}",
+                cli.format,
+                cli.no_color || std::env::var_os("NO_COLOR").is_some(),
+                cli.no_newline,
+                cli.render_to.is_some()
+            );
+            RenderThrottle::new(
+                config.min_render_interval_ms,
+                &[
+                    &config_key,
+                    &flags,
+                    &input.transcript_path,
+                    &input.workspace.current_dir,
+                    &input.model.id,
+                ],
+            )
+        }
+        _ => None,
+    };
+    if let Some(output) = throttle.as_ref().and_then(RenderThrottle::cached) {
+        write_output(&cli, &output)?;
+        return Ok(());
+    }
 
     // Collect segment data
     let segments_data = collect_all_segments(&config, &input);
+    match &cli.record {
+        Some(dir) => {
+            let output = emit_statusline(&cli, &config, segments_data.clone())?;
+            record(dir, &content, &config, &segments_data, &output)?;
+        }
+        None => {
+            let output = emit_statusline(&cli, &config, segments_data)?;
+            if let Some(throttle) = &throttle {
+                throttle.store(&output);
+            }
+        }
+    }
 
-    // Render statusline
-    let generator = StatusLineGenerator::new(config);
-    let statusline = generator.generate(segments_data);
+    Ok(())
+}
+
+/// Segment config by name from the config, falling back to the theme's
+/// Underscores, dashes and case are ignored, so `newapi_cost` finds `new_api_cost`
+fn find_segment_config(config: &Config, name: &str) -> Option<SegmentConfig> {
+    let normalize = |name: &str| name.replace(['_', '-'], "").to_ascii_lowercase();
+    let wanted = normalize(name);
+    config
+        .segments
+        .iter()
+        .cloned()
+        .chain(ThemePresets::get_theme(&config.theme).segments)
+        .find(|segment| normalize(&segment_name(segment.id)) == wanted)
+}
 
-    println!("{}", statusline);
+fn unknown_segment(name: &str) -> ReportedError {
+    ReportedError::new(
+        ErrorCode::Internal,
+        format!(
+            "Segment `{}` is not configured in the current config or theme",
+            name
+        ),
+    )
+}
+
+/// Load the config file, or the built-in defaults with `--no-config`
+/// A config that fails to load falls back to the defaults too
+/// The config's `cache_dir` applies to every cache used afterwards
+fn load_config(cli: &Cli) -> Config {
+    let config = if cli.no_config {
+        Config::default()
+    } else {
+        Config::load().unwrap_or_else(|e| {
+            errors::report_recoverable(&ReportedError::new(ErrorCode::ConfigLoad, e.to_string()));
+            Config::default()
+        })
+    };
+    if let Some(dir) = &config.cache_dir {
+        ccometixline::utils::cache::set_cache_dir(dir);
+    }
+    config
+}
+
+/// Reorder segments as given by `--order`
+fn apply_order(cli: &Cli, config: &mut Config) -> Result<(), ReportedError> {
+    config
+        .reorder_segments(&cli.order)
+        .map_err(|e| ReportedError::new(ErrorCode::ConfigInvalid, e))
+}
+
+/// Overall time budget for the network part of `--ping`
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Check that config loads, enabled network segments respond and a line renders,
+/// printing a JSON report; returns whether every check passed
+fn ping(cli: &Cli, config: &Config) -> bool {
+    let started = Instant::now();
+    let mut checks = Vec::new();
+    let mut check = |name: &str, result: Result<(), String>| {
+        checks.push(serde_json::json!({
+            "name": name,
+            "ok": result.is_ok(),
+            "error": result.err(),
+        }));
+    };
+
+    let config_result = if cli.no_config {
+        Ok(())
+    } else {
+        Config::load().map(|_| ()).map_err(|e| e.to_string())
+    };
+    check("config", config_result);
+
+    let newapi = config
+        .segments
+        .iter()
+        .find(|s| s.enabled && s.id == SegmentId::NewApiCost)
+        .map(|s| s.options.clone());
+    if let Some(options) = newapi {
+        // Run the request on a thread so a hanging connection can't exceed the budget
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let segment = NewApiCostSegment::new().with_config_from_options(&options);
+            let _ = tx.send(segment.fetch_cost_once());
+        });
+        let result = match rx.recv_timeout(PING_TIMEOUT) {
+            Ok(Some(_)) => Ok(()),
+            Ok(None) => Err("request failed".to_string()),
+            Err(_) => Err(format!("no response within {}s", PING_TIMEOUT.as_secs())),
+        };
+        check("new_api_cost", result);
+    }
+
+    let dummy_segments: Vec<(SegmentConfig, SegmentData)> = config
+        .segments
+        .iter()
+        .filter(|s| s.enabled)
+        .map(|s| {
+            let data = SegmentData {
+                primary: "ok".to_string(),
+                secondary: String::new(),
+                metadata: Default::default(),
+            };
+            (s.clone(), data)
+        })
+        .collect();
+    let line = StatusLineGenerator::new(config.clone()).generate(dummy_segments);
+    check(
+        "render",
+        if line.is_empty() {
+            Err("nothing rendered".to_string())
+        } else {
+            Ok(())
+        },
+    );
+
+    let healthy = checks.iter().all(|c| c["ok"] == serde_json::json!(true));
+    let config_path = (!cli.no_config).then(|| Config::display_path(&Config::get_config_path()));
+    let report = serde_json::json!({
+        "ok": healthy,
+        "config_path": config_path,
+        "elapsed_ms": started.elapsed().as_millis() as u64,
+        "checks": checks,
+    });
+    println!("{}", report);
+
+    healthy
+}
+
+/// The configured NewApi cost segment, provided it has credentials
+fn configured_newapi_segment(
+    config: &Config,
+) -> Result<NewApiCostSegment, Box<dyn std::error::Error>> {
+    let segment_config = config
+        .segments
+        .iter()
+        .find(|s| s.id == SegmentId::NewApiCost)
+        .ok_or("No new_api_cost segment configured")?;
+    let segment = NewApiCostSegment::new().with_config_from_options(&segment_config.options);
+    if segment.base_url.is_none() || segment.user_token.is_none() || segment.user_id.is_none() {
+        return Err("new_api_cost needs base_url, user_token and user_id".into());
+    }
+    Ok(segment)
+}
+
+/// Time the NewApi cost request `iterations` times using the configured credentials
+fn bench_network(config: &Config, iterations: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let segment = configured_newapi_segment(config)?;
+
+    let mut latencies = Vec::new();
+    let mut failures = 0u32;
+    for i in 1..=iterations {
+        let started = Instant::now();
+        let result = segment.fetch_cost_once();
+        let elapsed = started.elapsed();
+        latencies.push(elapsed);
+        if result.is_none() {
+            failures += 1;
+        }
+        println!(
+            "#{:<3} {:>6}ms {}",
+            i,
+            elapsed.as_millis(),
+            if result.is_some() { "ok" } else { "failed" }
+        );
+    }
+
+    latencies.sort();
+    let ms = |d: Duration| d.as_millis();
+    println!(
+        "{} requests: min {}ms, median {}ms, max {}ms, {:.0}% failed",
+        iterations,
+        ms(latencies[0]),
+        ms(latencies[latencies.len() / 2]),
+        ms(latencies[latencies.len() - 1]),
+        failures as f64 * 100.0 / iterations as f64
+    );
 
     Ok(())
 }
+
+/// Render collected segments in the requested format and write them to stdout or `--render-to`
+/// Returns the bytes written
+fn emit_statusline(
+    cli: &Cli,
+    config: &Config,
+    segments_data: Vec<(SegmentConfig, SegmentData)>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let statusline = match cli.format {
+        OutputFormat::Json => render_json(config, segments_data),
+        OutputFormat::Text => {
+            let plain = cli.no_color || std::env::var_os("NO_COLOR").is_some();
+            let generator = StatusLineGenerator::new(config.clone()).with_color(!plain);
+            // The title escape only makes sense for colored terminal output
+            let title = if plain || cli.render_to.is_some() {
+                None
+            } else {
+                generator.generate_title(&segments_data)
+            };
+            let line = generator.generate(segments_data);
+            if line.is_empty() && config.quiet_on_empty.unwrap_or(false) {
+                write_output(cli, "")?;
+                return Ok(String::new());
+            }
+            format!("{}{}", title.unwrap_or_default(), line)
+        }
+    };
+
+    let newline = !(cli.no_newline || config.no_newline.unwrap_or(false));
+    let output = if newline {
+        format!("{}\n", statusline)
+    } else {
+        statusline
+    };
+
+    write_output(cli, &output)?;
+    Ok(output)
+}
+
+/// Write a rendered statusline to `--render-to` or stdout
+fn write_output(cli: &Cli, output: &str) -> io::Result<()> {
+    match &cli.render_to {
+        Some(path) => write_atomic(path, output.as_bytes()),
+        None => write_stdout(output.as_bytes()),
+    }
+}
+
+/// Write and flush the final output; if the reader already went away
+/// (`BrokenPipe`) nobody is left to show anything to, so exit quietly
+/// with success, which also ends `--watch`
+fn write_stdout(bytes: &[u8]) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    match stdout.write_all(bytes).and_then(|_| stdout.flush()) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(0),
+        result => result,
+    }
+}
@@ -0,0 +1,175 @@
+//! Helpers shared by unit tests: temporary directories, guarded environment
+//! changes, an isolated cache directory and a mock HTTP server.
+
+pub mod mock_http;
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Directory under the system temp dir, removed with everything in it on drop
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(prefix: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "ccline-test-{}-{}-{}",
+            prefix,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("create temp dir");
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.path.join(path)
+    }
+
+    /// Write a file below the directory, creating parent directories
+    pub fn write<P: AsRef<Path>>(&self, path: P, contents: &str) -> PathBuf {
+        let path = self.path.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("create parent dir");
+        }
+        std::fs::write(&path, contents).expect("write fixture");
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Serializes tests that change process-wide state (environment, cwd)
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+/// Environment changes that are undone on drop; holds the process-wide lock
+/// so tests touching the environment never interleave
+pub struct EnvGuard {
+    saved: Vec<(String, Option<OsString>)>,
+    saved_dir: Option<PathBuf>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl EnvGuard {
+    pub fn new() -> Self {
+        Self {
+            saved: Vec::new(),
+            saved_dir: None,
+            _lock: ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+
+    fn remember(&mut self, name: &str) {
+        if !self.saved.iter().any(|(saved, _)| saved == name) {
+            self.saved.push((name.to_string(), std::env::var_os(name)));
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: impl AsRef<std::ffi::OsStr>) -> &mut Self {
+        self.remember(name);
+        std::env::set_var(name, value);
+        self
+    }
+
+    pub fn remove(&mut self, name: &str) -> &mut Self {
+        self.remember(name);
+        std::env::remove_var(name);
+        self
+    }
+
+    /// Change the working directory until the guard is dropped
+    pub fn set_current_dir(&mut self, dir: &Path) -> &mut Self {
+        if self.saved_dir.is_none() {
+            self.saved_dir = std::env::current_dir().ok();
+        }
+        std::env::set_current_dir(dir).expect("change working dir");
+        self
+    }
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        for (name, value) in self.saved.drain(..).rev() {
+            match value {
+                Some(value) => std::env::set_var(&name, value),
+                None => std::env::remove_var(&name),
+            }
+        }
+        if let Some(dir) = self.saved_dir.take() {
+            let _ = std::env::set_current_dir(dir);
+        }
+    }
+}
+
+/// Point every `Cache` used by the current test thread at a fresh directory
+pub fn isolated_cache() -> TempDir {
+    let dir = TempDir::new("cache");
+    crate::utils::cache::TEST_CACHE_DIR.with(|cell| *cell.borrow_mut() = Some(dir.path().into()));
+    dir
+}
+
+/// Minimal stdin input with `fields` merged over the top level
+pub fn input_data(fields: serde_json::Value) -> crate::config::InputData {
+    let mut input = serde_json::json!({
+        "model": {"id": "claude-sonnet-4", "display_name": "Sonnet 4"},
+        "workspace": {"current_dir": std::env::temp_dir()},
+        "transcript_path": "",
+    });
+    if let (Some(input), serde_json::Value::Object(fields)) = (input.as_object_mut(), fields) {
+        input.extend(fields);
+    }
+    serde_json::from_value(input).expect("valid input data")
+}
+
+/// Enabled segment config with a plain `*` icon and no colors or options
+pub fn segment_config(id: crate::config::SegmentId) -> crate::config::SegmentConfig {
+    use crate::config::{ColorConfig, IconConfig, SegmentConfig, TextStyleConfig};
+
+    SegmentConfig {
+        id,
+        enabled: true,
+        icon: IconConfig {
+            plain: "*".to_string(),
+            nerd_font: "*".to_string(),
+        },
+        colors: ColorConfig {
+            icon: None,
+            text: None,
+            background: None,
+        },
+        styles: TextStyleConfig::default(),
+        options: std::collections::HashMap::new(),
+        group: None,
+        name: None,
+    }
+}
+
+/// Segment data with the given texts and no metadata
+pub fn segment_data(primary: &str, secondary: &str) -> crate::core::segments::SegmentData {
+    crate::core::segments::SegmentData {
+        primary: primary.to_string(),
+        secondary: secondary.to_string(),
+        metadata: std::collections::HashMap::new(),
+    }
+}
+
+/// Text with ANSI SGR escapes removed
+pub fn strip_ansi(text: &str) -> String {
+    regex::Regex::new("\x1b\\[[0-9;]*m")
+        .unwrap()
+        .replace_all(text, "")
+        .into_owned()
+}
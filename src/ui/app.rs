@@ -10,6 +10,7 @@ use crate::ui::components::{
     settings::SettingsComponent,
     theme_selector::ThemeSelectorComponent,
 };
+use crate::utils::display_width;
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
     execute,
@@ -73,6 +74,10 @@ impl App {
 
         // Load config
         let mut config = Config::load().unwrap_or_else(|_| Config::default());
+        // The preview reads the last rendered input from the cache directory
+        if let Some(dir) = &config.cache_dir {
+            crate::utils::cache::set_cache_dir(dir);
+        }
 
         // If a theme is specified, reload it to get the latest changes
         if !config.theme.is_empty() && config.theme != "default" {
@@ -218,6 +223,7 @@ impl App {
                             }
                         }
                         KeyCode::Enter => app.toggle_current(),
+                        KeyCode::Char(' ') => app.toggle_segment_enabled(),
                         KeyCode::Tab => app.switch_panel(),
                         KeyCode::Char('1') => app.switch_to_theme("default"),
                         KeyCode::Char('2') => app.switch_to_theme("minimal"),
@@ -302,6 +308,7 @@ impl App {
             vec![
                 "[Tab] Switch Panel",
                 "[Enter] Toggle/Edit",
+                "[Space] Enable/Disable",
                 "[Shift+↑↓] Reorder",
                 "[1-4] Theme",
                 "[P] Switch Theme",
@@ -320,7 +327,7 @@ impl App {
 
         // Use same logic as help.render for line wrapping
         for (i, item) in help_items.iter().enumerate() {
-            let item_width = item.chars().count();
+            let item_width = display_width(item);
             let needs_separator = i > 0 && current_width > 0;
             let separator_width = if needs_separator { 2 } else { 0 };
             let total_width = item_width + separator_width;
@@ -487,60 +494,52 @@ impl App {
         }
     }
 
+    /// Flip the selected segment's enabled flag
+    fn toggle_segment_enabled(&mut self) {
+        if let Some(segment) = self.config.segments.get_mut(self.selected_segment) {
+            segment.enabled = !segment.enabled;
+            let segment_name = match segment.id {
+                SegmentId::Model => "Model",
+                SegmentId::Directory => "Directory",
+                SegmentId::Git => "Git",
+                SegmentId::ContextWindow => "Context Window",
+                SegmentId::Usage => "Usage",
+                SegmentId::Cost => "Cost",
+                SegmentId::NewApiCost => "NewApi Cost",
+                SegmentId::Session => "Session",
+                SegmentId::OutputStyle => "Output Style",
+                SegmentId::Update => "Update",
+                SegmentId::RuntimeVersion => "Runtime Version",
+                SegmentId::Notify => "Notify",
+                SegmentId::Secrets => "Secrets",
+                SegmentId::Host => "Host",
+                SegmentId::Tmux => "Tmux",
+                SegmentId::ExitCode => "Exit Code",
+                SegmentId::Plan => "Plan",
+                SegmentId::Custom => "Custom",
+            };
+            let is_enabled = segment.enabled;
+            self.status_message = Some(format!(
+                "{} segment {}",
+                segment_name,
+                if is_enabled { "enabled" } else { "disabled" }
+            ));
+            self.preview.update_preview(&self.config);
+        }
+    }
+
     fn toggle_current(&mut self) {
         match self.selected_panel {
             Panel::SegmentList => {
                 // Toggle segment enabled/disabled in segment list
-                if let Some(segment) = self.config.segments.get_mut(self.selected_segment) {
-                    segment.enabled = !segment.enabled;
-                    let segment_name = match segment.id {
-                        SegmentId::Model => "Model",
-                        SegmentId::Directory => "Directory",
-                        SegmentId::Git => "Git",
-                        SegmentId::ContextWindow => "Context Window",
-                        SegmentId::Usage => "Usage",
-                        SegmentId::Cost => "Cost",
-                        SegmentId::NewApiCost => "NewApi Cost",
-                        SegmentId::Session => "Session",
-                        SegmentId::OutputStyle => "Output Style",
-                        SegmentId::Update => "Update",
-                    };
-                    let is_enabled = segment.enabled;
-                    self.status_message = Some(format!(
-                        "{} segment {}",
-                        segment_name,
-                        if is_enabled { "enabled" } else { "disabled" }
-                    ));
-                    self.preview.update_preview(&self.config);
-                }
+                self.toggle_segment_enabled();
             }
             Panel::Settings => {
                 // Edit field in settings panel
                 match self.selected_field {
                     FieldSelection::Enabled => {
                         // Toggle enabled state in settings panel too
-                        if let Some(segment) = self.config.segments.get_mut(self.selected_segment) {
-                            segment.enabled = !segment.enabled;
-                            let segment_name = match segment.id {
-                                SegmentId::Model => "Model",
-                                SegmentId::Directory => "Directory",
-                                SegmentId::Git => "Git",
-                                SegmentId::ContextWindow => "Context Window",
-                                SegmentId::Usage => "Usage",
-                                SegmentId::Cost => "Cost",
-                                SegmentId::NewApiCost => "NewApi Cost",
-                                SegmentId::Session => "Session",
-                                SegmentId::OutputStyle => "Output Style",
-                                SegmentId::Update => "Update",
-                            };
-                            let is_enabled = segment.enabled;
-                            self.status_message = Some(format!(
-                                "{} segment {}",
-                                segment_name,
-                                if is_enabled { "enabled" } else { "disabled" }
-                            ));
-                            self.preview.update_preview(&self.config);
-                        }
+                        self.toggle_segment_enabled();
                     }
                     FieldSelection::Icon => self.open_icon_selector(),
                     FieldSelection::IconColor
@@ -650,25 +649,23 @@ impl App {
 
     /// Move the currently selected segment up in the list
     fn move_segment_up(&mut self) {
-        if self.selected_panel == Panel::SegmentList && self.selected_segment > 0 {
-            let current_idx = self.selected_segment;
-            self.config.segments.swap(current_idx, current_idx - 1);
-            self.selected_segment -= 1;
-            self.preview.update_preview(&self.config);
-            self.status_message = Some("Moved segment up".to_string());
-        }
+        self.move_segment(-1, "Moved segment up");
     }
 
     /// Move the currently selected segment down in the list
     fn move_segment_down(&mut self) {
-        if self.selected_panel == Panel::SegmentList
-            && self.selected_segment < self.config.segments.len() - 1
-        {
-            let current_idx = self.selected_segment;
-            self.config.segments.swap(current_idx, current_idx + 1);
-            self.selected_segment += 1;
+        self.move_segment(1, "Moved segment down");
+    }
+
+    /// Shift the selected segment by `offset`, keeping it selected
+    fn move_segment(&mut self, offset: isize, message: &str) {
+        if self.selected_panel != Panel::SegmentList {
+            return;
+        }
+        if let Some(new_idx) = move_item(&mut self.config.segments, self.selected_segment, offset) {
+            self.selected_segment = new_idx;
             self.preview.update_preview(&self.config);
-            self.status_message = Some("Moved segment down".to_string());
+            self.status_message = Some(message.to_string());
         }
     }
 
@@ -706,3 +703,68 @@ impl App {
         self.separator_editor.open(&self.config.style.separator);
     }
 }
+
+/// Swap the item at `index` with its neighbour `offset` places away
+/// Returns the item's new index, or None when either position is out of bounds
+fn move_item<T>(items: &mut [T], index: usize, offset: isize) -> Option<usize> {
+    let target = index.checked_add_signed(offset)?;
+    if index >= items.len() || target >= items.len() {
+        return None;
+    }
+    items.swap(index, target);
+    Some(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_item_swaps_with_neighbour() {
+        let mut items = vec!["model", "directory", "git"];
+
+        assert_eq!(move_item(&mut items, 2, -1), Some(1));
+        assert_eq!(items, ["model", "git", "directory"]);
+        assert_eq!(move_item(&mut items, 0, 1), Some(1));
+        assert_eq!(items, ["git", "model", "directory"]);
+    }
+
+    #[test]
+    fn move_item_stops_at_the_ends() {
+        let mut items = vec!["model", "git"];
+
+        assert_eq!(move_item(&mut items, 0, -1), None);
+        assert_eq!(move_item(&mut items, 1, 1), None);
+        assert_eq!(move_item(&mut items, 5, -1), None);
+        assert_eq!(move_item::<&str>(&mut [], 0, 1), None);
+        assert_eq!(items, ["model", "git"]);
+    }
+
+    #[test]
+    fn toggled_segments_persist_through_save_and_load() {
+        let mut env = crate::test_support::EnvGuard::new();
+        let home = crate::test_support::TempDir::new("home");
+        env.set("HOME", home.path());
+        let _cache = crate::test_support::isolated_cache();
+
+        let mut app = App::new(Config::default());
+        let before: Vec<bool> = app.config.segments.iter().map(|s| s.enabled).collect();
+        app.selected_segment = 1;
+        app.toggle_segment_enabled();
+        app.toggle_segment_enabled();
+        app.toggle_segment_enabled();
+
+        let path = home.join("config.toml");
+        app.config.save_to(&path).unwrap();
+        let saved: Vec<bool> = Config::load_from(&path)
+            .unwrap()
+            .segments
+            .iter()
+            .map(|s| s.enabled)
+            .collect();
+
+        let mut expected = before;
+        expected[1] = !expected[1];
+        assert_eq!(saved, expected);
+    }
+}
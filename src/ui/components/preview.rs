@@ -1,9 +1,9 @@
-use crate::config::{Config, SegmentId};
+use crate::config::{Config, InputData, SegmentConfig, SegmentId};
 use crate::core::segments::SegmentData;
 use crate::core::StatusLineGenerator;
 use ratatui::{
     layout::Rect,
-    text::{Line, Text},
+    text::Text,
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
@@ -12,6 +12,12 @@ use std::collections::HashMap;
 pub struct PreviewComponent {
     preview_cache: String,
     preview_text: Text<'static>,
+    /// Width inside the preview borders, which long rows wrap at
+    content_width: u16,
+    /// Input from the last real statusline render, used for local segments
+    last_input: Option<InputData>,
+    /// Real segment data keyed by segment, with the options it was collected with
+    collected: HashMap<String, (String, Option<SegmentData>)>,
 }
 
 impl Default for PreviewComponent {
@@ -25,6 +31,9 @@ impl PreviewComponent {
         Self {
             preview_cache: String::new(),
             preview_text: Text::default(),
+            content_width: 78,
+            last_input: InputData::load_last_seen(),
+            collected: HashMap::new(),
         }
     }
 
@@ -33,8 +42,8 @@ impl PreviewComponent {
     }
 
     pub fn update_preview_with_width(&mut self, config: &Config, width: u16) {
-        // Generate mock segments data directly for preview
-        let segments_data = self.generate_mock_segments_data(config);
+        // Real data for local segments when input was seen, mock data otherwise
+        let segments_data = self.generate_segments_data(config);
 
         // Generate both string and TUI text versions
         let renderer = StatusLineGenerator::new(config.clone());
@@ -42,30 +51,20 @@ impl PreviewComponent {
         // Keep string version for compatibility (if needed elsewhere)
         self.preview_cache = renderer.generate(segments_data.clone());
 
-        // Generate TUI-optimized text with smart segment wrapping for preview display
-        // Use actual available width minus borders
-        let content_width = width.saturating_sub(2);
-        let preview_result = renderer.generate_for_tui_preview(segments_data, content_width);
-
-        // Convert to owned text by cloning the spans
-        let owned_lines: Vec<Line<'static>> = preview_result
-            .lines
-            .into_iter()
-            .map(|line| {
-                let owned_spans: Vec<ratatui::text::Span<'static>> = line
-                    .spans
-                    .into_iter()
-                    .map(|span| ratatui::text::Span::styled(span.content.to_string(), span.style))
-                    .collect();
-                Line::from(owned_spans)
-            })
-            .collect();
-
-        self.preview_text = Text::from(owned_lines);
+        // The same output as the real statusline, wrapped at the available width minus borders
+        self.content_width = width.saturating_sub(2).max(1);
+        self.preview_text = renderer.generate_for_tui_preview(segments_data);
     }
 
     pub fn calculate_height(&self) -> u16 {
-        let line_count = self.preview_text.lines.len().max(1);
+        // Rows wider than the pane take as many lines as they wrap onto
+        let line_count = self
+            .preview_text
+            .lines
+            .iter()
+            .map(|line| line.width().div_ceil(self.content_width as usize).max(1))
+            .sum::<usize>()
+            .max(1);
         // Min 3 (1 line + 2 borders), max 8 to prevent taking too much space
         ((line_count + 2).max(3) as u16).min(8)
     }
@@ -81,123 +80,221 @@ impl PreviewComponent {
         &self.preview_cache
     }
 
-    /// Generate mock segments data for preview display
-    /// This creates perfect preview data without depending on real environment
-    fn generate_mock_segments_data(
-        &self,
-        config: &Config,
-    ) -> Vec<(crate::config::SegmentConfig, SegmentData)> {
-        let mut segments_data = Vec::new();
-
-        for segment_config in &config.segments {
-            if !segment_config.enabled {
-                continue;
+    /// Segment data for the preview
+    /// Local segments are collected from the last seen input when available;
+    /// network segments always use mock data so the preview stays instant
+    fn generate_segments_data(&mut self, config: &Config) -> Vec<(SegmentConfig, SegmentData)> {
+        config
+            .segments
+            .iter()
+            .filter(|segment_config| segment_config.enabled)
+            .map(|segment_config| {
+                let data = self
+                    .collect_real(segment_config)
+                    .unwrap_or_else(|| Self::mock_segment_data(segment_config.id));
+                (segment_config.clone(), data)
+            })
+            .collect()
+    }
+
+    /// Collect a local segment from the last seen input
+    /// Results are reused until the segment's options change
+    fn collect_real(&mut self, segment_config: &SegmentConfig) -> Option<SegmentData> {
+        let is_network = crate::core::registry::create_segment(segment_config)
+            .is_some_and(|segment| segment.uses_network());
+        if is_network {
+            return None;
+        }
+        let input = self.last_input.as_ref()?;
+
+        // Round-trip through Value for a stable key order
+        let options = serde_json::to_value(&segment_config.options)
+            .map(|value| value.to_string())
+            .unwrap_or_default();
+        if let Some((cached_options, data)) = self.collected.get(&segment_config.key()) {
+            if *cached_options == options {
+                return data.clone();
             }
+        }
+
+        let data = crate::core::registry::create_segment(segment_config)
+            .and_then(|segment| segment.collect(input))
+            .filter(|data| !data.is_empty() && !data.is_error());
+        self.collected
+            .insert(segment_config.key(), (options, data.clone()));
+        data
+    }
 
-            let mock_data = match segment_config.id {
-                SegmentId::Model => SegmentData {
-                    primary: "Sonnet 4".to_string(),
-                    secondary: "".to_string(),
-                    metadata: {
-                        let mut map = HashMap::new();
-                        map.insert("model".to_string(), "claude-4-sonnet-20250512".to_string());
-                        map
-                    },
+    /// Mock data for a segment, independent of the real environment
+    fn mock_segment_data(id: SegmentId) -> SegmentData {
+        match id {
+            SegmentId::Model => SegmentData {
+                primary: "Sonnet 4".to_string(),
+                secondary: "".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("model".to_string(), "claude-4-sonnet-20250512".to_string());
+                    map
                 },
-                SegmentId::Directory => SegmentData {
-                    primary: "CCometixLine".to_string(),
-                    secondary: "".to_string(),
-                    metadata: {
-                        let mut map = HashMap::new();
-                        map.insert("current_dir".to_string(), "~/CCometixLine".to_string());
-                        map
-                    },
+            },
+            SegmentId::Directory => SegmentData {
+                primary: "CCometixLine".to_string(),
+                secondary: "".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("current_dir".to_string(), "~/CCometixLine".to_string());
+                    map
                 },
-                SegmentId::Git => SegmentData {
-                    primary: "master".to_string(),
-                    secondary: "✓".to_string(),
-                    metadata: {
-                        let mut map = HashMap::new();
-                        map.insert("branch".to_string(), "master".to_string());
-                        map.insert("status".to_string(), "Clean".to_string());
-                        map.insert("ahead".to_string(), "0".to_string());
-                        map.insert("behind".to_string(), "0".to_string());
-                        map
-                    },
+            },
+            SegmentId::Git => SegmentData {
+                primary: "master".to_string(),
+                secondary: "✓".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("branch".to_string(), "master".to_string());
+                    map.insert("status".to_string(), "Clean".to_string());
+                    map.insert("ahead".to_string(), "0".to_string());
+                    map.insert("behind".to_string(), "0".to_string());
+                    map
                 },
-                SegmentId::ContextWindow => SegmentData {
-                    primary: "78.2%".to_string(),
-                    secondary: "· 156.4k".to_string(),
-                    metadata: {
-                        let mut map = HashMap::new();
-                        map.insert("total_tokens".to_string(), "156400".to_string());
-                        map.insert("percentage".to_string(), "78.2".to_string());
-                        map.insert("session_tokens".to_string(), "48200".to_string());
-                        map
-                    },
+            },
+            SegmentId::ContextWindow => SegmentData {
+                primary: "78.2%".to_string(),
+                secondary: "· 156.4k".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("total_tokens".to_string(), "156400".to_string());
+                    map.insert("percentage".to_string(), "78.2".to_string());
+                    map.insert("session_tokens".to_string(), "48200".to_string());
+                    map
                 },
-                SegmentId::Usage => SegmentData {
-                    primary: "24%".to_string(),
-                    secondary: "· 10-7-2".to_string(),
-                    metadata: HashMap::new(),
+            },
+            SegmentId::Usage => SegmentData {
+                primary: "24%".to_string(),
+                secondary: "· 10-7-2".to_string(),
+                metadata: HashMap::new(),
+            },
+            SegmentId::Cost => SegmentData {
+                primary: "$0.02".to_string(),
+                secondary: "".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("cost".to_string(), "0.01234".to_string());
+                    map
                 },
-                SegmentId::Cost => SegmentData {
-                    primary: "$0.02".to_string(),
-                    secondary: "".to_string(),
-                    metadata: {
-                        let mut map = HashMap::new();
-                        map.insert("cost".to_string(), "0.01234".to_string());
-                        map
-                    },
+            },
+            SegmentId::Session => SegmentData {
+                primary: "3m45s".to_string(),
+                secondary: "+156 -23".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("duration_ms".to_string(), "225000".to_string());
+                    map.insert("lines_added".to_string(), "156".to_string());
+                    map.insert("lines_removed".to_string(), "23".to_string());
+                    map
                 },
-                SegmentId::Session => SegmentData {
-                    primary: "3m45s".to_string(),
-                    secondary: "+156 -23".to_string(),
-                    metadata: {
-                        let mut map = HashMap::new();
-                        map.insert("duration_ms".to_string(), "225000".to_string());
-                        map.insert("lines_added".to_string(), "156".to_string());
-                        map.insert("lines_removed".to_string(), "23".to_string());
-                        map
-                    },
+            },
+            SegmentId::OutputStyle => SegmentData {
+                primary: "default".to_string(),
+                secondary: "".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("style_name".to_string(), "default".to_string());
+                    map
                 },
-                SegmentId::OutputStyle => SegmentData {
-                    primary: "default".to_string(),
-                    secondary: "".to_string(),
-                    metadata: {
-                        let mut map = HashMap::new();
-                        map.insert("style_name".to_string(), "default".to_string());
-                        map
-                    },
+            },
+            SegmentId::Update => SegmentData {
+                primary: format!("v{}", env!("CARGO_PKG_VERSION")),
+                secondary: "".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert(
+                        "current_version".to_string(),
+                        env!("CARGO_PKG_VERSION").to_string(),
+                    );
+                    map.insert("update_available".to_string(), "false".to_string());
+                    map
                 },
-                SegmentId::Update => SegmentData {
-                    primary: format!("v{}", env!("CARGO_PKG_VERSION")),
-                    secondary: "".to_string(),
-                    metadata: {
-                        let mut map = HashMap::new();
-                        map.insert(
-                            "current_version".to_string(),
-                            env!("CARGO_PKG_VERSION").to_string(),
-                        );
-                        map.insert("update_available".to_string(), "false".to_string());
-                        map
-                    },
+            },
+            SegmentId::NewApiCost => SegmentData {
+                primary: "¥12.34".to_string(),
+                secondary: "newapi".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("cost".to_string(), "12.34".to_string());
+                    map.insert("provider".to_string(), "newapi".to_string());
+                    map
                 },
-                SegmentId::NewApiCost => SegmentData {
-                    primary: "¥12.34".to_string(),
-                    secondary: "newapi".to_string(),
-                    metadata: {
-                        let mut map = HashMap::new();
-                        map.insert("cost".to_string(), "12.34".to_string());
-                        map.insert("provider".to_string(), "newapi".to_string());
-                        map
-                    },
+            },
+            SegmentId::RuntimeVersion => SegmentData {
+                primary: "py 3.11".to_string(),
+                secondary: "".to_string(),
+                metadata: HashMap::new(),
+            },
+            SegmentId::Notify => SegmentData {
+                primary: "5".to_string(),
+                secondary: "".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("count".to_string(), "5".to_string());
+                    map
                 },
-            };
-
-            segments_data.push((segment_config.clone(), mock_data));
+            },
+            SegmentId::Secrets => SegmentData {
+                primary: ".env".to_string(),
+                secondary: "".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("files".to_string(), ".env".to_string());
+                    map
+                },
+            },
+            SegmentId::Host => SegmentData {
+                primary: "dev@buildbox".to_string(),
+                secondary: "".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("host".to_string(), "buildbox".to_string());
+                    map.insert("user".to_string(), "dev".to_string());
+                    map.insert("ssh".to_string(), "true".to_string());
+                    map
+                },
+            },
+            SegmentId::Tmux => SegmentData {
+                primary: "work:editor".to_string(),
+                secondary: "".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("session".to_string(), "work".to_string());
+                    map.insert("window".to_string(), "editor".to_string());
+                    map.insert("pane".to_string(), "%3".to_string());
+                    map
+                },
+            },
+            SegmentId::ExitCode => SegmentData {
+                primary: "✗ 1".to_string(),
+                secondary: "".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("exit_code".to_string(), "1".to_string());
+                    map
+                },
+            },
+            SegmentId::Plan => SegmentData {
+                primary: "Max".to_string(),
+                secondary: "".to_string(),
+                metadata: {
+                    let mut map = HashMap::new();
+                    map.insert("plan".to_string(), "max".to_string());
+                    map.insert("source".to_string(), "input".to_string());
+                    map
+                },
+            },
+            SegmentId::Custom => SegmentData {
+                primary: "Custom".to_string(),
+                secondary: "".to_string(),
+                metadata: HashMap::new(),
+            },
         }
-
-        segments_data
     }
 }
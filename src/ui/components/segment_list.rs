@@ -58,6 +58,14 @@ impl SegmentListComponent {
                     SegmentId::Session => "Session",
                     SegmentId::OutputStyle => "Output Style",
                     SegmentId::Update => "Update",
+                    SegmentId::RuntimeVersion => "Runtime Version",
+                    SegmentId::Notify => "Notify",
+                    SegmentId::Secrets => "Secrets",
+                    SegmentId::Host => "Host",
+                    SegmentId::Tmux => "Tmux",
+                    SegmentId::ExitCode => "Exit Code",
+                    SegmentId::Plan => "Plan",
+                    SegmentId::Custom => "Custom",
                 };
 
                 if is_selected {
@@ -37,6 +37,14 @@ impl SettingsComponent {
                 SegmentId::Session => "Session",
                 SegmentId::OutputStyle => "Output Style",
                 SegmentId::Update => "Update",
+                SegmentId::RuntimeVersion => "Runtime Version",
+                SegmentId::Notify => "Notify",
+                SegmentId::Secrets => "Secrets",
+                SegmentId::Host => "Host",
+                SegmentId::Tmux => "Tmux",
+                SegmentId::ExitCode => "Exit Code",
+                SegmentId::Plan => "Plan",
+                SegmentId::Custom => "Custom",
             };
             let current_icon = match config.style.mode {
                 StyleMode::Plain => &segment.icon.plain,
@@ -12,6 +12,9 @@ pub struct ThemePresets;
 
 impl ThemePresets {
     pub fn get_theme(theme_name: &str) -> Config {
+        let resolved = Self::resolve_theme_name(theme_name);
+        let theme_name = resolved.as_deref().unwrap_or(theme_name);
+
         // First try to load from file
         if let Ok(config) = Self::load_theme_from_file(theme_name) {
             return config;
@@ -77,6 +80,74 @@ impl ThemePresets {
         Ok(())
     }
 
+    /// Resolve a theme name: an exact match wins, otherwise a prefix matching
+    /// exactly one theme (`son` → `sonokai`); ambiguous or unknown names fail
+    /// with the candidates listed
+    pub fn resolve_theme_name(theme_name: &str) -> Result<String, String> {
+        let themes = Self::list_available_themes();
+        if themes.iter().any(|theme| theme == theme_name) {
+            return Ok(theme_name.to_string());
+        }
+
+        let candidates: Vec<&String> = themes
+            .iter()
+            .filter(|theme| !theme_name.is_empty() && theme.starts_with(theme_name))
+            .collect();
+        match candidates.as_slice() {
+            [theme] => Ok(theme.to_string()),
+            [] => Err(format!(
+                "unknown theme '{}', available themes: {}",
+                theme_name,
+                themes.join(", ")
+            )),
+            _ => Err(format!(
+                "theme '{}' is ambiguous, matching: {}",
+                theme_name,
+                candidates
+                    .iter()
+                    .map(|theme| theme.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    /// Resolve the theme override for this run
+    /// Priority: `--theme` > `CCLINE_THEME` > config file (Ok(None) keeps the loaded config)
+    /// An unknown or ambiguous `--theme` is an error; for `CCLINE_THEME` it warns and is ignored
+    pub fn resolve_theme_override(cli_theme: Option<&str>) -> Result<Option<String>, String> {
+        if let Some(theme) = cli_theme {
+            return Self::resolve_theme_name(theme).map(Some);
+        }
+
+        let Ok(env_theme) = std::env::var("CCLINE_THEME") else {
+            return Ok(None);
+        };
+        let env_theme = env_theme.trim();
+        if env_theme.is_empty() {
+            return Ok(None);
+        }
+
+        match Self::resolve_theme_name(env_theme) {
+            Ok(theme) => Ok(Some(theme)),
+            Err(message) => {
+                eprintln!("ccline: {} in CCLINE_THEME, using config theme", message);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Apply the resolved theme override (see `resolve_theme_override`) to a loaded config
+    pub fn apply_theme_override(
+        config: Config,
+        cli_theme: Option<&str>,
+    ) -> Result<Config, Box<dyn std::error::Error>> {
+        Ok(match Self::resolve_theme_override(cli_theme)? {
+            Some(theme) => Self::get_theme(&theme),
+            None => config,
+        })
+    }
+
     /// List all available themes (built-in + custom)
     pub fn list_available_themes() -> Vec<String> {
         let mut themes = vec![
@@ -127,6 +198,11 @@ impl ThemePresets {
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: " | ".to_string(),
+                subseparator: None,
+                gradient: None,
+                max_total_width: None,
+                separator_fg: None,
+                separator_bg: None,
             },
             segments: vec![
                 theme_cometix::model_segment(),
@@ -140,6 +216,17 @@ impl ThemePresets {
                 theme_cometix::output_style_segment(),
             ],
             theme: "cometix".to_string(),
+            include: Vec::new(),
+            redact: Vec::new(),
+            format: None,
+            title_format: None,
+            max_concurrency: None,
+            no_newline: None,
+            empty_placeholder: None,
+            quiet_on_empty: None,
+            cache_dir: None,
+            min_render_interval_ms: None,
+            preview_real_input: None,
         }
     }
 
@@ -148,6 +235,11 @@ impl ThemePresets {
             style: StyleConfig {
                 mode: StyleMode::Plain,
                 separator: " | ".to_string(),
+                subseparator: None,
+                gradient: None,
+                max_total_width: None,
+                separator_fg: None,
+                separator_bg: None,
             },
             segments: vec![
                 theme_default::model_segment(),
@@ -161,6 +253,17 @@ impl ThemePresets {
                 theme_default::output_style_segment(),
             ],
             theme: "default".to_string(),
+            include: Vec::new(),
+            redact: Vec::new(),
+            format: None,
+            title_format: None,
+            max_concurrency: None,
+            no_newline: None,
+            empty_placeholder: None,
+            quiet_on_empty: None,
+            cache_dir: None,
+            min_render_interval_ms: None,
+            preview_real_input: None,
         }
     }
 
@@ -169,6 +272,11 @@ impl ThemePresets {
             style: StyleConfig {
                 mode: StyleMode::Plain,
                 separator: " │ ".to_string(),
+                subseparator: None,
+                gradient: None,
+                max_total_width: None,
+                separator_fg: None,
+                separator_bg: None,
             },
             segments: vec![
                 theme_minimal::model_segment(),
@@ -182,6 +290,17 @@ impl ThemePresets {
                 theme_minimal::output_style_segment(),
             ],
             theme: "minimal".to_string(),
+            include: Vec::new(),
+            redact: Vec::new(),
+            format: None,
+            title_format: None,
+            max_concurrency: None,
+            no_newline: None,
+            empty_placeholder: None,
+            quiet_on_empty: None,
+            cache_dir: None,
+            min_render_interval_ms: None,
+            preview_real_input: None,
         }
     }
 
@@ -190,6 +309,11 @@ impl ThemePresets {
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: " | ".to_string(),
+                subseparator: None,
+                gradient: None,
+                max_total_width: None,
+                separator_fg: None,
+                separator_bg: None,
             },
             segments: vec![
                 theme_gruvbox::model_segment(),
@@ -203,6 +327,17 @@ impl ThemePresets {
                 theme_gruvbox::output_style_segment(),
             ],
             theme: "gruvbox".to_string(),
+            include: Vec::new(),
+            redact: Vec::new(),
+            format: None,
+            title_format: None,
+            max_concurrency: None,
+            no_newline: None,
+            empty_placeholder: None,
+            quiet_on_empty: None,
+            cache_dir: None,
+            min_render_interval_ms: None,
+            preview_real_input: None,
         }
     }
 
@@ -211,6 +346,11 @@ impl ThemePresets {
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: "".to_string(),
+                subseparator: None,
+                gradient: None,
+                max_total_width: None,
+                separator_fg: None,
+                separator_bg: None,
             },
             segments: vec![
                 theme_nord::model_segment(),
@@ -224,6 +364,17 @@ impl ThemePresets {
                 theme_nord::output_style_segment(),
             ],
             theme: "nord".to_string(),
+            include: Vec::new(),
+            redact: Vec::new(),
+            format: None,
+            title_format: None,
+            max_concurrency: None,
+            no_newline: None,
+            empty_placeholder: None,
+            quiet_on_empty: None,
+            cache_dir: None,
+            min_render_interval_ms: None,
+            preview_real_input: None,
         }
     }
 
@@ -232,6 +383,11 @@ impl ThemePresets {
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: "".to_string(),
+                subseparator: None,
+                gradient: None,
+                max_total_width: None,
+                separator_fg: None,
+                separator_bg: None,
             },
             segments: vec![
                 theme_powerline_dark::model_segment(),
@@ -245,6 +401,17 @@ impl ThemePresets {
                 theme_powerline_dark::output_style_segment(),
             ],
             theme: "powerline-dark".to_string(),
+            include: Vec::new(),
+            redact: Vec::new(),
+            format: None,
+            title_format: None,
+            max_concurrency: None,
+            no_newline: None,
+            empty_placeholder: None,
+            quiet_on_empty: None,
+            cache_dir: None,
+            min_render_interval_ms: None,
+            preview_real_input: None,
         }
     }
 
@@ -253,6 +420,11 @@ impl ThemePresets {
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: "".to_string(),
+                subseparator: None,
+                gradient: None,
+                max_total_width: None,
+                separator_fg: None,
+                separator_bg: None,
             },
             segments: vec![
                 theme_powerline_light::model_segment(),
@@ -266,6 +438,17 @@ impl ThemePresets {
                 theme_powerline_light::output_style_segment(),
             ],
             theme: "powerline-light".to_string(),
+            include: Vec::new(),
+            redact: Vec::new(),
+            format: None,
+            title_format: None,
+            max_concurrency: None,
+            no_newline: None,
+            empty_placeholder: None,
+            quiet_on_empty: None,
+            cache_dir: None,
+            min_render_interval_ms: None,
+            preview_real_input: None,
         }
     }
 
@@ -274,6 +457,11 @@ impl ThemePresets {
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: "".to_string(),
+                subseparator: None,
+                gradient: None,
+                max_total_width: None,
+                separator_fg: None,
+                separator_bg: None,
             },
             segments: vec![
                 theme_powerline_rose_pine::model_segment(),
@@ -287,6 +475,17 @@ impl ThemePresets {
                 theme_powerline_rose_pine::output_style_segment(),
             ],
             theme: "powerline-rose-pine".to_string(),
+            include: Vec::new(),
+            redact: Vec::new(),
+            format: None,
+            title_format: None,
+            max_concurrency: None,
+            no_newline: None,
+            empty_placeholder: None,
+            quiet_on_empty: None,
+            cache_dir: None,
+            min_render_interval_ms: None,
+            preview_real_input: None,
         }
     }
 
@@ -295,6 +494,11 @@ impl ThemePresets {
             style: StyleConfig {
                 mode: StyleMode::NerdFont,
                 separator: "".to_string(),
+                subseparator: None,
+                gradient: None,
+                max_total_width: None,
+                separator_fg: None,
+                separator_bg: None,
             },
             segments: vec![
                 theme_powerline_tokyo_night::model_segment(),
@@ -308,6 +512,117 @@ impl ThemePresets {
                 theme_powerline_tokyo_night::output_style_segment(),
             ],
             theme: "powerline-tokyo-night".to_string(),
+            include: Vec::new(),
+            redact: Vec::new(),
+            format: None,
+            title_format: None,
+            max_concurrency: None,
+            no_newline: None,
+            empty_placeholder: None,
+            quiet_on_empty: None,
+            cache_dir: None,
+            min_render_interval_ms: None,
+            preview_real_input: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{EnvGuard, TempDir};
+
+    fn isolated_env() -> (EnvGuard, TempDir) {
+        let mut env = EnvGuard::new();
+        let home = TempDir::new("home");
+        env.set("HOME", home.path());
+        env.remove("CCLINE_THEME");
+        (env, home)
+    }
+
+    #[test]
+    fn cli_theme_wins_over_env() {
+        let (mut env, _home) = isolated_env();
+        env.set("CCLINE_THEME", "gruvbox");
+
+        assert_eq!(
+            ThemePresets::resolve_theme_override(Some("nord")),
+            Ok(Some("nord".to_string()))
+        );
+    }
+
+    #[test]
+    fn env_theme_applies_without_cli_theme() {
+        let (mut env, _home) = isolated_env();
+        assert_eq!(ThemePresets::resolve_theme_override(None), Ok(None));
+
+        env.set("CCLINE_THEME", "gruvbox");
+        assert_eq!(
+            ThemePresets::resolve_theme_override(None),
+            Ok(Some("gruvbox".to_string()))
+        );
+
+        env.set("CCLINE_THEME", "  ");
+        assert_eq!(ThemePresets::resolve_theme_override(None), Ok(None));
+    }
+
+    #[test]
+    fn invalid_env_theme_falls_back_but_invalid_cli_theme_errors() {
+        let (mut env, _home) = isolated_env();
+        env.set("CCLINE_THEME", "no-such-theme");
+
+        assert_eq!(ThemePresets::resolve_theme_override(None), Ok(None));
+        assert!(ThemePresets::resolve_theme_override(Some("no-such-theme")).is_err());
+    }
+
+    #[test]
+    fn exact_theme_name_wins_over_longer_matches() {
+        let (_env, home) = isolated_env();
+        home.write(".claude/ccline/themes/nord-light.toml", "");
+
+        assert_eq!(
+            ThemePresets::resolve_theme_name("nord"),
+            Ok("nord".to_string())
+        );
+    }
+
+    #[test]
+    fn unique_prefix_resolves_including_custom_themes() {
+        let (_env, home) = isolated_env();
+        home.write(".claude/ccline/themes/sonokai.toml", "");
+
+        assert_eq!(
+            ThemePresets::resolve_theme_name("gru"),
+            Ok("gruvbox".to_string())
+        );
+        assert_eq!(
+            ThemePresets::resolve_theme_name("son"),
+            Ok("sonokai".to_string())
+        );
+    }
+
+    #[test]
+    fn ambiguous_prefix_lists_the_candidates() {
+        let (_env, _home) = isolated_env();
+
+        let error = ThemePresets::resolve_theme_name("powerline-").unwrap_err();
+        assert!(error.contains("ambiguous"), "{}", error);
+        assert!(error.contains(
+            "powerline-dark, powerline-light, powerline-rose-pine, powerline-tokyo-night"
+        ));
+        assert!(!error.contains("nord"));
+    }
+
+    #[test]
+    fn unknown_or_empty_name_lists_every_theme() {
+        let (_env, _home) = isolated_env();
+
+        for name in ["zzz", ""] {
+            let error = ThemePresets::resolve_theme_name(name).unwrap_err();
+            assert!(error.contains("unknown theme"), "{}", error);
+            for theme in ThemePresets::list_available_themes() {
+                assert!(error.contains(&theme), "{} missing from {}", theme, error);
+            }
         }
     }
 }
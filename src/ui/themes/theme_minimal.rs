@@ -18,6 +18,8 @@ pub fn model_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -36,6 +38,8 @@ pub fn directory_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -58,6 +62,8 @@ pub fn git_segment() -> SegmentConfig {
             opts.insert("show_sha".to_string(), serde_json::Value::Bool(false));
             opts
         },
+        group: None,
+        name: None,
     }
 }
 
@@ -76,6 +82,8 @@ pub fn context_window_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -94,6 +102,8 @@ pub fn cost_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -125,6 +135,8 @@ pub fn newapi_cost_segment() -> SegmentConfig {
             );
             opts
         },
+        group: None,
+        name: None,
     }
 }
 
@@ -143,6 +155,8 @@ pub fn session_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -161,6 +175,8 @@ pub fn output_style_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -191,5 +207,7 @@ pub fn usage_segment() -> SegmentConfig {
             opts.insert("timeout".to_string(), serde_json::Value::Number(2.into()));
             opts
         },
+        group: None,
+        name: None,
     }
 }
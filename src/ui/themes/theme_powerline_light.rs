@@ -22,6 +22,8 @@ pub fn model_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -52,6 +54,8 @@ pub fn directory_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -86,6 +90,8 @@ pub fn git_segment() -> SegmentConfig {
             opts.insert("show_sha".to_string(), serde_json::Value::Bool(false));
             opts
         },
+        group: None,
+        name: None,
     }
 }
 
@@ -116,6 +122,8 @@ pub fn context_window_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -146,6 +154,8 @@ pub fn cost_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -189,6 +199,8 @@ pub fn newapi_cost_segment() -> SegmentConfig {
             );
             opts
         },
+        group: None,
+        name: None,
     }
 }
 
@@ -219,6 +231,8 @@ pub fn session_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -249,6 +263,8 @@ pub fn output_style_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -279,5 +295,7 @@ pub fn usage_segment() -> SegmentConfig {
             opts.insert("timeout".to_string(), serde_json::Value::Number(2.into()));
             opts
         },
+        group: None,
+        name: None,
     }
 }
@@ -30,6 +30,8 @@ pub fn model_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -60,6 +62,8 @@ pub fn directory_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -94,6 +98,8 @@ pub fn git_segment() -> SegmentConfig {
             opts.insert("show_sha".to_string(), serde_json::Value::Bool(false));
             opts
         },
+        group: None,
+        name: None,
     }
 }
 
@@ -124,6 +130,8 @@ pub fn context_window_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -154,6 +162,8 @@ pub fn cost_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -197,6 +207,8 @@ pub fn newapi_cost_segment() -> SegmentConfig {
             );
             opts
         },
+        group: None,
+        name: None,
     }
 }
 
@@ -227,6 +239,8 @@ pub fn session_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -257,6 +271,8 @@ pub fn output_style_segment() -> SegmentConfig {
         },
         styles: TextStyleConfig::default(),
         options: HashMap::new(),
+        group: None,
+        name: None,
     }
 }
 
@@ -287,5 +303,7 @@ pub fn usage_segment() -> SegmentConfig {
             opts.insert("timeout".to_string(), serde_json::Value::Number(2.into()));
             opts
         },
+        group: None,
+        name: None,
     }
 }
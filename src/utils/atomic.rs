@@ -0,0 +1,142 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Write a file by writing a sibling temp file and renaming it over the target,
+/// so readers never observe a partially written file
+pub fn write_atomic<P: AsRef<Path>>(path: P, contents: &[u8]) -> std::io::Result<()> {
+    replace_with(path.as_ref(), contents, None)
+}
+
+/// Like `write_atomic` for an existing file, but keeps its permissions (such
+/// as the executable bit) and, when `path` is a symlink, replaces the file
+/// it points to instead of the link
+pub fn replace_atomic<P: AsRef<Path>>(path: P, contents: &[u8]) -> std::io::Result<()> {
+    let target = fs::canonicalize(path)?;
+    let permissions = fs::metadata(&target)?.permissions();
+    replace_with(&target, contents, Some(permissions))
+}
+
+fn replace_with(
+    path: &Path,
+    contents: &[u8],
+    permissions: Option<fs::Permissions>,
+) -> std::io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a file path"))?;
+
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = write_and_rename(&tmp_path, path, contents, permissions);
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+fn write_and_rename(
+    tmp_path: &Path,
+    path: &Path,
+    contents: &[u8],
+    permissions: Option<fs::Permissions>,
+) -> std::io::Result<()> {
+    let mut file = fs::File::create(tmp_path)?;
+    file.write_all(contents)?;
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions)?;
+    }
+    file.sync_all()?;
+    fs::rename(tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn writes_the_line_and_leaves_no_temp_file() {
+        let dir = TempDir::new("atomic");
+        let path = dir.join("statusline.txt");
+        write_atomic(&path, b"old line that is longer\n").unwrap();
+        write_atomic(&path, b"* Sonnet 4\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "* Sonnet 4\n");
+        let entries: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn readers_never_see_a_partial_write() {
+        let dir = TempDir::new("atomic");
+        let path = dir.join("statusline.txt");
+        let short = "a".repeat(10);
+        let long = "b".repeat(64 * 1024);
+        write_atomic(&path, short.as_bytes()).unwrap();
+
+        let done = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let reader = {
+            let (path, done) = (path.clone(), done.clone());
+            let (short, long) = (short.clone(), long.clone());
+            std::thread::spawn(move || {
+                while !done.load(std::sync::atomic::Ordering::Relaxed) {
+                    let content = fs::read_to_string(&path).unwrap();
+                    assert!(content == short || content == long, "partial read");
+                }
+            })
+        };
+
+        for i in 0..200 {
+            let content = if i % 2 == 0 { &long } else { &short };
+            write_atomic(&path, content.as_bytes()).unwrap();
+        }
+        done.store(true, std::sync::atomic::Ordering::Relaxed);
+        reader.join().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn replace_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new("atomic");
+        let path = dir.write("cli.js", "old");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        replace_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o755
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn replace_writes_through_a_symlink() {
+        let dir = TempDir::new("atomic");
+        let real = dir.write("lib/cli.js", "old");
+        let link = dir.join("cli.js");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        replace_atomic(&link, b"new").unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&real).unwrap(), "new");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn replace_needs_an_existing_file() {
+        let dir = TempDir::new("atomic");
+        assert!(replace_atomic(dir.join("missing.js"), b"new").is_err());
+        assert!(!dir.join("missing.js").exists());
+    }
+}
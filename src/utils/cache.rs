@@ -0,0 +1,405 @@
+use super::write_atomic;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Serializes read-modify-write cycles within this process; across processes
+/// the last writer wins, and atomic writes keep the file readable either way
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Cache directory set by the `cache_dir` config option
+static CACHE_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Keep caches in `dir` (a leading `~/` is the home directory) for the rest of
+/// the process instead of the platform cache directory
+pub fn set_cache_dir(dir: &str) {
+    if let Some(dir) = resolve_cache_dir(Some(dir)) {
+        let _ = CACHE_DIR_OVERRIDE.set(dir);
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Per-test cache directory, see `test_support::isolated_cache`
+    pub(crate) static TEST_CACHE_DIR: std::cell::RefCell<Option<PathBuf>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Directory holding every cache file
+pub fn cache_dir() -> Option<PathBuf> {
+    #[cfg(test)]
+    if let Some(dir) = TEST_CACHE_DIR.with(|dir| dir.borrow().clone()) {
+        return Some(dir);
+    }
+
+    match CACHE_DIR_OVERRIDE.get() {
+        Some(dir) => Some(dir.clone()),
+        None => resolve_cache_dir(None),
+    }
+}
+
+/// A configured directory, else the platform cache directory:
+/// `$XDG_CACHE_HOME/ccometixline` (`~/.cache` when unset) on Linux,
+/// `~/Library/Caches/ccometixline` on macOS, `%LOCALAPPDATA%\ccometixline` on Windows
+pub fn resolve_cache_dir(configured: Option<&str>) -> Option<PathBuf> {
+    match configured.filter(|dir| !dir.is_empty()) {
+        Some(dir) => match (dir.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => Some(home.join(rest)),
+            _ => Some(PathBuf::from(dir)),
+        },
+        None => dirs::cache_dir().map(|dir| dir.join("ccometixline")),
+    }
+}
+
+/// Files the caches keep in `dir` (`<namespace>.json`), sorted by name;
+/// anything else in the directory is left alone
+pub fn cache_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let is_cache = path.extension().is_some_and(|ext| ext == "json")
+            && !path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if is_cache && path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// `--cache-status`: the cache directory and the size of each cache file
+pub fn cache_status(dir: &Path) -> std::io::Result<String> {
+    let files = cache_files(dir)?;
+    let mut lines = vec![format!("Cache directory: {}", dir.display())];
+    if files.is_empty() {
+        lines.push("  (empty)".to_string());
+    }
+    let mut total = 0;
+    for path in &files {
+        let bytes = std::fs::metadata(path)?.len();
+        total += bytes;
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        lines.push(format!("  {:<20} {:>8} bytes", name, bytes));
+    }
+    if !files.is_empty() {
+        lines.push(format!("{} files, {} bytes", files.len(), total));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// `--cache-clear`: remove every cache file in `dir`, returning how many
+pub fn clear_cache(dir: &Path) -> std::io::Result<usize> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let files = cache_files(dir)?;
+    for path in &files {
+        std::fs::remove_file(path)?;
+    }
+    Ok(files.len())
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry<V> {
+    /// When the entry was written (seconds since epoch)
+    stored_at: u64,
+    value: V,
+}
+
+/// Persistent key-value cache backed by a JSON file, with an optional TTL
+///
+/// Each namespace (usually a segment id) gets its own file under
+/// [`cache_dir`], so segments never clobber each other's entries.
+pub struct Cache<K, V> {
+    path: Option<PathBuf>,
+    ttl: Option<Duration>,
+    _marker: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V> Cache<K, V>
+where
+    K: Ord + Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    /// Cache stored at `<cache_dir>/<namespace>.json`
+    pub fn new(namespace: &str) -> Self {
+        let path = cache_dir().map(|dir| dir.join(format!("{}.json", namespace)));
+        Self {
+            path,
+            ttl: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Cache stored at an explicit file path
+    pub fn at<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: Some(path.into()),
+            ttl: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Treat entries older than `ttl` as missing
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Fresh value for `key` (None when missing, expired or unreadable)
+    pub fn get(&self, key: &K) -> Option<V> {
+        let (value, age) = self.get_with_age(key)?;
+        match self.ttl {
+            Some(ttl) if age >= ttl => None,
+            _ => Some(value),
+        }
+    }
+
+    /// Value for `key` regardless of the TTL, along with its age
+    pub fn get_with_age(&self, key: &K) -> Option<(V, Duration)> {
+        let entry = self.load_entries().remove(key)?;
+        let value = serde_json::from_value(entry.value).ok()?;
+        let age = now_secs().saturating_sub(entry.stored_at);
+        Some((value, Duration::from_secs(age)))
+    }
+
+    /// Store `value` under `key`, dropping any expired entries on the way
+    pub fn insert(&self, key: K, value: &V) -> std::io::Result<()> {
+        let value = serde_json::to_value(value)?;
+        self.modify(|entries, now| {
+            entries.insert(
+                key,
+                CacheEntry {
+                    stored_at: now,
+                    value,
+                },
+            );
+        })
+    }
+
+    /// Remove the entry for `key`, if any
+    pub fn remove(&self, key: &K) -> std::io::Result<()> {
+        self.modify(|entries, _| {
+            entries.remove(key);
+        })
+    }
+
+    fn modify<F>(&self, f: F) -> std::io::Result<()>
+    where
+        F: FnOnce(&mut BTreeMap<K, CacheEntry<serde_json::Value>>, u64),
+    {
+        let path = self.path.as_ref().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "no cache directory")
+        })?;
+        let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let now = now_secs();
+        let mut entries = self.load_entries();
+        if let Some(ttl) = self.ttl {
+            entries.retain(|_, entry| now.saturating_sub(entry.stored_at) < ttl.as_secs());
+        }
+        f(&mut entries, now);
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&entries)?;
+        write_atomic(path, json.as_bytes())
+    }
+
+    /// Raw entries, keeping values as JSON so entries of other shapes survive a rewrite
+    fn load_entries(&self) -> BTreeMap<K, CacheEntry<serde_json::Value>> {
+        self.path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{isolated_cache, TempDir};
+
+    /// Cache file holding `key` stored `age_secs` ago
+    fn write_aged_entry(dir: &TempDir, key: &str, value: u32, age_secs: u64) -> PathBuf {
+        let entries = serde_json::json!({
+            key: {"stored_at": now_secs() - age_secs, "value": value},
+        });
+        dir.write("aged.json", &entries.to_string())
+    }
+
+    #[test]
+    fn insert_and_get_round_trip_per_namespace() {
+        let dir = isolated_cache();
+        let costs: Cache<String, f64> = Cache::new("new_api_cost");
+        let weather: Cache<String, f64> = Cache::new("weather");
+
+        costs.insert("today".to_string(), &1.5).unwrap();
+        weather.insert("today".to_string(), &21.0).unwrap();
+
+        assert_eq!(costs.get(&"today".to_string()), Some(1.5));
+        assert_eq!(weather.get(&"today".to_string()), Some(21.0));
+        assert!(dir.join("new_api_cost.json").is_file());
+        assert!(dir.join("weather.json").is_file());
+
+        costs.remove(&"today".to_string()).unwrap();
+        assert_eq!(costs.get(&"today".to_string()), None);
+        assert_eq!(weather.get(&"today".to_string()), Some(21.0));
+    }
+
+    #[test]
+    fn entries_expire_after_ttl() {
+        let dir = TempDir::new("cache");
+        let path = write_aged_entry(&dir, "k", 7, 120);
+
+        let short: Cache<String, u32> = Cache::at(&path).with_ttl(Duration::from_secs(60));
+        assert_eq!(short.get(&"k".to_string()), None);
+        let (value, age) = short.get_with_age(&"k".to_string()).unwrap();
+        assert_eq!(value, 7);
+        assert!(age >= Duration::from_secs(120));
+
+        let long: Cache<String, u32> = Cache::at(&path).with_ttl(Duration::from_secs(300));
+        assert_eq!(long.get(&"k".to_string()), Some(7));
+        let forever: Cache<String, u32> = Cache::at(&path);
+        assert_eq!(forever.get(&"k".to_string()), Some(7));
+    }
+
+    #[test]
+    fn insert_drops_expired_entries() {
+        let dir = TempDir::new("cache");
+        let path = write_aged_entry(&dir, "old", 1, 120);
+        let cache: Cache<String, u32> = Cache::at(&path).with_ttl(Duration::from_secs(60));
+
+        cache.insert("new".to_string(), &2).unwrap();
+
+        let raw: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(raw.get("old").is_none());
+        assert_eq!(raw["new"]["value"], 2);
+    }
+
+    #[test]
+    fn unreadable_file_is_treated_as_empty_and_replaced() {
+        let dir = TempDir::new("cache");
+        let path = dir.write("broken.json", "{not json");
+        let cache: Cache<String, u32> = Cache::at(&path);
+
+        assert_eq!(cache.get(&"k".to_string()), None);
+        cache.insert("k".to_string(), &3).unwrap();
+        assert_eq!(cache.get(&"k".to_string()), Some(3));
+        // Only the cache file itself: the temp file was renamed into place
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn concurrent_inserts_keep_every_entry() {
+        let dir = TempDir::new("cache");
+        let path = dir.join("shared.json");
+
+        let threads: Vec<_> = (0..8u32)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let cache: Cache<u32, u32> = Cache::at(path);
+                    for j in 0..10 {
+                        cache.insert(i * 10 + j, &(i * 10 + j)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let cache: Cache<u32, u32> = Cache::at(&path);
+        for key in 0..80 {
+            assert_eq!(cache.get(&key), Some(key));
+        }
+    }
+
+    #[test]
+    fn configured_dir_overrides_the_platform_default() {
+        // Holds the environment steady while HOME is read
+        let _env = crate::test_support::EnvGuard::new();
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(
+            resolve_cache_dir(Some("~/.claude/ccline/cache")),
+            Some(home.join(".claude/ccline/cache"))
+        );
+        assert_eq!(
+            resolve_cache_dir(Some("/var/cache/ccline")),
+            Some(PathBuf::from("/var/cache/ccline"))
+        );
+        assert_eq!(resolve_cache_dir(Some("")), resolve_cache_dir(None));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn default_dir_follows_xdg_cache_home() {
+        let home = TempDir::new("home");
+        let mut env = crate::test_support::EnvGuard::new();
+        env.set("HOME", home.path())
+            .set("XDG_CACHE_HOME", home.join("xdg-cache"));
+        assert_eq!(
+            resolve_cache_dir(None),
+            Some(home.join("xdg-cache/ccometixline"))
+        );
+
+        env.remove("XDG_CACHE_HOME");
+        assert_eq!(
+            resolve_cache_dir(None),
+            Some(home.join(".cache/ccometixline"))
+        );
+    }
+
+    #[test]
+    fn status_and_clear_touch_only_cache_files() {
+        let dir = TempDir::new("cache");
+        dir.write("git.json", "{}");
+        dir.write("newapi_cost.json", "{\"k\": 1}");
+        dir.write("notes.txt", "keep");
+        dir.write(".git.json.1.tmp", "");
+
+        let status = cache_status(dir.path()).unwrap();
+        assert!(status.starts_with(&format!("Cache directory: {}", dir.path().display())));
+        assert!(status.contains("git.json"), "{status}");
+        assert!(status.contains("newapi_cost.json"), "{status}");
+        assert!(!status.contains("notes.txt"), "{status}");
+        assert!(status.ends_with("2 files, 10 bytes"), "{status}");
+
+        assert_eq!(clear_cache(dir.path()).unwrap(), 2);
+        assert!(dir.join("notes.txt").exists());
+        assert!(cache_status(dir.path()).unwrap().ends_with("(empty)"));
+    }
+
+    #[test]
+    fn missing_dir_is_an_empty_cache() {
+        let dir = TempDir::new("cache");
+        let missing = dir.join("never-created");
+
+        assert_eq!(clear_cache(&missing).unwrap(), 0);
+        assert!(cache_status(&missing).unwrap().ends_with("(empty)"));
+    }
+}
@@ -1,6 +1,7 @@
 use regex::Regex;
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone)]
 pub struct LocationResult {
@@ -9,20 +10,87 @@ pub struct LocationResult {
     pub variable_name: Option<String>,
 }
 
+/// Lockfile beside the patch target, held from read to save so two patch
+/// runs can't interleave their read-modify-write; removed on drop
+#[derive(Debug)]
+pub struct PatchLock {
+    path: PathBuf,
+}
+
+impl PatchLock {
+    /// Lockfile used for a target: `<target>.ccline.lock`
+    pub fn path_for(target: &Path) -> PathBuf {
+        let mut name = target.as_os_str().to_os_string();
+        name.push(".ccline.lock");
+        PathBuf::from(name)
+    }
+
+    /// Create the lockfile, failing at once if another patch holds it
+    pub fn acquire(target: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = Self::path_for(target);
+        let mut file = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let holder = fs::read_to_string(&path).unwrap_or_default();
+                return Err(format!(
+                    "another patch is in progress (lock {} held by pid {}); \
+                     if no patch is running, delete the lock file and retry",
+                    path.display(),
+                    holder.trim()
+                )
+                .into());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let _ = write!(file, "{}", std::process::id());
+
+        Ok(Self { path })
+    }
+}
+
+impl Drop for PatchLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
 #[derive(Debug)]
 pub struct ClaudeCodePatcher {
     file_content: String,
     file_path: String,
+    /// None for a read-only preview, which can't be saved
+    lock: Option<PatchLock>,
 }
 
 impl ClaudeCodePatcher {
+    /// Lock the file (see `PatchLock`) and read it; the lock is released
+    /// when the patcher is dropped
     pub fn new<P: AsRef<Path>>(file_path: P) -> Result<Self, Box<dyn std::error::Error>> {
         let path = file_path.as_ref();
+        let lock = PatchLock::acquire(path)?;
         let content = fs::read_to_string(path)?;
 
         Ok(Self {
             file_content: content,
             file_path: path.to_string_lossy().to_string(),
+            lock: Some(lock),
+        })
+    }
+
+    /// Read the file without locking it, for `--dry-run`; modifications only
+    /// change the in-memory copy and `save` refuses to write
+    pub fn preview<P: AsRef<Path>>(file_path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = file_path.as_ref();
+        let content = fs::read_to_string(path)?;
+
+        Ok(Self {
+            file_content: content,
+            file_path: path.to_string_lossy().to_string(),
+            lock: None,
         })
     }
 
@@ -94,9 +162,13 @@ impl ClaudeCodePatcher {
         Ok(())
     }
 
-    /// Save the modified content back to file
+    /// Save the modified content back to file, replacing it atomically
+    /// A symlinked cli.js stays a symlink and the file keeps its permissions
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        fs::write(&self.file_path, &self.file_content)?;
+        if self.lock.is_none() {
+            return Err("patcher was opened as a preview and cannot save".into());
+        }
+        super::replace_atomic(&self.file_path, self.file_content.as_bytes())?;
         Ok(())
     }
 
@@ -378,3 +450,89 @@ impl ClaudeCodePatcher {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn second_patch_is_rejected_while_the_first_holds_the_lock() {
+        let dir = TempDir::new("patch");
+        let target = dir.write("cli.js", "original");
+
+        let first = ClaudeCodePatcher::new(&target).unwrap();
+        assert!(PatchLock::path_for(&target).exists());
+
+        let err = ClaudeCodePatcher::new(&target).unwrap_err().to_string();
+        assert!(err.contains("another patch is in progress"), "{err}");
+        assert!(err.contains(&std::process::id().to_string()), "{err}");
+
+        // A preview doesn't need the lock
+        assert!(ClaudeCodePatcher::preview(&target).is_ok());
+
+        drop(first);
+        assert!(!PatchLock::path_for(&target).exists());
+        assert!(ClaudeCodePatcher::new(&target).is_ok());
+    }
+
+    #[test]
+    fn concurrent_patches_get_the_lock_once() {
+        let dir = TempDir::new("patch");
+        let target = dir.write("cli.js", "original");
+        let barrier = std::sync::Barrier::new(8);
+
+        let patchers: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        barrier.wait();
+                        ClaudeCodePatcher::new(&target).ok()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        assert_eq!(patchers.iter().flatten().count(), 1);
+    }
+
+    #[test]
+    fn preview_cannot_save() {
+        let dir = TempDir::new("patch");
+        let target = dir.write("cli.js", "original");
+
+        let preview = ClaudeCodePatcher::preview(&target).unwrap();
+        assert!(preview.save().is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "original");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn save_keeps_a_symlinked_executable_cli() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new("patch");
+        let real = dir.write("pkg/cli.js", "original");
+        fs::set_permissions(&real, fs::Permissions::from_mode(0o755)).unwrap();
+        let link = dir.join("cli.js");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let mut patcher = ClaudeCodePatcher::new(&link).unwrap();
+        patcher.file_content = "patched".to_string();
+        patcher.save().unwrap();
+
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&real).unwrap(), "patched");
+        assert_eq!(
+            fs::metadata(&real).unwrap().permissions().mode() & 0o777,
+            0o755
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Error reporting on stderr, as text or (with `--json-errors`) as one JSON
+//! object per line so wrappers can react to failures programmatically.
+
+use serde_json::json;
+use std::fmt;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Stable error codes used in JSON error output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The config file could not be read or parsed
+    ConfigLoad,
+    /// The config parsed but failed validation (`--check`)
+    ConfigInvalid,
+    /// stdin was not valid Claude Code input
+    InputParse,
+    /// A network segment could not reach its API
+    Network,
+    /// A segment failed for another reason
+    Segment,
+    /// File system or stream error
+    Io,
+    /// Anything else
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::ConfigLoad => "config_load",
+            ErrorCode::ConfigInvalid => "config_invalid",
+            ErrorCode::InputParse => "input_parse",
+            ErrorCode::Network => "network",
+            ErrorCode::Segment => "segment",
+            ErrorCode::Io => "io",
+            ErrorCode::Internal => "internal",
+        }
+    }
+}
+
+/// An error with a stable code, optionally tied to a segment
+#[derive(Debug, Clone)]
+pub struct ReportedError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub segment: Option<String>,
+}
+
+impl ReportedError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            segment: None,
+        }
+    }
+
+    pub fn with_segment(mut self, segment: impl Into<String>) -> Self {
+        self.segment = Some(segment.into());
+        self
+    }
+
+    /// Classify an error bubbling out of `main`, keeping the code of a
+    /// `ReportedError` and guessing one for common library errors
+    pub fn from_boxed(error: Box<dyn std::error::Error>) -> Self {
+        let error = match error.downcast::<ReportedError>() {
+            Ok(reported) => return *reported,
+            Err(error) => error,
+        };
+        let code = if error.is::<serde_json::Error>() {
+            ErrorCode::InputParse
+        } else if error.is::<toml::de::Error>() {
+            ErrorCode::ConfigLoad
+        } else if error.is::<std::io::Error>() {
+            ErrorCode::Io
+        } else {
+            ErrorCode::Internal
+        };
+        Self::new(code, error.to_string())
+    }
+
+    /// `{"code":"...","message":"...","segment":null}`
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "code": self.code.as_str(),
+            "message": self.message,
+            "segment": self.segment,
+        })
+    }
+}
+
+impl fmt::Display for ReportedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.segment {
+            Some(segment) => write!(f, "{}: {}", segment, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for ReportedError {}
+
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// Switch error output to JSON lines (`--json-errors`)
+pub fn set_json_errors(enabled: bool) {
+    JSON_ERRORS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn json_errors() -> bool {
+    JSON_ERRORS.load(Ordering::Relaxed)
+}
+
+/// Report an error that ends the program
+pub fn report_fatal(error: &ReportedError) {
+    let line = if json_errors() {
+        error.to_json().to_string()
+    } else {
+        format!("Error: {}", error)
+    };
+    let _ = writeln!(std::io::stderr(), "{}", line);
+}
+
+/// Report a failure ccline recovers from (e.g. a hidden network segment)
+/// Only printed in JSON mode; in text mode these stay silent as before
+pub fn report_recoverable(error: &ReportedError) {
+    if json_errors() {
+        let _ = writeln!(std::io::stderr(), "{}", error.to_json());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_shape_is_stable() {
+        let error =
+            ReportedError::new(ErrorCode::Network, "timed out").with_segment("new_api_cost");
+        assert_eq!(
+            error.to_json(),
+            json!({"code": "network", "message": "timed out", "segment": "new_api_cost"})
+        );
+        assert_eq!(error.to_string(), "new_api_cost: timed out");
+    }
+
+    #[test]
+    fn boxed_errors_are_classified() {
+        let code = |error: Box<dyn std::error::Error>| ReportedError::from_boxed(error).code;
+
+        let json = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert_eq!(code(json.into()), ErrorCode::InputParse);
+        let toml = toml::from_str::<toml::Value>("a = [").unwrap_err();
+        assert_eq!(code(toml.into()), ErrorCode::ConfigLoad);
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        assert_eq!(code(io.into()), ErrorCode::Io);
+        assert_eq!(code("other".into()), ErrorCode::Internal);
+        // An already reported error keeps its code and segment
+        let reported = ReportedError::new(ErrorCode::Segment, "boom").with_segment("git");
+        let kept = ReportedError::from_boxed(Box::new(reported));
+        assert_eq!(
+            (kept.code, kept.segment.as_deref()),
+            (ErrorCode::Segment, Some("git"))
+        );
+    }
+}
@@ -0,0 +1,211 @@
+/// Number and currency formatting conventions for a locale
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locale {
+    pub decimal_separator: char,
+    pub group_separator: Option<char>,
+    /// Place the currency symbol after the amount (e.g. `1.234,56 €`)
+    pub currency_after: bool,
+    /// Separate the currency symbol from the amount with a space
+    pub currency_space: bool,
+}
+
+impl Default for Locale {
+    /// Plain formatting: `.` decimals, no grouping, symbol first (`¥1234.56`)
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            group_separator: None,
+            currency_after: false,
+            currency_space: false,
+        }
+    }
+}
+
+impl Locale {
+    /// Look up a locale by name (`en-US`, `de_DE`, `fr`, ...)
+    /// Returns None for unknown locales
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalized = name.trim().to_lowercase().replace('_', "-");
+        let language = normalized.split('-').next().unwrap_or("");
+
+        let locale = match normalized.as_str() {
+            "en-in" => Self::new('.', Some(','), false, false),
+            "de-ch" => Self::new('.', Some('\''), false, true),
+            "pt-br" => Self::new(',', Some('.'), false, true),
+            _ => match language {
+                "en" | "zh" | "ja" | "ko" => Self::new('.', Some(','), false, false),
+                "de" | "es" | "it" | "nl" | "pt" => Self::new(',', Some('.'), true, true),
+                "fr" | "ru" | "pl" | "sv" | "fi" | "cs" => {
+                    Self::new(',', Some('\u{a0}'), true, true)
+                }
+                _ => return None,
+            },
+        };
+
+        Some(locale)
+    }
+
+    fn new(
+        decimal_separator: char,
+        group_separator: Option<char>,
+        currency_after: bool,
+        currency_space: bool,
+    ) -> Self {
+        Self {
+            decimal_separator,
+            group_separator,
+            currency_after,
+            currency_space,
+        }
+    }
+
+    /// Format a number with a fixed number of decimals
+    pub fn format_number(&self, value: f64, decimals: usize) -> String {
+        let formatted = format!("{:.*}", decimals, value.abs());
+        let (integer_part, fraction_part) = match formatted.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (formatted.as_str(), None),
+        };
+
+        let mut result = String::new();
+        if value < 0.0 && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            result.push('-');
+        }
+
+        match self.group_separator {
+            Some(separator) => {
+                let len = integer_part.len();
+                for (i, digit) in integer_part.chars().enumerate() {
+                    if i > 0 && (len - i) % 3 == 0 {
+                        result.push(separator);
+                    }
+                    result.push(digit);
+                }
+            }
+            None => result.push_str(integer_part),
+        }
+
+        if let Some(fraction) = fraction_part {
+            result.push(self.decimal_separator);
+            result.push_str(fraction);
+        }
+
+        result
+    }
+
+    /// Format a currency amount with a fixed number of decimals
+    pub fn format_currency(&self, value: f64, symbol: &str, decimals: usize) -> String {
+        self.place_currency(&self.format_number(value, decimals), symbol)
+    }
+
+    /// Format a number with a k/M/B suffix and at most `decimals` decimals, e.g. `12.5k`
+    pub fn format_compact(&self, value: f64, decimals: usize) -> String {
+        let (scaled, suffix) = match value.abs() {
+            v if v >= 1e9 => (value / 1e9, "B"),
+            v if v >= 1e6 => (value / 1e6, "M"),
+            v if v >= 1e3 => (value / 1e3, "k"),
+            _ => (value, ""),
+        };
+
+        let mut formatted = self.format_number(scaled, decimals);
+        if decimals > 0 {
+            let trimmed = formatted
+                .trim_end_matches('0')
+                .trim_end_matches(self.decimal_separator);
+            formatted = trimmed.to_string();
+        }
+        formatted.push_str(suffix);
+        formatted
+    }
+
+    /// Representations of a currency amount from most to least precise,
+    /// e.g. `¥12,500.00`, `¥12,500`, `¥12.5k`, `¥13k`
+    pub fn currency_candidates(&self, value: f64, symbol: &str, decimals: usize) -> Vec<String> {
+        let mut candidates = vec![
+            self.format_currency(value, symbol, decimals),
+            self.format_currency(value, symbol, 0),
+            self.place_currency(&self.format_compact(value, 1), symbol),
+            self.place_currency(&self.format_compact(value, 0), symbol),
+        ];
+        candidates.dedup();
+        candidates
+    }
+
+    /// Attach a currency symbol to an already formatted amount
+    pub fn place_currency(&self, amount: &str, symbol: &str) -> String {
+        let space = if self.currency_space { " " } else { "" };
+        if self.currency_after {
+            format!("{}{}{}", amount, space, symbol)
+        } else {
+            format!("{}{}{}", symbol, space, amount)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(name: &str) -> Locale {
+        Locale::from_name(name).unwrap()
+    }
+
+    #[test]
+    fn default_is_plain() {
+        assert_eq!(
+            Locale::default().format_currency(1234.5, "¥", 2),
+            "¥1234.50"
+        );
+    }
+
+    #[test]
+    fn english_groups_with_commas() {
+        assert_eq!(
+            locale("en-US").format_currency(1234567.891, "$", 2),
+            "$1,234,567.89"
+        );
+        assert_eq!(locale("zh_CN").format_currency(12.5, "¥", 2), "¥12.50");
+    }
+
+    #[test]
+    fn german_puts_symbol_after() {
+        assert_eq!(
+            locale("de-DE").format_currency(1234.56, "€", 2),
+            "1.234,56 €"
+        );
+        assert_eq!(
+            locale("de-CH").format_currency(1234.56, "CHF", 2),
+            "CHF 1'234.56"
+        );
+    }
+
+    #[test]
+    fn french_groups_with_non_breaking_space() {
+        assert_eq!(
+            locale("fr").format_currency(1234.56, "€", 2),
+            "1\u{a0}234,56 €"
+        );
+    }
+
+    #[test]
+    fn brazilian_keeps_symbol_first() {
+        assert_eq!(
+            locale("pt-BR").format_currency(1234.56, "R$", 2),
+            "R$ 1.234,56"
+        );
+    }
+
+    #[test]
+    fn unknown_locale_is_rejected() {
+        assert_eq!(Locale::from_name("xx-YY"), None);
+        assert_eq!(Locale::from_name(""), None);
+    }
+
+    #[test]
+    fn negative_and_rounded_zero() {
+        let en = locale("en");
+        assert_eq!(en.format_number(-1234.5, 1), "-1,234.5");
+        assert_eq!(en.format_number(-0.001, 2), "0.00");
+        assert_eq!(en.format_number(999.999, 2), "1,000.00");
+    }
+}
@@ -0,0 +1,260 @@
+//! Minimal leveled logging to stderr, enabled with `CCLINE_LOG=<level>`
+//! (falls back to `RUST_LOG`). stdout is reserved for the statusline.
+//! With `--log-file`, lines are appended to that file instead, at `debug`
+//! level unless a level is set.
+//! Lines start with an ISO-8601 timestamp and the time since startup unless
+//! `CCLINE_LOG_TIME=0`.
+
+use chrono::{DateTime, Local, SecondsFormat};
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "error" => Some(Level::Error),
+            "warn" | "warning" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+/// File log lines are appended to, set by `--log-file`
+static LOG_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Append log lines to `path` instead of writing them to stderr
+/// Must be called before the first log line to take effect
+pub fn set_log_file<P: Into<PathBuf>>(path: P) {
+    let _ = LOG_FILE.set(path.into());
+}
+
+/// Most verbose level to emit, read once from the environment (None disables logging)
+fn max_level() -> Option<Level> {
+    static MAX_LEVEL: OnceLock<Option<Level>> = OnceLock::new();
+    *MAX_LEVEL.get_or_init(|| level_from_env(LOG_FILE.get().is_some()))
+}
+
+/// `CCLINE_LOG`, else `RUST_LOG`; with a log file and neither set, `debug`
+fn level_from_env(has_log_file: bool) -> Option<Level> {
+    std::env::var("CCLINE_LOG")
+        .or_else(|_| std::env::var("RUST_LOG"))
+        .ok()
+        .and_then(|value| Level::parse(&value))
+        .or_else(|| has_log_file.then_some(Level::Debug))
+}
+
+/// Append one line to the log file; a file that can't be opened must not
+/// break the statusline
+fn append_line(path: &Path, line: &str) {
+    if let Ok(mut file) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+    {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+pub fn enabled(level: Level) -> bool {
+    max_level().map(|max| level <= max).unwrap_or(false)
+}
+
+/// Format a log line, e.g. `[ccline DEBUG core::statusline] git collected in 12ms`
+pub fn format_line(level: Level, target: &str, args: fmt::Arguments) -> String {
+    let target = target.strip_prefix("ccometixline::").unwrap_or(target);
+    format!("[ccline {} {}] {}", level.label(), target, args)
+}
+
+/// When this invocation started; set by [`start_clock`], else by the first log line
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+/// Mark the start of the invocation, so elapsed times cover all of it
+pub fn start_clock() {
+    STARTED.get_or_init(Instant::now);
+}
+
+/// Whether lines carry a timestamp and elapsed time (`CCLINE_LOG_TIME=0` turns them off)
+fn timestamps_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        !matches!(
+            std::env::var("CCLINE_LOG_TIME").as_deref(),
+            Ok("0") | Ok("false") | Ok("off")
+        )
+    })
+}
+
+/// Format a log line with its time, e.g.
+/// `2025-08-01T09:30:00.123+08:00 +12ms [ccline DEBUG core::statusline] git collected in 12ms`
+pub fn format_timed_line(
+    time: DateTime<Local>,
+    elapsed: Duration,
+    level: Level,
+    target: &str,
+    args: fmt::Arguments,
+) -> String {
+    format!(
+        "{} +{}ms {}",
+        time.to_rfc3339_opts(SecondsFormat::Millis, false),
+        elapsed.as_millis(),
+        format_line(level, target, args)
+    )
+}
+
+pub fn write(level: Level, target: &str, args: fmt::Arguments) {
+    let line = if timestamps_enabled() {
+        let elapsed = STARTED.get_or_init(Instant::now).elapsed();
+        format_timed_line(Local::now(), elapsed, level, target, args)
+    } else {
+        format_line(level, target, args)
+    };
+    match LOG_FILE.get() {
+        Some(path) => append_line(path, &line),
+        None => {
+            let _ = writeln!(std::io::stderr().lock(), "{}", line);
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)+) => {
+        if $crate::utils::log::enabled($level) {
+            $crate::utils::log::write($level, module_path!(), format_args!($($arg)+));
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! log_error {
+    ($($arg:tt)+) => { $crate::log_at!($crate::utils::log::Level::Error, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! log_warn {
+    ($($arg:tt)+) => { $crate::log_at!($crate::utils::log::Level::Warn, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! log_debug {
+    ($($arg:tt)+) => { $crate::log_at!($crate::utils::log::Level::Debug, $($arg)+) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{EnvGuard, TempDir};
+    use chrono::TimeZone;
+
+    #[test]
+    fn level_comes_from_ccline_log_then_rust_log() {
+        let mut env = EnvGuard::new();
+        env.set("CCLINE_LOG", "warn").set("RUST_LOG", "trace");
+        assert_eq!(level_from_env(false), Some(Level::Warn));
+
+        env.remove("CCLINE_LOG");
+        assert_eq!(level_from_env(false), Some(Level::Trace));
+    }
+
+    #[test]
+    fn logging_is_off_by_default_and_debug_with_a_log_file() {
+        let mut env = EnvGuard::new();
+        env.remove("CCLINE_LOG").remove("RUST_LOG");
+        assert_eq!(level_from_env(false), None);
+        assert_eq!(level_from_env(true), Some(Level::Debug));
+
+        // An explicit level still wins over the log file default
+        env.set("CCLINE_LOG", "error");
+        assert_eq!(level_from_env(true), Some(Level::Error));
+    }
+
+    #[test]
+    fn log_file_gets_each_line_appended() {
+        let mut env = EnvGuard::new();
+        env.set("CCLINE_LOG", "info");
+        let dir = TempDir::new("log");
+        let path = dir.join("ccline.log");
+
+        assert!(level_from_env(true).is_some_and(|max| Level::Info <= max));
+        let line = format_line(
+            Level::Info,
+            "ccometixline::core::statusline",
+            format_args!("rendered"),
+        );
+        append_line(&path, &line);
+        append_line(&path, &line);
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[ccline INFO core::statusline] rendered\n".repeat(2)
+        );
+
+        // An unwritable destination is ignored rather than failing
+        append_line(&dir.join("missing/ccline.log"), &line);
+    }
+
+    #[test]
+    fn levels_parse_case_insensitively() {
+        assert_eq!(Level::parse("DEBUG"), Some(Level::Debug));
+        assert_eq!(Level::parse(" warning "), Some(Level::Warn));
+        assert_eq!(Level::parse("verbose"), None);
+        assert!(Level::Error < Level::Trace);
+    }
+
+    #[test]
+    fn line_names_level_and_module() {
+        let line = format_line(
+            Level::Debug,
+            "ccometixline::core::statusline",
+            format_args!("{} collected in {}ms", "git", 12),
+        );
+        assert_eq!(
+            line,
+            "[ccline DEBUG core::statusline] git collected in 12ms"
+        );
+    }
+
+    #[test]
+    fn timed_line_starts_with_timestamp_and_elapsed() {
+        let time = Local.with_ymd_and_hms(2025, 8, 1, 9, 30, 0).unwrap();
+        let line = format_timed_line(
+            time,
+            Duration::from_millis(12),
+            Level::Warn,
+            "ccometixline::core::segments::newapi_cost",
+            format_args!("request failed"),
+        );
+
+        let (stamp, rest) = line.split_once(' ').unwrap();
+        assert_eq!(DateTime::parse_from_rfc3339(stamp).unwrap(), time);
+        assert_eq!(
+            rest,
+            "+12ms [ccline WARN core::segments::newapi_cost] request failed"
+        );
+    }
+}
@@ -1,4 +1,15 @@
+pub mod atomic;
+pub mod cache;
 pub mod claude_code_patcher;
 pub mod credentials;
+pub mod errors;
+pub mod locale;
+pub mod log;
+pub mod process;
+pub mod width;
 
-pub use claude_code_patcher::{ClaudeCodePatcher, LocationResult};
+pub use atomic::{replace_atomic, write_atomic};
+pub use cache::Cache;
+pub use claude_code_patcher::{ClaudeCodePatcher, LocationResult, PatchLock};
+pub use locale::Locale;
+pub use width::{display_width, truncate_to_width, TruncateStrategy};
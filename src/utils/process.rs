@@ -0,0 +1,114 @@
+use std::io;
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+/// Like `Command::output`, but kills the command once `timeout` has passed
+/// and fails with `ErrorKind::TimedOut`
+pub fn output_with_timeout(command: &mut Command, timeout: Duration) -> io::Result<Output> {
+    // Own process group, so a timeout also reaches what a shell started
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // The timer kills the command at the deadline unless it finished first
+    let (finished, done) = mpsc::channel::<()>();
+    let pid = child.id();
+    let timer = std::thread::spawn(move || match done.recv_timeout(timeout) {
+        Err(RecvTimeoutError::Timeout) => {
+            kill_tree(pid);
+            true
+        }
+        _ => false,
+    });
+
+    // Drains both pipes while blocking until the command exits
+    let output = child.wait_with_output();
+    let _ = finished.send(());
+
+    if timer.join().unwrap_or(false) {
+        return Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("command timed out after {}ms", timeout.as_millis()),
+        ));
+    }
+    output
+}
+
+/// Kill a command and everything it started
+fn kill_tree(pid: u32) {
+    #[cfg(unix)]
+    let mut kill = {
+        let mut kill = Command::new("kill");
+        kill.arg("-KILL").arg("--").arg(format!("-{}", pid));
+        kill
+    };
+
+    #[cfg(windows)]
+    let mut kill = {
+        let mut kill = Command::new("taskkill");
+        kill.arg("/F").arg("/T").arg("/PID").arg(pid.to_string());
+        kill
+    };
+
+    let _ = kill
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn sh(script: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(script);
+        command
+    }
+
+    #[test]
+    fn returns_the_output_of_a_quick_command() {
+        let output = output_with_timeout(
+            &mut sh("echo out; echo err >&2; exit 3"),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[test]
+    fn drains_output_larger_than_a_pipe_buffer() {
+        let script = "head -c 300000 /dev/zero; head -c 300000 /dev/zero >&2";
+        let output = output_with_timeout(&mut sh(script), Duration::from_secs(5)).unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout.len(), 300_000);
+        assert_eq!(output.stderr.len(), 300_000);
+    }
+
+    #[test]
+    fn times_out_and_kills_what_the_shell_started() {
+        let started = Instant::now();
+        // `sleep` runs as a child of `sh` and would keep the pipes open
+        let error = output_with_timeout(&mut sh("sleep 5; echo late"), Duration::from_millis(100))
+            .unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert!(
+            started.elapsed() < Duration::from_secs(2),
+            "{:?}",
+            started.elapsed()
+        );
+    }
+}
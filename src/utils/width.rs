@@ -0,0 +1,184 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Terminal display width of `text`, ignoring ANSI escape sequences
+///
+/// Widths follow the Unicode East Asian Width and emoji presentation tables,
+/// so CJK text, emoji (`✉️`, flags, ZWJ sequences) take two columns and
+/// combining marks take none. Use this for all layout math instead of
+/// counting chars.
+pub fn display_width(text: &str) -> usize {
+    strip_ansi(text).width()
+}
+
+/// Remove ANSI CSI escape sequences (colors, styles) from `text`
+pub fn strip_ansi(text: &str) -> String {
+    let mut visible = String::with_capacity(text.len());
+    let mut in_escape = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            // Start of ANSI escape sequence
+            in_escape = true;
+            // Skip the [ character
+            if chars.peek() == Some(&'[') {
+                chars.next();
+            }
+        } else if in_escape {
+            // Skip until we find the end of the escape sequence (letter)
+            if ch.is_alphabetic() {
+                in_escape = false;
+            }
+        } else {
+            visible.push(ch);
+        }
+    }
+
+    visible
+}
+
+/// Which part of an over-long text is replaced by `…`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TruncateStrategy {
+    /// Keep the tail, e.g. `…/deep/dir`
+    Start,
+    /// Keep both ends, e.g. `~/pro…/dir`
+    Middle,
+    /// Keep the head, e.g. `~/projects/…`
+    #[default]
+    End,
+}
+
+impl TruncateStrategy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "start" => Some(TruncateStrategy::Start),
+            "middle" => Some(TruncateStrategy::Middle),
+            "end" => Some(TruncateStrategy::End),
+            _ => None,
+        }
+    }
+}
+
+/// Shorten plain `text` to at most `max_width` columns, marking the cut with `…`
+/// Text that already fits is returned unchanged
+pub fn truncate_to_width(text: &str, max_width: usize, strategy: TruncateStrategy) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+
+    // Columns left for kept text once the ellipsis is placed
+    let budget = max_width - 1;
+    match strategy {
+        TruncateStrategy::End => format!("{}…", take_width(text.chars(), budget)),
+        TruncateStrategy::Start => format!("…{}", take_tail_width(text, budget)),
+        TruncateStrategy::Middle => {
+            let head_budget = budget.div_ceil(2);
+            format!(
+                "{}…{}",
+                take_width(text.chars(), head_budget),
+                take_tail_width(text, budget - head_budget)
+            )
+        }
+    }
+}
+
+/// Longest prefix of `chars` that fits in `budget` columns
+fn take_width(chars: impl Iterator<Item = char>, budget: usize) -> String {
+    let mut kept = String::new();
+    let mut used = 0;
+    for ch in chars {
+        let ch_width = ch.width().unwrap_or(0);
+        if used + ch_width > budget {
+            break;
+        }
+        used += ch_width;
+        kept.push(ch);
+    }
+    kept
+}
+
+/// Longest suffix of `text` that fits in `budget` columns
+fn take_tail_width(text: &str, budget: usize) -> String {
+    take_width(text.chars().rev(), budget)
+        .chars()
+        .rev()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_glyphs_take_two_columns() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("中文"), 4);
+        assert_eq!(display_width("🤖"), 2);
+        assert_eq!(display_width("✉\u{fe0f}"), 2);
+        assert_eq!(display_width("⚙\u{fe0f}"), 2);
+        assert_eq!(display_width("🇯🇵"), 2);
+    }
+
+    #[test]
+    fn combining_sequences_take_one_glyph_width() {
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width("👨\u{200d}👩\u{200d}👧"), 2);
+        assert_eq!(display_width("👍🏽"), 2);
+    }
+
+    #[test]
+    fn ansi_escapes_have_no_width() {
+        assert_eq!(display_width("\x1b[1;38;5;196m中\x1b[0m x"), 4);
+        assert_eq!(strip_ansi("\x1b[96mSonnet\x1b[0m"), "Sonnet");
+    }
+
+    const PATH: &str = "~/projects/ccline/src/core";
+
+    #[test]
+    fn each_strategy_keeps_its_part_of_a_path() {
+        assert_eq!(
+            truncate_to_width(PATH, 12, TruncateStrategy::End),
+            "~/projects/…"
+        );
+        assert_eq!(
+            truncate_to_width(PATH, 12, TruncateStrategy::Start),
+            "…ne/src/core"
+        );
+        assert_eq!(
+            truncate_to_width(PATH, 12, TruncateStrategy::Middle),
+            "~/proj…/core"
+        );
+    }
+
+    #[test]
+    fn truncation_respects_display_width() {
+        for strategy in [
+            TruncateStrategy::Start,
+            TruncateStrategy::Middle,
+            TruncateStrategy::End,
+        ] {
+            let truncated = truncate_to_width("中文中文中文", 5, strategy);
+            assert!(display_width(&truncated) <= 5, "{strategy:?}: {truncated}");
+            assert!(truncated.contains('…'));
+        }
+        assert_eq!(
+            truncate_to_width("中文中文中文", 5, TruncateStrategy::End),
+            "中文…"
+        );
+    }
+
+    #[test]
+    fn fitting_text_is_unchanged() {
+        assert_eq!(truncate_to_width(PATH, 40, TruncateStrategy::Start), PATH);
+        assert_eq!(truncate_to_width(PATH, 0, TruncateStrategy::End), "");
+        assert_eq!(
+            TruncateStrategy::parse("middle"),
+            Some(TruncateStrategy::Middle)
+        );
+        assert_eq!(TruncateStrategy::parse("left"), None);
+    }
+}
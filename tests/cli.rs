@@ -0,0 +1,976 @@
+//! End-to-end checks of the `ccline` binary, run with an isolated HOME
+
+#[allow(dead_code)]
+#[path = "../src/test_support/mock_http.rs"]
+mod mock_http;
+
+use mock_http::MockServer;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// Directory removed again on drop
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let path =
+            std::env::temp_dir().join(format!("ccline-cli-{}-{}-{}", name, std::process::id(), n));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+
+    fn write(&self, name: &str, contents: &str) -> PathBuf {
+        let path = self.0.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Statusline input for a workspace outside any git repository
+fn input(workspace: &Path) -> String {
+    serde_json::json!({
+        "model": {"id": "claude-sonnet-4", "display_name": "Sonnet 4"},
+        "workspace": {"current_dir": workspace},
+        "transcript_path": "",
+    })
+    .to_string()
+}
+
+/// Run ccline with HOME pointed at `home` and no config-related environment
+fn ccline(home: &TempDir, args: &[&str], stdin: &str) -> Output {
+    ccline_with_env(home, args, &[], stdin)
+}
+
+fn ccline_with_env(home: &TempDir, args: &[&str], env: &[(&str, &str)], stdin: &str) -> Output {
+    let mut command = ccline_command(home, args);
+    command.envs(env.iter().copied());
+    run(command, stdin)
+}
+
+/// The ccline command with HOME pointed at `home`, colors off and no
+/// config-related environment
+fn ccline_command(home: &TempDir, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_ccometixline"));
+    command
+        .args(args)
+        .env_remove("CCLINE_LOG")
+        .env_remove("RUST_LOG")
+        .env("HOME", home.path())
+        .env("NO_COLOR", "1")
+        .env_remove("CCLINE_CONFIG")
+        .env_remove("CCLINE_THEME")
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("XDG_CACHE_HOME")
+        .current_dir(home.path());
+    command
+}
+
+fn run(mut command: Command, stdin: &str) -> Output {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run ccline");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn no_config_ignores_the_config_file() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    let input = input(workspace.path());
+
+    let defaults = ccline(&home, &[], &input);
+    let config = stdout(&ccline(&home, &["--print"], ""));
+    home.write(
+        ".claude/ccline/config.toml",
+        &config.replace("separator = \" | \"", "separator = \" ~~ \""),
+    );
+    let configured = ccline(&home, &[], &input);
+    let no_config = ccline(&home, &["--no-config"], &input);
+
+    assert!(defaults.status.success());
+    assert_eq!(stdout(&no_config), stdout(&defaults));
+    assert_ne!(stdout(&configured), stdout(&defaults));
+}
+
+#[test]
+fn order_flag_moves_segments_to_the_front() {
+    let home = TempDir::new("home");
+    let output = ccline(&home, &["--print", "--order", "git,model"], "");
+    assert!(output.status.success());
+
+    let printed: toml::Value = toml::from_str(&stdout(&output)).unwrap();
+    let ids: Vec<&str> = printed["segments"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|segment| segment["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(&ids[..2], ["git", "model"]);
+
+    let output = ccline(&home, &["--print", "--order", "weather"], "");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("weather"));
+}
+
+/// NewApi stat response for a cost of 2.00 at the default quota_per_unit
+const STAT_BODY: &str = r#"{"success":true,"message":"","data":{"quota":1000000}}"#;
+
+#[test]
+fn newapi_flags_enable_the_disabled_default_segment() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    let server = MockServer::json(STAT_BODY);
+
+    let output = ccline(
+        &home,
+        &[
+            "--no-config",
+            "--newapi-base-url",
+            &server.url(),
+            "--newapi-user-token",
+            "sk-test",
+            "--newapi-user-id",
+            "42",
+        ],
+        &input(workspace.path()),
+    );
+
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("2.00"), "{}", stdout(&output));
+    let requests = server.requests();
+    assert!(requests
+        .iter()
+        .any(|r| r.path.starts_with("/api/log/self/stat")));
+}
+
+#[test]
+fn newapi_flags_create_the_segment_missing_from_config() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    let server = MockServer::json(STAT_BODY);
+
+    let mut config: toml::Value =
+        toml::from_str(&stdout(&ccline(&home, &["--print"], ""))).unwrap();
+    config["segments"]
+        .as_array_mut()
+        .unwrap()
+        .retain(|segment| segment["id"].as_str() != Some("new_api_cost"));
+    let config_path = home.write("config.toml", &toml::to_string(&config).unwrap());
+
+    let output = ccline(
+        &home,
+        &[
+            "--config-path",
+            config_path.to_str().unwrap(),
+            "--newapi-base-url",
+            &server.url(),
+            "--newapi-user-token",
+            "sk-test",
+            "--newapi-user-id",
+            "42",
+        ],
+        &input(workspace.path()),
+    );
+
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("2.00"), "{}", stdout(&output));
+}
+
+#[test]
+fn debug_logging_goes_to_stderr_only() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    let output = ccline_with_env(
+        &home,
+        &["--no-config"],
+        &[("CCLINE_LOG", "debug"), ("CCLINE_LOG_TIME", "0")],
+        &input(workspace.path()),
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr
+            .lines()
+            .any(|line| line.starts_with("[ccline DEBUG core::statusline] model collected in")),
+        "{stderr}"
+    );
+    assert!(!stdout(&output).contains("[ccline"));
+
+    // RUST_LOG is the fallback when CCLINE_LOG is unset
+    let fallback = ccline_with_env(
+        &home,
+        &["--no-config"],
+        &[("RUST_LOG", "debug"), ("CCLINE_LOG_TIME", "0")],
+        &input(workspace.path()),
+    );
+    assert!(String::from_utf8_lossy(&fallback.stderr).contains("[ccline DEBUG"));
+
+    let quiet = ccline(&home, &["--no-config"], &input(workspace.path()));
+    assert!(!String::from_utf8_lossy(&quiet.stderr).contains("[ccline"));
+}
+
+/// `--newapi-*` flags pointing the cost segment at a mock server
+fn newapi_flags(server: &MockServer) -> Vec<String> {
+    [
+        "--no-config",
+        "--newapi-base-url",
+        &server.url(),
+        "--newapi-user-token",
+        "sk-test",
+        "--newapi-user-id",
+        "42",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect()
+}
+
+fn args(flags: &[String], extra: &[&str]) -> Vec<String> {
+    flags
+        .iter()
+        .cloned()
+        .chain(extra.iter().map(|arg| arg.to_string()))
+        .collect()
+}
+
+fn ccline_args(home: &TempDir, args: &[String], stdin: &str) -> Output {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    ccline(home, &args, stdin)
+}
+
+#[test]
+fn bench_network_runs_the_requested_iterations() {
+    let home = TempDir::new("home");
+    let server = MockServer::json(STAT_BODY);
+
+    let output = ccline_args(
+        &home,
+        &args(&newapi_flags(&server), &["--bench-network", "3"]),
+        "",
+    );
+
+    assert!(output.status.success());
+    let stdout = stdout(&output);
+    assert_eq!(stdout.lines().filter(|l| l.starts_with('#')).count(), 3);
+    assert!(stdout.contains("3 requests:"), "{stdout}");
+    assert!(stdout.contains("0% failed"), "{stdout}");
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[test]
+fn bench_network_reports_failures() {
+    let home = TempDir::new("home");
+    let server = MockServer::start(vec![mock_http::MockResponse::new(500, "down")]);
+
+    let output = ccline_args(
+        &home,
+        &args(&newapi_flags(&server), &["--bench-network", "2"]),
+        "",
+    );
+
+    let stdout = stdout(&output);
+    assert!(stdout.contains("2 requests:"), "{stdout}");
+    assert!(stdout.contains("100% failed"), "{stdout}");
+}
+
+fn ping_report(output: &Output) -> serde_json::Value {
+    serde_json::from_str(stdout(output).trim()).expect("ping prints a JSON report")
+}
+
+fn ping_check<'a>(report: &'a serde_json::Value, name: &str) -> &'a serde_json::Value {
+    report["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|check| check["name"] == name)
+        .unwrap_or_else(|| panic!("no {name} check in {report}"))
+}
+
+#[test]
+fn ping_succeeds_against_a_healthy_api() {
+    let home = TempDir::new("home");
+    let server = MockServer::json(STAT_BODY);
+
+    let output = ccline_args(&home, &args(&newapi_flags(&server), &["--ping"]), "");
+
+    assert_eq!(output.status.code(), Some(0));
+    let report = ping_report(&output);
+    assert_eq!(report["ok"], true);
+    assert_eq!(ping_check(&report, "new_api_cost")["ok"], true);
+    assert_eq!(ping_check(&report, "render")["ok"], true);
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn ping_fails_when_the_api_is_down() {
+    let home = TempDir::new("home");
+    let server = MockServer::start(vec![mock_http::MockResponse::new(503, "down")]);
+
+    let output = ccline_args(&home, &args(&newapi_flags(&server), &["--ping"]), "");
+
+    assert_eq!(output.status.code(), Some(1));
+    let report = ping_report(&output);
+    assert_eq!(report["ok"], false);
+    let check = ping_check(&report, "new_api_cost");
+    assert_eq!(check["ok"], false);
+    assert_eq!(check["error"], "request failed");
+}
+
+#[test]
+fn init_on_an_existing_config_asks_for_force() {
+    let home = TempDir::new("home");
+    let path = home.path().join("config.toml");
+    std::fs::write(&path, "# mine\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = ccline(&home, &["--config-path", path, "--init"], "");
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("already exists"));
+    assert!(stdout(&output).contains("--init --force"));
+    assert_eq!(std::fs::read_to_string(path).unwrap(), "# mine\n");
+
+    let output = ccline(&home, &["--config-path", path, "--init", "--force"], "");
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("previous config saved"));
+    assert_eq!(
+        std::fs::read_to_string(format!("{path}.bak")).unwrap(),
+        "# mine\n"
+    );
+}
+
+#[test]
+fn init_with_ccline_config_writes_the_new_path() {
+    let home = TempDir::new("home");
+    let default = home.path().join(".claude/ccline/config.toml");
+    std::fs::create_dir_all(default.parent().unwrap()).unwrap();
+    std::fs::write(&default, "# default\n").unwrap();
+
+    let output = ccline_with_env(&home, &["--init"], &[("CCLINE_CONFIG", "new.toml")], "");
+
+    assert!(output.status.success());
+    assert!(
+        stdout(&output).contains("Created config"),
+        "{}",
+        stdout(&output)
+    );
+    assert!(home.path().join("new.toml").is_file());
+    assert_eq!(std::fs::read_to_string(&default).unwrap(), "# default\n");
+}
+
+fn stderr_json(output: &Output) -> Vec<serde_json::Value> {
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|_| panic!("not JSON: {line}")))
+        .collect()
+}
+
+#[test]
+fn json_errors_report_invalid_input() {
+    let home = TempDir::new("home");
+
+    let output = ccline(&home, &["--no-config", "--json-errors"], "not json");
+
+    assert_eq!(output.status.code(), Some(1));
+    let errors = stderr_json(&output);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["code"], "input_parse");
+    assert!(errors[0]["message"].is_string());
+    assert!(errors[0]["segment"].is_null());
+}
+
+#[test]
+fn json_errors_report_a_broken_config() {
+    let home = TempDir::new("home");
+    let path = home.path().join("config.toml");
+    std::fs::write(&path, "theme = [\n").unwrap();
+
+    let output = ccline(
+        &home,
+        &["--json-errors", "--config-path", path.to_str().unwrap()],
+        &input(home.path()),
+    );
+
+    let errors = stderr_json(&output);
+    assert_eq!(errors[0]["code"], "config_load");
+    assert!(
+        errors[0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("TOML parse error"),
+        "{errors:?}"
+    );
+}
+
+/// Write the default config with `title_format` set
+fn config_with_title(home: &TempDir, title_format: &str) {
+    let config = stdout(&ccline(home, &["--print", "--no-config"], ""));
+    home.write(
+        ".claude/ccline/config.toml",
+        &format!("title_format = {:?}\n{}", title_format, config),
+    );
+}
+
+#[test]
+fn title_escape_carries_the_templated_text() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    config_with_title(&home, "{model} - ccline");
+
+    let mut command = ccline_command(&home, &[]);
+    command.env_remove("NO_COLOR");
+    let output = run(command, &input(workspace.path()));
+
+    assert!(output.status.success());
+    assert!(
+        stdout(&output).starts_with("\x1b]0;Sonnet 4 - ccline\x07"),
+        "{:?}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn title_escape_is_omitted_when_unset_or_colorless() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    let input = input(workspace.path());
+
+    let mut command = ccline_command(&home, &[]);
+    command.env_remove("NO_COLOR");
+    assert!(!stdout(&run(command, &input)).contains("\x1b]0;"));
+
+    config_with_title(&home, "{model}");
+    assert!(!stdout(&ccline(&home, &[], &input)).contains("\x1b]0;"));
+    let mut command = ccline_command(&home, &["--no-color"]);
+    command.env_remove("NO_COLOR");
+    assert!(!stdout(&run(command, &input)).contains("\x1b]0;"));
+}
+
+#[test]
+fn segment_json_prints_one_segments_data() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+
+    let output = ccline(
+        &home,
+        &["--no-config", "--segment-json", "model"],
+        &input(workspace.path()),
+    );
+
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_str(stdout(&output).trim()).unwrap();
+    assert_eq!(json["primary"], "Sonnet 4");
+    assert!(json["secondary"].is_string());
+    assert!(json["metadata"].is_object(), "{json}");
+
+    // A segment with nothing to show prints null
+    let output = ccline(
+        &home,
+        &["--no-config", "--segment-json", "git"],
+        &input(workspace.path()),
+    );
+    assert_eq!(stdout(&output).trim(), "null");
+}
+
+#[test]
+fn validate_segment_explains_a_blank_segment() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+
+    let output = ccline(
+        &home,
+        &["--no-config", "--validate-segment", "new_api_cost"],
+        &input(workspace.path()),
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    let report = stdout(&output);
+    assert!(report.starts_with("Segment: new_api_cost\n"), "{report}");
+    assert!(report.contains("Config: disabled"), "{report}");
+    assert!(
+        report.contains("Missing options: base_url, user_token, user_id"),
+        "{report}"
+    );
+    assert!(report.contains("Result: nothing to show"), "{report}");
+}
+
+#[test]
+fn log_file_gets_timestamped_lines_instead_of_stderr() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    let log = home.path().join("ccline.log");
+
+    let output = ccline(
+        &home,
+        &["--no-config", "--log-file", log.to_str().unwrap()],
+        &input(workspace.path()),
+    );
+
+    assert!(output.status.success());
+    assert!(
+        output.stderr.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let contents = std::fs::read_to_string(&log).unwrap();
+    let line = contents
+        .lines()
+        .find(|line| line.contains("collected in"))
+        .unwrap_or_else(|| panic!("no collection line in {contents}"));
+
+    // `<ISO-8601 timestamp> +<elapsed>ms [ccline DEBUG ...] ...`
+    let mut parts = line.splitn(3, ' ');
+    let stamp = parts.next().unwrap();
+    let elapsed = parts.next().unwrap();
+    assert!(
+        chrono::DateTime::parse_from_rfc3339(stamp).is_ok(),
+        "{line}"
+    );
+    let elapsed_ms: u64 = elapsed
+        .strip_prefix('+')
+        .and_then(|ms| ms.strip_suffix("ms"))
+        .and_then(|ms| ms.parse().ok())
+        .unwrap_or_else(|| panic!("no elapsed time in {line}"));
+    assert!(elapsed_ms < 60_000);
+    assert!(
+        parts.next().unwrap().starts_with("[ccline DEBUG "),
+        "{line}"
+    );
+}
+
+#[test]
+fn init_example_input_matches_the_dry_run_sample() {
+    let home = TempDir::new("home");
+    let sample = home.path().join("sample.json");
+
+    let output = ccline(
+        &home,
+        &["--init-example-input", sample.to_str().unwrap()],
+        "",
+    );
+    assert!(output.status.success());
+    let written: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&sample).unwrap()).unwrap();
+    let home_dir = std::fs::canonicalize(home.path()).unwrap();
+    assert_eq!(
+        written["workspace"]["current_dir"],
+        home_dir.display().to_string()
+    );
+
+    // Piping the file renders exactly what --dry-run renders on its own
+    let piped = ccline(
+        &home,
+        &["--no-config"],
+        &std::fs::read_to_string(&sample).unwrap(),
+    );
+    let dry_run = ccline(&home, &["--no-config", "--dry-run"], "");
+    assert!(dry_run.status.success());
+    assert!(!stdout(&dry_run).trim().is_empty());
+    assert_eq!(stdout(&dry_run), stdout(&piped));
+}
+
+#[test]
+fn no_newline_prints_exactly_the_statusline() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    let input = input(workspace.path());
+
+    let default = ccline(&home, &[], &input);
+    assert!(default.stdout.ends_with(b"\n"));
+
+    let flag = ccline(&home, &["--no-newline"], &input);
+    assert!(flag.status.success());
+    assert!(!flag.stdout.ends_with(b"\n"));
+    assert_eq!(flag.stdout, default.stdout[..default.stdout.len() - 1]);
+
+    let config = stdout(&ccline(&home, &["--print"], ""));
+    home.write(
+        ".claude/ccline/config.toml",
+        &format!("no_newline = true\n{}", config),
+    );
+    let configured = ccline(&home, &[], &input);
+    assert_eq!(configured.stdout, flag.stdout);
+}
+
+#[test]
+fn replay_reproduces_the_recorded_output_byte_for_byte() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    let capture = TempDir::new("capture");
+    let server = MockServer::json(STAT_BODY);
+    let dir = capture.path().to_str().unwrap();
+
+    let recorded = ccline_args(
+        &home,
+        &args(&newapi_flags(&server), &["--record", dir]),
+        &input(workspace.path()),
+    );
+    assert!(recorded.status.success());
+    assert!(stdout(&recorded).contains("¥2.00"));
+    assert_eq!(
+        std::fs::read(capture.path().join("output.txt")).unwrap(),
+        recorded.stdout
+    );
+    let requests = server.requests().len();
+
+    // A fresh HOME has no cache, so the cost can only come from the capture
+    let elsewhere = TempDir::new("home");
+    let replayed = ccline(&elsewhere, &["--replay", dir], "");
+    assert!(replayed.status.success());
+    assert_eq!(replayed.stdout, recorded.stdout);
+    assert!(!String::from_utf8_lossy(&replayed.stderr).contains("differs"));
+    assert_eq!(server.requests().len(), requests);
+}
+
+#[test]
+fn record_masks_the_user_token_in_every_captured_file() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    let capture = TempDir::new("capture");
+    let server = MockServer::json(STAT_BODY);
+    let flags: Vec<String> = newapi_flags(&server)
+        .into_iter()
+        .map(|arg| arg.replace("sk-test", "sk-SECRET123"))
+        .collect();
+
+    let recorded = ccline_args(
+        &home,
+        &args(&flags, &["--record", capture.path().to_str().unwrap()]),
+        &input(workspace.path()),
+    );
+    assert!(recorded.status.success());
+
+    let mut files = 0;
+    for entry in std::fs::read_dir(capture.path()).unwrap() {
+        let path = entry.unwrap().path();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("sk-SECRET123"), "{}", path.display());
+        files += 1;
+    }
+    assert_eq!(files, 4);
+    let config = std::fs::read_to_string(capture.path().join("config.toml")).unwrap();
+    assert!(config.contains("user_token = \"[redacted]\""));
+
+    let replayed = ccline(
+        &TempDir::new("home"),
+        &["--replay", capture.path().to_str().unwrap()],
+        "",
+    );
+    assert_eq!(replayed.stdout, recorded.stdout);
+}
+
+/// Write the default config with every segment disabled plus `extra` settings
+fn config_with_nothing_shown(home: &TempDir, extra: &str) {
+    let config = stdout(&ccline(home, &["--print", "--no-config"], ""))
+        .replace("enabled = true", "enabled = false");
+    home.write(
+        ".claude/ccline/config.toml",
+        &format!("{}\n{}", extra, config),
+    );
+}
+
+#[test]
+fn empty_line_prints_placeholder_or_nothing() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    let input = input(workspace.path());
+
+    config_with_nothing_shown(&home, "");
+    assert_eq!(ccline(&home, &[], &input).stdout, b"\n");
+
+    config_with_nothing_shown(&home, "empty_placeholder = \"—\"");
+    assert_eq!(stdout(&ccline(&home, &[], &input)), "—\n");
+
+    config_with_nothing_shown(&home, "quiet_on_empty = true");
+    let quiet = ccline(&home, &[], &input);
+    assert!(quiet.status.success());
+    assert!(quiet.stdout.is_empty());
+
+    config_with_nothing_shown(&home, "quiet_on_empty = true\nempty_placeholder = \"—\"");
+    let check = ccline(&home, &["--check"], "");
+    assert!(!check.status.success());
+    assert!(String::from_utf8_lossy(&check.stderr).contains("mutually exclusive"));
+}
+
+#[test]
+fn patch_dry_run_reports_each_patch_and_writes_nothing() {
+    let home = TempDir::new("home");
+    let cli_js = "createElement(Xy,{mode:a,spinnerTip:t,verbose:v,overrideMessage:m,key:k});";
+    let target = home.write("cli.js", cli_js);
+    let target_arg = target.to_str().unwrap();
+
+    let output = ccline(&home, &["--patch", target_arg, "--dry-run"], "");
+    assert!(output.status.success());
+    let report = stdout(&output);
+    assert!(report.contains("✅ Verbose property: found"), "{}", report);
+    assert!(
+        report.contains("❌ Context low warnings: not found"),
+        "{}",
+        report
+    );
+    assert!(
+        report.contains("❌ ESC interrupt display: not found"),
+        "{}",
+        report
+    );
+
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), cli_js);
+    let files: Vec<_> = std::fs::read_dir(home.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .filter(|name| name.to_string_lossy().starts_with("cli.js"))
+        .collect();
+    assert_eq!(files, ["cli.js"]);
+}
+
+#[test]
+fn no_color_and_no_color_env_print_plain_text() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    let input = input(workspace.path());
+
+    let mut colored = ccline_command(&home, &[]);
+    colored.env_remove("NO_COLOR");
+    let colored = stdout(&run(colored, &input));
+    assert!(colored.contains('\x1b'), "{:?}", colored);
+
+    let mut flag = ccline_command(&home, &["--no-color"]);
+    flag.env_remove("NO_COLOR");
+    let flag = stdout(&run(flag, &input));
+    assert!(!flag.contains('\x1b'), "{:?}", flag);
+
+    assert_eq!(stdout(&ccline(&home, &[], &input)), flag);
+}
+
+#[test]
+fn check_warns_about_missing_newapi_options() {
+    let home = TempDir::new("home");
+    let config = stdout(&ccline(&home, &["--print", "--no-config"], ""));
+    let mut config: toml::Value = toml::from_str(&config).unwrap();
+    for segment in config["segments"].as_array_mut().unwrap() {
+        if segment["id"].as_str() == Some("new_api_cost") {
+            segment["enabled"] = toml::Value::Boolean(true);
+            let options = segment["options"].as_table_mut().unwrap();
+            options.insert("base_url".into(), "https://api.example.com".into());
+            options.remove("user_token");
+            options.remove("user_id");
+        }
+    }
+    home.write(
+        ".claude/ccline/config.toml",
+        &toml::to_string(&config).unwrap(),
+    );
+
+    let output = ccline(&home, &["--check"], "");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(
+        stderr.contains("\"new_api_cost\" is enabled but missing user_token, user_id"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn cache_status_and_clear_use_the_resolved_directory() {
+    let home = TempDir::new("home");
+    let default_dir = home.path().join(".cache/ccometixline");
+
+    let status = stdout(&ccline(&home, &["--cache-status"], ""));
+    assert!(
+        status.starts_with(&format!("Cache directory: {}", default_dir.display())),
+        "{}",
+        status
+    );
+
+    let config = stdout(&ccline(&home, &["--print", "--no-config"], ""));
+    home.write(
+        ".claude/ccline/config.toml",
+        &format!("cache_dir = \"~/my-cache\"\n{}", config),
+    );
+    home.write("my-cache/git.json", "{}");
+    let status = stdout(&ccline(&home, &["--cache-status"], ""));
+    let configured = home.path().join("my-cache");
+    assert!(
+        status.starts_with(&format!("Cache directory: {}", configured.display())),
+        "{}",
+        status
+    );
+    assert!(status.contains("git.json"), "{}", status);
+
+    let cleared = ccline(&home, &["--cache-clear"], "");
+    assert!(cleared.status.success());
+    assert!(stdout(&cleared).starts_with("Removed 1 cache files"));
+    assert!(!configured.join("git.json").exists());
+}
+
+#[test]
+fn last_input_is_kept_only_when_opted_in() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    let input = input(workspace.path());
+    let last_input = home.path().join(".cache/ccometixline/last_input.json");
+
+    assert!(ccline(&home, &[], &input).status.success());
+    assert!(!last_input.exists());
+
+    let config = stdout(&ccline(&home, &["--print", "--no-config"], ""));
+    home.write(
+        ".claude/ccline/config.toml",
+        &format!("preview_real_input = true\n{}", config),
+    );
+    assert!(ccline(&home, &[], &input).status.success());
+    assert_eq!(std::fs::read_to_string(&last_input).unwrap(), input);
+}
+
+#[test]
+fn doctor_reports_the_picked_config_file() {
+    let home = TempDir::new("home");
+    let home_dir = std::fs::canonicalize(home.path()).unwrap();
+
+    let report = stdout(&ccline(&home, &["--doctor"], ""));
+    assert!(
+        report.contains("(default location, not created yet)"),
+        "{}",
+        report
+    );
+    assert!(report.contains(".ccline.toml (missing)"), "{}", report);
+
+    let config = stdout(&ccline(&home, &["--print", "--no-config"], ""));
+    home.write(".ccline.toml", &config);
+    let report = stdout(&ccline(&home, &["--doctor"], ""));
+    assert!(
+        report.starts_with(&format!(
+            "Using config: {} (search path)",
+            home_dir.join(".ccline.toml").display()
+        )),
+        "{}",
+        report
+    );
+    assert!(report.contains(".ccline.toml (used)"), "{}", report);
+
+    let elsewhere = home.write("elsewhere.toml", &config);
+    let report = stdout(&ccline_with_env(
+        &home,
+        &["--doctor"],
+        &[("CCLINE_CONFIG", elsewhere.to_str().unwrap())],
+        "",
+    ));
+    assert!(
+        report.starts_with(&format!(
+            "Using config: {} (CCLINE_CONFIG)",
+            home_dir.join("elsewhere.toml").display()
+        )),
+        "{}",
+        report
+    );
+    assert!(report.contains(".ccline.toml (found)"), "{}", report);
+}
+
+#[test]
+fn closed_stdout_exits_quietly() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+
+    let mut child = ccline_command(&home, &["--no-config"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run ccline");
+    // The reader goes away before ccline has read its input
+    drop(child.stdout.take());
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input(workspace.path()).as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success(), "{:?}", output.status);
+    assert!(
+        output.stderr.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn checkpoint_cost_is_recorded_until_cleared() {
+    let home = TempDir::new("home");
+    let server = MockServer::json(STAT_BODY);
+    let flags = newapi_flags(&server);
+
+    let set = ccline_args(&home, &args(&flags, &["--checkpoint-cost"]), "");
+    assert!(
+        set.status.success(),
+        "{}",
+        String::from_utf8_lossy(&set.stderr)
+    );
+    assert_eq!(stdout(&set).trim(), "Checkpoint set at ¥2.00");
+
+    let cleared = ccline_args(&home, &args(&flags, &["--clear-checkpoint"]), "");
+    assert_eq!(stdout(&cleared).trim(), "Checkpoint cleared");
+    let again = ccline_args(&home, &args(&flags, &["--clear-checkpoint"]), "");
+    assert_eq!(stdout(&again).trim(), "No checkpoint set");
+}
+
+#[test]
+fn min_render_interval_reprints_the_cached_line() {
+    let home = TempDir::new("home");
+    let workspace = TempDir::new("workspace");
+    let input = input(workspace.path());
+    let server = MockServer::json(STAT_BODY);
+
+    let mut config: toml::Value =
+        toml::from_str(&stdout(&ccline(&home, &["--print", "--no-config"], ""))).unwrap();
+    config.as_table_mut().unwrap().insert(
+        "min_render_interval_ms".into(),
+        toml::Value::Integer(60_000),
+    );
+    let config_path = home.write("config.toml", &toml::to_string(&config).unwrap());
+    let flags: Vec<String> = newapi_flags(&server)
+        .into_iter()
+        .filter(|flag| flag != "--no-config")
+        .collect();
+    let flags = args(&flags, &["--config-path", config_path.to_str().unwrap()]);
+
+    let first = ccline_args(&home, &flags, &input);
+    let second = ccline_args(&home, &flags, &input);
+    assert!(stdout(&first).contains("2.00"), "{}", stdout(&first));
+    assert_eq!(first.stdout, second.stdout);
+    // Only the first run fetched the cost
+    assert_eq!(server.requests().len(), 1);
+
+    // Another theme is another config, so it renders fresh
+    let themed = ccline_args(&home, &args(&flags, &["--theme", "nord"]), &input);
+    assert!(themed.status.success());
+    assert_eq!(server.requests().len(), 2);
+}